@@ -0,0 +1,145 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ecvrf::{
+    helper::randomize,
+    secp256k1::{
+        curve::{Affine, Scalar, AFFINE_G},
+        SecretKey,
+    },
+    ECVRF,
+};
+
+// Fixed secret key so runs are comparable
+const SECRET_KEY: [u8; 32] = [
+    0x1e, 0x99, 0x42, 0x3a, 0x4e, 0xd2, 0x76, 0x08, 0xa1, 0x5a, 0x26, 0x16, 0xa2, 0xb0, 0xe9, 0xe5,
+    0x2c, 0xed, 0x33, 0x0a, 0xc5, 0x30, 0xed, 0xcc, 0x32, 0xc8, 0xff, 0xc6, 0xa5, 0x26, 0xae, 0xdd,
+];
+
+fn ecvrf_instance() -> ECVRF<'static> {
+    ECVRF::new(SecretKey::parse(&SECRET_KEY).unwrap())
+}
+
+// Fixed alpha, a proof depends on it through hash_to_curve
+fn fixed_alpha() -> Scalar {
+    let mut alpha = Scalar::default();
+    alpha.set_int(0x6f72_616e);
+    alpha
+}
+
+fn bench_prove(c: &mut Criterion) {
+    let ecvrf = ecvrf_instance();
+    let alpha = fixed_alpha();
+    c.bench_function("prove", |b| {
+        b.iter(|| ecvrf.prove(black_box(&alpha)).unwrap())
+    });
+}
+
+fn bench_verify(c: &mut Criterion) {
+    let ecvrf = ecvrf_instance();
+    let alpha = fixed_alpha();
+    let proof = ecvrf.prove(&alpha).unwrap();
+    c.bench_function("verify", |b| {
+        b.iter(|| ecvrf.verify(black_box(&alpha), black_box(&proof)).unwrap())
+    });
+}
+
+fn bench_hash_to_curve(c: &mut Criterion) {
+    let ecvrf = ecvrf_instance();
+    let alpha = fixed_alpha();
+    let pk: Affine = ecvrf.prove(&alpha).unwrap().public_key().into();
+    c.bench_function("hash_to_curve", |b| {
+        b.iter(|| ecvrf.hash_to_curve(black_box(&alpha), Some(black_box(&pk))))
+    });
+}
+
+fn bench_hash_points(c: &mut Criterion) {
+    let ecvrf = ecvrf_instance();
+    let alpha = fixed_alpha();
+    let proof = ecvrf.prove(&alpha).unwrap();
+    let pk: Affine = proof.public_key().into();
+    let h = ecvrf.hash_to_curve(&alpha, Some(&pk));
+    c.bench_function("hash_points", |b| {
+        b.iter(|| {
+            ecvrf.hash_points(
+                black_box(&AFFINE_G),
+                black_box(&h),
+                black_box(&pk),
+                black_box(&proof.gamma),
+                black_box(&AFFINE_G),
+                black_box(&h),
+            )
+        })
+    });
+}
+
+fn bench_prove_and_verify(c: &mut Criterion) {
+    let ecvrf = ecvrf_instance();
+    let alpha = fixed_alpha();
+    c.bench_function("prove + verify", |b| {
+        b.iter(|| {
+            let proof = ecvrf.prove(black_box(&alpha)).unwrap();
+            ecvrf.verify(&alpha, &proof).unwrap()
+        })
+    });
+}
+
+fn bench_verify_batch(c: &mut Criterion) {
+    let ecvrf = ecvrf_instance();
+    let items: Vec<_> = (0..256)
+        .map(|_| {
+            let alpha: Scalar = randomize();
+            (alpha, ecvrf.prove(&alpha).unwrap())
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("verify 256 proofs");
+    group.sample_size(10);
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            items
+                .iter()
+                .map(|(alpha, proof)| ecvrf.verify(alpha, proof).unwrap())
+                .collect::<Vec<bool>>()
+        })
+    });
+    group.bench_function("batch", |b| {
+        b.iter(|| ecvrf.verify_batch(black_box(&items)))
+    });
+    group.finish();
+}
+
+// cargo bench --features parallel
+#[cfg(feature = "parallel")]
+fn bench_prove_many(c: &mut Criterion) {
+    let ecvrf = ecvrf_instance();
+    let alphas: Vec<Scalar> = (0..1000).map(|_| randomize()).collect();
+
+    let mut group = c.benchmark_group("prove 1000 alphas");
+    group.sample_size(10);
+    group.bench_function("serial", |b| {
+        b.iter(|| {
+            alphas
+                .iter()
+                .map(|alpha| ecvrf.prove(alpha).unwrap())
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("parallel", |b| {
+        b.iter(|| ecvrf.prove_many(black_box(&alphas)).unwrap())
+    });
+    group.finish();
+}
+
+#[cfg(not(feature = "parallel"))]
+fn bench_prove_many(_: &mut Criterion) {}
+
+criterion_group!(
+    benches,
+    bench_prove,
+    bench_verify,
+    bench_hash_to_curve,
+    bench_hash_points,
+    bench_prove_and_verify,
+    bench_verify_batch,
+    bench_prove_many
+);
+criterion_main!(benches);
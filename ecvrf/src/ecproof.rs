@@ -1,15 +1,46 @@
+use crate::{
+    helper::{
+        address_to_scalar, affine_from_compressed, affine_to_compressed, calculate_witness_address,
+        ecmult, get_address, jacobian_to_affine, projective_ec_add, scalar_from_b32,
+    },
+    HashMode, SchemeVersion, Verifier, VrfError, ECVRF,
+};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
 use libsecp256k1::{
-    curve::{Affine, Field, Scalar},
-    PublicKey,
+    curve::{Affine, Field, Jacobian, Scalar},
+    util::COMPRESSED_PUBLIC_KEY_SIZE,
+    PublicKey, ECMULT_CONTEXT,
 };
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use tiny_keccak::{Hasher, Keccak};
 
-#[derive(Clone, Copy, Debug)]
+// verifyProof() of the on-chain verifier, see contracts/interfaces/IOrandECVRF.sol
+pub const VERIFY_PROOF_SIGNATURE: &str =
+    "verifyProof(uint256[2],uint256[2],uint256,uint256,uint256,address,uint256[2],uint256[2],uint256)";
+
+// Size of a proof in wire format:
+// gamma (33) || c (32) || s (32) || y (32) || public key (33)
+pub const PROOF_SIZE: usize = 162;
+
+// Proofs of other hash modes than keccak256 end with a hash mode tag,
+// keccak256 proofs are kept in the original format
+pub const TAGGED_PROOF_SIZE: usize = PROOF_SIZE + 1;
+const HASH_MODE_SHA256: u8 = 1;
+const HASH_MODE_KECCAK256_COMPRESSED: u8 = 2;
+
+#[derive(Clone, Copy)]
 pub struct ECVRFProof {
     pub gamma: Affine,
     pub c: Scalar,
     pub s: Scalar,
     pub y: Scalar,
     pub pk: PublicKey,
+    // Hash function of c and y
+    pub hash_mode: HashMode,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -28,24 +59,917 @@ pub struct ECVRFContractProof {
 
 impl ECVRFProof {
     pub fn new(gamma: Affine, c: Scalar, s: Scalar, y: Scalar, pk: PublicKey) -> Self {
-        Self { gamma, c, s, y, pk }
-    }
-}
-
-impl ToString for ECVRFProof {
-    fn to_string(&self) -> String {
-        let mut pub_affine: Affine = self.pk.into();
-        pub_affine.x.normalize();
-        pub_affine.y.normalize();
-        format!(
-            "gamma: x: 0x{}\n > y: 0x{}\nc: 0x{}\ns: 0x{}\ny: 0x{}\npublic key:\n > x: {}\n > y: {}\n",
-            hex::encode(self.gamma.x.b32()),
-            hex::encode(self.gamma.y.b32()),
-            hex::encode(self.c.b32()),
-            hex::encode(self.s.b32()),
-            hex::encode(self.y.b32()),
-            hex::encode(pub_affine.x.b32()),
-            hex::encode(pub_affine.y.b32())
+        Self::new_with_hash(gamma, c, s, y, pk, HashMode::Keccak256)
+    }
+
+    pub fn new_with_hash(
+        gamma: Affine,
+        c: Scalar,
+        s: Scalar,
+        y: Scalar,
+        pk: PublicKey,
+        hash_mode: HashMode,
+    ) -> Self {
+        Self {
+            gamma,
+            c,
+            s,
+            y,
+            pk,
+            hash_mode,
+        }
+    }
+
+    // Public key that was used to create this proof
+    pub fn public_key(&self) -> PublicKey {
+        self.pk
+    }
+
+    // Verify this proof, the embedded public key must be the expected one
+    // otherwise a valid proof of an attacker's key would be accepted
+    // It's verified with the recorded hash mode, use a verifier to require a mode
+    pub fn verify_with_pubkey(
+        &self,
+        alpha: &Scalar,
+        expected_pk: &PublicKey,
+    ) -> Result<bool, VrfError> {
+        self.verify_with_pubkey_and_version(alpha, expected_pk, SchemeVersion::CURRENT)
+    }
+
+    // Verify this proof with the given scheme version, e.g. V1 for epochs created before
+    // domain separation
+    pub fn verify_with_pubkey_and_version(
+        &self,
+        alpha: &Scalar,
+        expected_pk: &PublicKey,
+        version: SchemeVersion,
+    ) -> Result<bool, VrfError> {
+        Verifier::new_with_version(version).verify(expected_pk, alpha, self)
+    }
+
+    // Verify a proof with its embedded public key and the static contexts, no instance is needed
+    // It only shows the proof is consistent, check proof.public_key() if the signer matters
+    pub fn verify_standalone(alpha: &Scalar, proof: &ECVRFProof) -> Result<bool, VrfError> {
+        Self::verify_standalone_with_version(alpha, proof, SchemeVersion::CURRENT)
+    }
+
+    // Verify a proof with its embedded public key and the given scheme version
+    pub fn verify_standalone_with_version(
+        alpha: &Scalar,
+        proof: &ECVRFProof,
+        version: SchemeVersion,
+    ) -> Result<bool, VrfError> {
+        Verifier::new_with_version(version).verify(&proof.pk, alpha, proof)
+    }
+
+    // Alpha of the next epoch, the randomness of this epoch feeds the next one
+    pub fn next_alpha(&self) -> Scalar {
+        self.y
+    }
+
+    // VRF output as a big-endian uint256
+    pub fn random_u256(&self) -> [u8; 32] {
+        self.y.b32()
+    }
+
+    // VRF output reduced to [0, modulus), both are big-endian uint256
+    // It's floor(y * modulus / 2^256) instead of y % modulus, each output is hit by
+    // floor(2^256 / modulus) or ceil(2^256 / modulus) values of y so the bias is at most
+    // modulus / 2^256. y is a scalar below the group order (about 2^256 - 2^128.3), which
+    // adds at most 2^-127 to the statistical distance from uniform.
+    // Zero modulus means the full range of uint256, y is returned as is.
+    pub fn random_in_range(&self, modulus: &[u8; 32]) -> [u8; 32] {
+        if modulus.iter().all(|b| *b == 0) {
+            return self.random_u256();
+        }
+        let product = mul_wide(&self.random_u256(), modulus);
+        product[0..32].try_into().unwrap()
+    }
+
+    // Encode proof to its wire format, the hash mode isn't recorded so it decodes as a
+    // keccak256 proof, use to_tagged_bytes() for proofs of other modes
+    pub fn to_bytes(&self) -> [u8; PROOF_SIZE] {
+        let mut buf = [0u8; PROOF_SIZE];
+        buf[0..33].copy_from_slice(&affine_to_compressed(&self.gamma));
+        buf[33..65].copy_from_slice(&self.c.b32());
+        buf[65..97].copy_from_slice(&self.s.b32());
+        buf[97..129].copy_from_slice(&self.y.b32());
+        buf[129..PROOF_SIZE].copy_from_slice(&self.pk.serialize_compressed());
+        buf
+    }
+
+    // Encode proof to its wire format with the hash mode, it's PROOF_SIZE bytes for keccak256
+    // proofs like to_bytes() and TAGGED_PROOF_SIZE bytes otherwise
+    pub fn to_tagged_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(TAGGED_PROOF_SIZE);
+        buf.extend_from_slice(&self.to_bytes());
+        match self.hash_mode {
+            HashMode::Keccak256 => {}
+            HashMode::Sha256 => buf.push(HASH_MODE_SHA256),
+            HashMode::Keccak256Compressed => buf.push(HASH_MODE_KECCAK256_COMPRESSED),
+        }
+        buf
+    }
+
+    // Decode proof from its wire format, gamma and public key must be valid points
+    pub fn from_bytes(buf: &[u8]) -> Result<ECVRFProof, VrfError> {
+        let hash_mode = match buf.len() {
+            PROOF_SIZE => HashMode::Keccak256,
+            TAGGED_PROOF_SIZE => match buf[PROOF_SIZE] {
+                HASH_MODE_SHA256 => HashMode::Sha256,
+                HASH_MODE_KECCAK256_COMPRESSED => HashMode::Keccak256Compressed,
+                _ => return Err(VrfError::InvalidHashMode),
+            },
+            _ => return Err(VrfError::InvalidLength),
+        };
+        Ok(ECVRFProof {
+            gamma: affine_from_compressed(&buf[0..33])?,
+            c: scalar_from_slice(&buf[33..65])?,
+            s: scalar_from_slice(&buf[65..97])?,
+            y: scalar_from_slice(&buf[97..129])?,
+            pk: public_key_from_compressed(&buf[129..PROOF_SIZE])?,
+            hash_mode,
+        })
+    }
+
+    // Encode proof to a CBOR map of its wire format fields as byte strings, keys are the JSON
+    // names and hash_mode is only encoded if it isn't keccak256, like in JSON
+    #[cfg(feature = "cbor")]
+    pub fn to_cbor(&self) -> Vec<u8> {
+        use ciborium::Value;
+        let field = |name: &str, bytes: &[u8]| {
+            (Value::Text(name.to_string()), Value::Bytes(bytes.to_vec()))
+        };
+        let mut map = vec![
+            field("gamma", &affine_to_compressed(&self.gamma)),
+            field("c", &self.c.b32()),
+            field("s", &self.s.b32()),
+            field("y", &self.y.b32()),
+            field("public_key", &self.pk.serialize_compressed()),
+        ];
+        let hash_mode = match self.hash_mode {
+            HashMode::Keccak256 => None,
+            HashMode::Sha256 => Some("sha256"),
+            HashMode::Keccak256Compressed => Some("keccak256_compressed"),
+        };
+        if let Some(hash_mode) = hash_mode {
+            map.push((
+                Value::Text("hash_mode".to_string()),
+                Value::Text(hash_mode.to_string()),
+            ));
+        }
+        let mut buf = Vec::with_capacity(TAGGED_PROOF_SIZE + 48);
+        ciborium::into_writer(&Value::Map(map), &mut buf).expect("CBOR is written to memory");
+        buf
+    }
+
+    // Decode proof from the CBOR map of to_cbor(), gamma and public key must be valid points
+    #[cfg(feature = "cbor")]
+    pub fn from_cbor(buf: &[u8]) -> Result<ECVRFProof, VrfError> {
+        use ciborium::Value;
+        let map = match ciborium::from_reader::<Value, _>(buf) {
+            Ok(Value::Map(map)) => map,
+            _ => return Err(VrfError::InvalidEncoding),
+        };
+        let field = |name: &str| {
+            map.iter().find_map(|(key, value)| match key {
+                Value::Text(key) if key == name => Some(value),
+                _ => None,
+            })
+        };
+        let bytes = |name: &str| match field(name) {
+            Some(Value::Bytes(bytes)) => Ok(bytes.as_slice()),
+            _ => Err(VrfError::InvalidEncoding),
+        };
+        let hash_mode = match field("hash_mode") {
+            None => HashMode::Keccak256,
+            Some(Value::Text(mode)) => match mode.as_str() {
+                "keccak256" => HashMode::Keccak256,
+                "sha256" => HashMode::Sha256,
+                "keccak256_compressed" => HashMode::Keccak256Compressed,
+                _ => return Err(VrfError::InvalidHashMode),
+            },
+            Some(_) => return Err(VrfError::InvalidEncoding),
+        };
+        Ok(ECVRFProof {
+            gamma: affine_from_compressed(bytes("gamma")?)?,
+            c: scalar_from_slice(bytes("c")?)?,
+            s: scalar_from_slice(bytes("s")?)?,
+            y: scalar_from_slice(bytes("y")?)?,
+            pk: public_key_from_compressed(bytes("public_key")?)?,
+            hash_mode,
+        })
+    }
+
+    // Beacon of drand's HTTP API so drand clients can read epochs: round is the epoch,
+    // randomness is y, signature is the proof in wire format and previous_signature the one
+    // of the previous epoch, null if there is none. Every field is hex without 0x like drand
+    // Epochs are chained through y rather than the signature, clients must not verify it
+    // as a BLS signature
+    #[cfg(feature = "drand")]
+    pub fn to_drand_json(&self, round: u64, previous: Option<&ECVRFProof>) -> serde_json::Value {
+        serde_json::json!({
+            "round": round,
+            "randomness": hex::encode(self.y.b32()),
+            "signature": hex::encode(self.to_bytes()),
+            "previous_signature": previous.map(|proof| hex::encode(proof.to_bytes())),
+        })
+    }
+
+    // Decode a proof in the JSON of the JS SDK and the Solidity verifier: points are [x, y],
+    // {"x", "y"} or SEC1 hex, uint256 are 0x hex or decimal strings like ethers emits them
+    // Public key is public_key or pk, y is keccak256 of gamma like on-chain if it's missing
+    // Witnesses and seed are ignored, they're recomputed by ECVRF::verify_contract()
+    #[cfg(feature = "std")]
+    pub fn from_orand_json(value: &serde_json::Value) -> Result<ECVRFProof, VrfError> {
+        let field = |name: &str| value.get(name).ok_or(VrfError::InvalidEncoding);
+        let gamma = json_point(field("gamma")?).map_err(|_| VrfError::InvalidGamma)?;
+        let public_key = json_point(
+            value
+                .get("public_key")
+                .or(value.get("pk"))
+                .ok_or(VrfError::InvalidEncoding)?,
         )
+        .and_then(|point| {
+            let mut uncompressed = [0u8; 65];
+            uncompressed[0] = 0x04;
+            uncompressed[1..33].copy_from_slice(&point.x.b32());
+            uncompressed[33..].copy_from_slice(&point.y.b32());
+            PublicKey::parse(&uncompressed).map_err(|_| VrfError::InvalidPublicKey)
+        })
+        .map_err(|_| VrfError::InvalidPublicKey)?;
+        let y = match value.get("y") {
+            Some(y) => json_scalar(y)?,
+            None => crate::helper::keccak256_affine_scalar(&gamma)?,
+        };
+        Ok(ECVRFProof {
+            gamma,
+            c: json_scalar(field("c")?)?,
+            s: json_scalar(field("s")?)?,
+            y,
+            pk: public_key,
+            hash_mode: HashMode::Keccak256,
+        })
+    }
+
+    // Rebuild the witnesses of the on-chain verifier, the proof must be created by prove_contract
+    // so it's always a keccak256 proof
+    pub fn to_contract_proof(&self, alpha: &Scalar) -> ECVRFContractProof {
+        let vrf = ECVRF::new_verifier(self.pk);
+        let mut pk: Affine = self.pk.into();
+        pk.x.normalize();
+        pk.y.normalize();
+        let mut gamma = self.gamma;
+        gamma.x.normalize();
+        gamma.y.normalize();
+        let h = vrf.hash_to_curve_prefix(alpha, &pk);
+
+        // U = c * pk + s * G = k * G
+        let mut u = Jacobian::default();
+        ECMULT_CONTEXT.ecmult(&mut u, &Jacobian::from_ge(&pk), &self.c, &self.s);
+        let u_witness = calculate_witness_address(&jacobian_to_affine(&u));
+
+        // witness_gamma = c * gamma, witness_hash = s * H
+        let witness_gamma = ecmult(&ECMULT_CONTEXT, &gamma, &self.c);
+        let witness_hash = ecmult(&ECMULT_CONTEXT, &h, &self.s);
+        let v = projective_ec_add(&witness_gamma, &witness_hash);
+        let mut inverse_z = v.z.inv();
+        inverse_z.normalize();
+
+        ECVRFContractProof {
+            pk: self.pk,
+            gamma,
+            c: self.c,
+            s: self.s,
+            y: self.y,
+            alpha: *alpha,
+            witness_address: address_to_scalar(&u_witness),
+            witness_gamma,
+            witness_hash,
+            inverse_z,
+        }
+    }
+
+    // ABI encoded call of verifyProof() of the on-chain verifier
+    pub fn to_eth_calldata(&self, alpha: &Scalar) -> Vec<u8> {
+        self.to_contract_proof(alpha).to_eth_calldata()
+    }
+
+    // Ethereum address of the public key, it identifies the oracle on-chain
+    pub fn eth_address(&self) -> [u8; 20] {
+        get_address(self.pk)
+    }
+}
+
+impl ECVRFContractProof {
+    // ABI encoded call of verifyProof(pk, gamma, c, s, alpha, uWitness, cGammaWitness,
+    // sHashWitness, zInv), every param is static so each one is a 32 bytes word
+    pub fn to_eth_calldata(&self) -> Vec<u8> {
+        let mut pk: Affine = self.pk.into();
+        pk.x.normalize();
+        pk.y.normalize();
+        let mut selector = [0u8; 32];
+        let mut hasher = Keccak::v256();
+        hasher.update(VERIFY_PROOF_SIGNATURE.as_bytes());
+        hasher.finalize(&mut selector);
+
+        let words = [
+            pk.x.b32(),
+            pk.y.b32(),
+            self.gamma.x.b32(),
+            self.gamma.y.b32(),
+            self.c.b32(),
+            self.s.b32(),
+            self.alpha.b32(),
+            // Address is left padded with zeros, it's the same as the scalar form
+            self.witness_address.b32(),
+            self.witness_gamma.x.b32(),
+            self.witness_gamma.y.b32(),
+            self.witness_hash.x.b32(),
+            self.witness_hash.y.b32(),
+            self.inverse_z.b32(),
+        ];
+        let mut calldata = Vec::with_capacity(4 + words.len() * 32);
+        calldata.extend_from_slice(&selector[0..4]);
+        for word in words.iter() {
+            calldata.extend_from_slice(word);
+        }
+        calldata
+    }
+}
+
+// Multiply two big-endian uint256, the result is a big-endian uint512
+fn mul_wide(a: &[u8; 32], b: &[u8; 32]) -> [u8; 64] {
+    // Little-endian u64 limbs
+    let limbs = |x: &[u8; 32]| -> [u64; 4] {
+        let mut r = [0u64; 4];
+        for (i, limb) in r.iter_mut().enumerate() {
+            let start = 32 - (i + 1) * 8;
+            *limb = u64::from_be_bytes(x[start..start + 8].try_into().unwrap());
+        }
+        r
+    };
+    let (a, b) = (limbs(a), limbs(b));
+    let mut r = [0u64; 8];
+    for i in 0..4 {
+        let mut carry = 0u128;
+        for j in 0..4 {
+            let t = a[i] as u128 * b[j] as u128 + r[i + j] as u128 + carry;
+            r[i + j] = t as u64;
+            carry = t >> 64;
+        }
+        r[i + 4] = carry as u64;
+    }
+    let mut buf = [0u8; 64];
+    for (i, limb) in r.iter().enumerate() {
+        let start = 64 - (i + 1) * 8;
+        buf[start..start + 8].copy_from_slice(&limb.to_be_bytes());
+    }
+    buf
+}
+
+// Decode a compressed SEC1 public key
+fn public_key_from_compressed(b: &[u8]) -> Result<PublicKey, VrfError> {
+    if b.len() != COMPRESSED_PUBLIC_KEY_SIZE {
+        return Err(VrfError::InvalidLength);
+    }
+    let mut packed = [0u8; COMPRESSED_PUBLIC_KEY_SIZE];
+    packed.copy_from_slice(b);
+    PublicKey::parse_compressed(&packed).map_err(|_| VrfError::InvalidPublicKey)
+}
+
+// uint256 of the JS SDK, 0x hex or decimal string
+#[cfg(feature = "std")]
+fn json_uint256(value: &serde_json::Value) -> Result<[u8; 32], VrfError> {
+    let text = value.as_str().ok_or(VrfError::InvalidEncoding)?;
+    let mut b32 = [0u8; 32];
+    if let Some(hex_digits) = text.strip_prefix("0x") {
+        // ethers drops leading zeros
+        let padded = format!("{:0>64}", hex_digits);
+        if padded.len() != 64 {
+            return Err(VrfError::InvalidLength);
+        }
+        hex::decode_to_slice(padded, &mut b32).map_err(|_| VrfError::InvalidEncoding)?;
+        return Ok(b32);
+    }
+    if text.is_empty() || !text.bytes().all(|digit| digit.is_ascii_digit()) {
+        return Err(VrfError::InvalidEncoding);
+    }
+    // b32 = b32 * 10 + digit, big-endian
+    for digit in text.bytes() {
+        let mut carry = (digit - b'0') as u32;
+        for byte in b32.iter_mut().rev() {
+            let product = *byte as u32 * 10 + carry;
+            *byte = product as u8;
+            carry = product >> 8;
+        }
+        if carry != 0 {
+            return Err(VrfError::InvalidLength);
+        }
+    }
+    Ok(b32)
+}
+
+#[cfg(feature = "std")]
+fn json_scalar(value: &serde_json::Value) -> Result<Scalar, VrfError> {
+    scalar_from_b32(&json_uint256(value)?)
+}
+
+// Point of the JS SDK, [x, y], {"x", "y"} or SEC1 hex, it must be on the curve
+#[cfg(feature = "std")]
+fn json_point(value: &serde_json::Value) -> Result<Affine, VrfError> {
+    use serde_json::Value;
+    let (x, y) = match value {
+        Value::Array(xy) if xy.len() == 2 => (json_uint256(&xy[0])?, json_uint256(&xy[1])?),
+        Value::Object(xy) => (
+            json_uint256(xy.get("x").ok_or(VrfError::InvalidEncoding)?)?,
+            json_uint256(xy.get("y").ok_or(VrfError::InvalidEncoding)?)?,
+        ),
+        Value::String(sec1) => {
+            let bytes = from_prefixed_hex(sec1).map_err(|_| VrfError::InvalidEncoding)?;
+            match bytes.len() {
+                COMPRESSED_PUBLIC_KEY_SIZE => return affine_from_compressed(&bytes),
+                65 if bytes[0] == 0x04 => (
+                    bytes[1..33].try_into().unwrap(),
+                    bytes[33..].try_into().unwrap(),
+                ),
+                _ => return Err(VrfError::InvalidLength),
+            }
+        }
+        _ => return Err(VrfError::InvalidEncoding),
+    };
+    let (mut field_x, mut field_y) = (Field::default(), Field::default());
+    if !field_x.set_b32(&x) || !field_y.set_b32(&y) {
+        return Err(VrfError::PointNotOnCurve);
+    }
+    let mut point = Affine::default();
+    point.set_xy(&field_x, &field_y);
+    if !point.is_valid_var() {
+        return Err(VrfError::PointNotOnCurve);
+    }
+    Ok(point)
+}
+
+// Parse a 32 bytes big-endian scalar, overflowed value will be rejected
+fn scalar_from_slice(b: &[u8]) -> Result<Scalar, VrfError> {
+    if b.len() != 32 {
+        return Err(VrfError::InvalidLength);
+    }
+    let mut packed = [0u8; 32];
+    packed.copy_from_slice(b);
+    scalar_from_b32(&packed)
+}
+
+// Hex representation of a proof, it's used by serde
+// hash_mode is omitted for keccak256 proofs so their JSON is unchanged
+#[derive(Serialize, Deserialize)]
+struct ECVRFProofHex {
+    gamma: String,
+    c: String,
+    s: String,
+    y: String,
+    public_key: String,
+    #[serde(default, skip_serializing_if = "is_keccak256")]
+    hash_mode: HashMode,
+}
+
+fn is_keccak256(hash_mode: &HashMode) -> bool {
+    *hash_mode == HashMode::Keccak256
+}
+
+fn to_prefixed_hex(b: &[u8]) -> String {
+    format!("0x{}", hex::encode(b))
+}
+
+fn from_prefixed_hex(s: &str) -> Result<Vec<u8>, String> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s)).map_err(|e| e.to_string())
+}
+
+impl Serialize for ECVRFProof {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ECVRFProofHex {
+            gamma: to_prefixed_hex(&affine_to_compressed(&self.gamma)),
+            c: to_prefixed_hex(&self.c.b32()),
+            s: to_prefixed_hex(&self.s.b32()),
+            y: to_prefixed_hex(&self.y.b32()),
+            public_key: to_prefixed_hex(&self.pk.serialize_compressed()),
+            hash_mode: self.hash_mode,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ECVRFProof {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = ECVRFProofHex::deserialize(deserializer)?;
+        let field = |name: &str, value: &str| {
+            from_prefixed_hex(value).map_err(|e| de::Error::custom(format!("{}: {}", name, e)))
+        };
+        let invalid = |name: &str, e: VrfError| de::Error::custom(format!("{}: {}", name, e));
+        Ok(ECVRFProof {
+            gamma: affine_from_compressed(&field("gamma", &raw.gamma)?)
+                .map_err(|e| invalid("gamma", e))?,
+            c: scalar_from_slice(&field("c", &raw.c)?).map_err(|e| invalid("c", e))?,
+            s: scalar_from_slice(&field("s", &raw.s)?).map_err(|e| invalid("s", e))?,
+            y: scalar_from_slice(&field("y", &raw.y)?).map_err(|e| invalid("y", e))?,
+            pk: public_key_from_compressed(&field("public_key", &raw.public_key)?)
+                .map_err(|e| invalid("public_key", e))?,
+            hash_mode: raw.hash_mode,
+        })
+    }
+}
+
+// One labeled 0x hex component per line, points are compressed SEC1 like in JSON
+// Hash mode is only printed if it isn't keccak256, like in JSON
+impl core::fmt::Display for ECVRFProof {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(
+            f,
+            "gamma: {}",
+            to_prefixed_hex(&affine_to_compressed(&self.gamma))
+        )?;
+        writeln!(f, "c: {}", to_prefixed_hex(&self.c.b32()))?;
+        writeln!(f, "s: {}", to_prefixed_hex(&self.s.b32()))?;
+        writeln!(f, "y: {}", to_prefixed_hex(&self.y.b32()))?;
+        write!(
+            f,
+            "public_key: {}",
+            to_prefixed_hex(&self.pk.serialize_compressed())
+        )?;
+        if !is_keccak256(&self.hash_mode) {
+            write!(f, "\nhash_mode: {:?}", self.hash_mode)?;
+        }
+        Ok(())
+    }
+}
+
+// Compact single line form, limbs of the derived Debug are unreadable
+impl core::fmt::Debug for ECVRFProof {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "ECVRFProof {{ gamma: {}, c: {}, s: {}, y: {}, public_key: {}",
+            to_prefixed_hex(&affine_to_compressed(&self.gamma)),
+            to_prefixed_hex(&self.c.b32()),
+            to_prefixed_hex(&self.s.b32()),
+            to_prefixed_hex(&self.y.b32()),
+            to_prefixed_hex(&self.pk.serialize_compressed())
+        )?;
+        if !is_keccak256(&self.hash_mode) {
+            write!(f, ", hash_mode: {:?}", self.hash_mode)?;
+        }
+        write!(f, " }}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ECVRFProof, PROOF_SIZE, TAGGED_PROOF_SIZE};
+    use crate::{
+        helper::{affine_from_compressed, affine_to_compressed, randomize},
+        HashMode, VrfError, ECVRF,
+    };
+    use libsecp256k1::SecretKey;
+    use libsecp256k1::{
+        curve::{Affine, Scalar},
+        PublicKey,
+    };
+    use num_bigint::BigUint;
+    use rand::thread_rng;
+
+    #[test]
+    fn proof_bytes_round_trip() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let alpha = randomize();
+        let proof = ecvrf.prove(&alpha).unwrap();
+
+        let decoded = ECVRFProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert_eq!(decoded.to_bytes(), proof.to_bytes());
+        assert!(ecvrf.verify(&alpha, &decoded).unwrap());
+    }
+
+    #[test]
+    fn compressed_gamma_verifies_like_the_original() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        for _ in 0..8 {
+            let alpha = randomize();
+            let proof = ecvrf.prove(&alpha).unwrap();
+            let mut reconstructed = proof;
+            reconstructed.gamma =
+                affine_from_compressed(&affine_to_compressed(&proof.gamma)).unwrap();
+            assert_eq!(reconstructed.gamma, proof.gamma);
+            assert!(ecvrf.verify(&alpha, &reconstructed).unwrap());
+            assert_eq!(
+                reconstructed.verify_with_pubkey(&alpha, &proof.pk).unwrap(),
+                proof.verify_with_pubkey(&alpha, &proof.pk).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn standalone_verification_matches_the_instance() {
+        for hash_mode in [
+            HashMode::Keccak256,
+            HashMode::Sha256,
+            HashMode::Keccak256Compressed,
+        ] {
+            let ecvrf = ECVRF::new_with_hash(SecretKey::random(&mut thread_rng()), hash_mode);
+            let alpha = randomize();
+            let proof = ecvrf.prove(&alpha).unwrap();
+            let other_alpha = randomize();
+            for alpha in [alpha, other_alpha] {
+                assert_eq!(
+                    ECVRFProof::verify_standalone(&alpha, &proof).unwrap(),
+                    ecvrf.verify(&alpha, &proof).unwrap()
+                );
+            }
+            assert!(ECVRFProof::verify_standalone(&alpha, &proof).unwrap());
+            assert!(!ECVRFProof::verify_standalone(&other_alpha, &proof).unwrap());
+
+            // Embedded key of another signer doesn't verify
+            let mut forged = proof;
+            forged.pk = PublicKey::from_secret_key(&SecretKey::random(&mut thread_rng()));
+            assert!(!ECVRFProof::verify_standalone(&alpha, &forged).unwrap());
+        }
+    }
+
+    #[test]
+    fn proof_is_formatted_as_hex() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let proof = ecvrf.prove(&randomize()).unwrap();
+        let c = format!("0x{}", hex::encode(proof.c.b32()));
+        let public_key = format!("0x{}", hex::encode(proof.pk.serialize_compressed()));
+
+        let display = proof.to_string();
+        assert_eq!(display.lines().count(), 5);
+        for field in ["gamma: 0x", "s: 0x", "y: 0x"] {
+            assert!(display.contains(field));
+        }
+        assert!(display.contains(&format!("c: {}", c)));
+        assert!(display.contains(&format!("public_key: {}", public_key)));
+
+        let debug = format!("{:?}", proof);
+        assert!(!debug.contains('\n'));
+        assert!(debug.starts_with("ECVRFProof { gamma: 0x"));
+        assert!(debug.contains(&format!("c: {}", c)));
+        assert!(debug.contains(&format!("public_key: {} }}", public_key)));
+    }
+
+    #[test]
+    fn proof_bytes_reject_malformed_input() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let mut buf = ecvrf.prove(&randomize()).unwrap().to_bytes();
+
+        assert_eq!(
+            ECVRFProof::from_bytes(&buf[..PROOF_SIZE - 1]).unwrap_err(),
+            VrfError::InvalidLength
+        );
+
+        // x = 5 gives x^3 + 7 = 132 which has no square root modulo p
+        buf[1..33].copy_from_slice(&[0u8; 32]);
+        buf[32] = 5;
+        assert_eq!(
+            ECVRFProof::from_bytes(&buf).unwrap_err(),
+            VrfError::PointNotOnCurve
+        );
+    }
+
+    #[test]
+    fn hash_mode_is_recorded_in_wire_format() {
+        let secret_key = SecretKey::random(&mut thread_rng());
+        let keccak_proof = ECVRF::new(secret_key).prove(&randomize()).unwrap();
+        let sha256_proof = ECVRF::new_with_hash(secret_key, HashMode::Sha256)
+            .prove(&randomize())
+            .unwrap();
+        assert_eq!(keccak_proof.to_bytes().len(), PROOF_SIZE);
+        assert!(serde_json::to_value(keccak_proof)
+            .unwrap()
+            .get("hash_mode")
+            .is_none());
+
+        let mut buf = sha256_proof.to_tagged_bytes();
+        assert_eq!(buf.len(), TAGGED_PROOF_SIZE);
+        assert_eq!(buf[..PROOF_SIZE], sha256_proof.to_bytes());
+        assert_eq!(keccak_proof.to_tagged_bytes(), keccak_proof.to_bytes());
+        assert_eq!(
+            serde_json::to_value(sha256_proof).unwrap()["hash_mode"],
+            "sha256"
+        );
+        assert!(sha256_proof.to_string().ends_with("hash_mode: Sha256"));
+        buf[PROOF_SIZE] = 7;
+        assert_eq!(
+            ECVRFProof::from_bytes(&buf).unwrap_err(),
+            VrfError::InvalidHashMode
+        );
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn proof_cbor_round_trip() {
+        let secret_key = SecretKey::random(&mut thread_rng());
+        let alpha = randomize();
+        for hash_mode in [
+            HashMode::Keccak256,
+            HashMode::Sha256,
+            HashMode::Keccak256Compressed,
+        ] {
+            let ecvrf = ECVRF::new_with_hash(secret_key, hash_mode);
+            let proof = ecvrf.prove(&alpha).unwrap();
+            let cbor = proof.to_cbor();
+            let decoded = ECVRFProof::from_cbor(&cbor).unwrap();
+            assert_eq!(decoded.to_tagged_bytes(), proof.to_tagged_bytes());
+            assert!(ecvrf.verify(&alpha, &decoded).unwrap());
+
+            // Fields are byte strings of the wire format
+            let value: ciborium::Value = ciborium::from_reader(cbor.as_slice()).unwrap();
+            let sizes: Vec<_> = value
+                .as_map()
+                .unwrap()
+                .iter()
+                .filter_map(|(key, value)| Some((key.as_text()?, value.as_bytes()?.len())))
+                .collect();
+            assert_eq!(
+                sizes,
+                vec![
+                    ("gamma", 33),
+                    ("c", 32),
+                    ("s", 32),
+                    ("y", 32),
+                    ("public_key", 33)
+                ]
+            );
+            assert!(cbor.len() < 2 * PROOF_SIZE);
+        }
+
+        let proof = ECVRF::new(secret_key).prove(&alpha).unwrap();
+        let cbor = proof.to_cbor();
+        assert_eq!(
+            ECVRFProof::from_cbor(&cbor[..cbor.len() - 1]).unwrap_err(),
+            VrfError::InvalidEncoding
+        );
+        let mut json = Vec::new();
+        ciborium::into_writer(&proof, &mut json).unwrap();
+        assert_eq!(
+            ECVRFProof::from_cbor(&json).unwrap_err(),
+            VrfError::InvalidEncoding
+        );
+    }
+
+    #[cfg(feature = "drand")]
+    #[test]
+    fn drand_beacon_has_hex_fields() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let previous = ecvrf.prove(&randomize()).unwrap();
+        let proof = ecvrf.prove(&previous.next_alpha()).unwrap();
+        let beacon = proof.to_drand_json(8, Some(&previous));
+        let object = beacon.as_object().unwrap();
+        let mut keys: Vec<_> = object.keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        assert_eq!(
+            keys,
+            vec!["previous_signature", "randomness", "round", "signature"]
+        );
+        assert_eq!(beacon["round"], 8);
+        let hex_field = |name: &str| hex::decode(beacon[name].as_str().unwrap()).unwrap();
+        assert_eq!(hex_field("randomness"), proof.y.b32());
+        assert_eq!(hex_field("signature"), proof.to_bytes());
+        assert_eq!(hex_field("previous_signature"), previous.to_bytes());
+        let signature = beacon["signature"].as_str().unwrap();
+        assert_eq!(signature, signature.to_lowercase());
+        assert!(!signature.starts_with("0x"));
+
+        // Genesis has no previous epoch
+        assert!(proof.to_drand_json(0, None)["previous_signature"].is_null());
+    }
+
+    #[test]
+    fn proof_json_round_trip() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let alpha = randomize();
+        let proof = ecvrf.prove(&alpha).unwrap();
+
+        let json = serde_json::to_value(proof).unwrap();
+        assert_eq!(
+            json["c"].as_str().unwrap(),
+            format!("0x{}", hex::encode(proof.c.b32()))
+        );
+        assert_eq!(json["gamma"].as_str().unwrap().len(), 2 + 33 * 2);
+
+        let decoded: ECVRFProof = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.to_bytes(), proof.to_bytes());
+        assert!(ecvrf.verify(&alpha, &decoded).unwrap());
+    }
+
+    #[test]
+    fn proof_json_rejects_bad_fields() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let json = serde_json::to_value(ecvrf.prove(&randomize()).unwrap()).unwrap();
+
+        let mut short_scalar = json.clone();
+        short_scalar["s"] = "0x0102".into();
+        assert!(serde_json::from_value::<ECVRFProof>(short_scalar).is_err());
+
+        let mut bad_gamma = json;
+        bad_gamma["gamma"] = format!("0x02{}05", "00".repeat(31)).into();
+        assert!(serde_json::from_value::<ECVRFProof>(bad_gamma).is_err());
+    }
+
+    #[test]
+    fn verify_with_expected_public_key() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let attacker = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let alpha = randomize();
+        let proof = ecvrf.prove(&alpha).unwrap();
+        let forged = attacker.prove(&alpha).unwrap();
+
+        let expected_pk = proof.public_key();
+        assert!(proof.verify_with_pubkey(&alpha, &expected_pk).unwrap());
+        assert!(!forged.verify_with_pubkey(&alpha, &expected_pk).unwrap());
+        assert!(!proof
+            .verify_with_pubkey(&randomize(), &expected_pk)
+            .unwrap());
+    }
+
+    // Epoch 21 of BNB chain, the fixture of contracts/test/001-ecvrf.spec.ts
+    const FIXTURE_PK: &str = "0446b01e9550b56f3655dbca90cfe6b31dec3ff137f825561c563444096803531e9d4f6e8329d300483a919b63843174f1fca692fc6d2c07b985f72386e4edc846";
+    const FIXTURE_ALPHA: &str = "eabe8358ea6cedc4d3759a48648631369b9fb6d1c26da3adbc26eff564e032c4";
+    const FIXTURE_GAMMA: &str = "0846c76355dec9acdbc3bc5146cfe2c5aff116cdd610130f7ce2cc8a22a323f6e47fea662f1acdcbc1bd5dbe668a65ed7d4949e4aadcbdec07adefa6230f810d";
+    const FIXTURE_C: &str = "dd7640ebb5cf83dbbb81b9b00b67c7d09cee8570c97b9329c2564933d97f2b13";
+    const FIXTURE_S: &str = "76b2077647a864d026657539736258056eb0b832a4833294dba014c2a579d296";
+    const FIXTURE_Y: &str = "a0c64323bd3aa4c8a3a4d80f0bde8b510c1fc2c3b6631008bd1f3d0d67f4dae3";
+    const FIXTURE_WITNESS_ADDRESS: &str = "3a71452865cb8d0130319227093537b207998c34";
+    const FIXTURE_WITNESS_GAMMA: &str = "bfb38907a586e3770477dafb940e47d28f238c8a6085ec2208d7bd9418f925bc64acfc6bc55a7655969b979735324a6b71235c998b59c09250c1b09cc45f112f";
+    const FIXTURE_WITNESS_HASH: &str = "f3c9f87643ca7c23e027b98e40ca688c98a047978d932571340e490f26617b0b2abf4b3d353fc02ec4c76db23e2a4b351d00ec1fc8cce93aff2de1463f5bf628";
+    const FIXTURE_INVERSE_Z: &str =
+        "c294252664566a8850cd8de33809a0c44347bc1a953da31542eed735cb472b59";
+
+    fn scalar(hex_str: &str) -> Scalar {
+        let mut r = Scalar::default();
+        r.set_b32(&hex::decode(hex_str).unwrap().try_into().unwrap())
+            .unwrap_u8();
+        r
+    }
+
+    #[test]
+    fn calldata_matches_contract_fixture() {
+        let pk = PublicKey::parse_slice(&hex::decode(FIXTURE_PK).unwrap(), None).unwrap();
+        let gamma: Affine =
+            PublicKey::parse_slice(&hex::decode(format!("04{}", FIXTURE_GAMMA)).unwrap(), None)
+                .unwrap()
+                .into();
+        let proof = ECVRFProof::new(
+            gamma,
+            scalar(FIXTURE_C),
+            scalar(FIXTURE_S),
+            scalar(FIXTURE_Y),
+            pk,
+        );
+
+        let expected = [
+            "5a3ee342",
+            &FIXTURE_PK[2..],
+            FIXTURE_GAMMA,
+            FIXTURE_C,
+            FIXTURE_S,
+            FIXTURE_ALPHA,
+            "000000000000000000000000",
+            FIXTURE_WITNESS_ADDRESS,
+            FIXTURE_WITNESS_GAMMA,
+            FIXTURE_WITNESS_HASH,
+            FIXTURE_INVERSE_Z,
+        ]
+        .concat();
+        let calldata = proof.to_eth_calldata(&scalar(FIXTURE_ALPHA));
+        assert_eq!(calldata.len(), 4 + 13 * 32);
+        assert_eq!(hex::encode(calldata), expected);
+    }
+
+    #[test]
+    fn random_in_range_is_below_modulus() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let mut moduli = vec![[0u8; 32], [0xffu8; 32]];
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        let mut small = [0u8; 32];
+        small[31] = 6;
+        moduli.extend([one, small]);
+        for _ in 0..16 {
+            moduli.push(randomize().b32());
+        }
+        for _ in 0..16 {
+            let proof = ecvrf.prove(&randomize()).unwrap();
+            let y = BigUint::from_bytes_be(&proof.random_u256());
+            assert_eq!(proof.random_u256(), proof.y.b32());
+            for modulus in moduli.iter() {
+                let m = BigUint::from_bytes_be(modulus);
+                let r = BigUint::from_bytes_be(&proof.random_in_range(modulus));
+                if m == BigUint::default() {
+                    assert_eq!(r, y);
+                    continue;
+                }
+                assert!(r < m);
+                assert_eq!(r, (&y * &m) >> 256);
+            }
+        }
     }
 }
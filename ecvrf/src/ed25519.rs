@@ -0,0 +1,317 @@
+use crate::{suite::VrfSuite, VrfError};
+use curve25519_dalek::{
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::{clamp_integer, Scalar},
+};
+use sha2::{Digest, Sha512};
+
+// ECVRF-EDWARDS25519-SHA512-TAI of RFC 9381
+pub const SUITE_STRING: u8 = 0x03;
+
+// Size of a proof in wire format:
+// gamma (32) || c (16) || s (32)
+pub const PROOF_SIZE: usize = 80;
+
+const CHALLENGE_SIZE: usize = 16;
+const HASH_TO_CURVE_DOMAIN: u8 = 0x01;
+const CHALLENGE_DOMAIN: u8 = 0x02;
+const PROOF_TO_HASH_DOMAIN: u8 = 0x03;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ed25519Proof {
+    pub gamma: EdwardsPoint,
+    pub c: Scalar,
+    pub s: Scalar,
+}
+
+impl Ed25519Proof {
+    // Encode proof to its fixed size wire format
+    pub fn to_bytes(&self) -> [u8; PROOF_SIZE] {
+        let mut buf = [0u8; PROOF_SIZE];
+        buf[0..32].copy_from_slice(self.gamma.compress().as_bytes());
+        // c is a 128 bits challenge, the upper half of its encoding is zero
+        buf[32..48].copy_from_slice(&self.c.as_bytes()[0..CHALLENGE_SIZE]);
+        buf[48..PROOF_SIZE].copy_from_slice(self.s.as_bytes());
+        buf
+    }
+
+    // Decode proof from its wire format, gamma must be a point and s must be canonical
+    pub fn from_bytes(buf: &[u8]) -> Result<Ed25519Proof, VrfError> {
+        if buf.len() != PROOF_SIZE {
+            return Err(VrfError::InvalidLength);
+        }
+        let gamma = CompressedEdwardsY(buf[0..32].try_into().unwrap())
+            .decompress()
+            .ok_or(VrfError::InvalidGamma)?;
+        let mut c = [0u8; 32];
+        c[0..CHALLENGE_SIZE].copy_from_slice(&buf[32..48]);
+        let s = Option::from(Scalar::from_canonical_bytes(
+            buf[48..PROOF_SIZE].try_into().unwrap(),
+        ))
+        .ok_or(VrfError::InvalidScalar)?;
+        Ok(Ed25519Proof {
+            gamma,
+            c: Scalar::from_bytes_mod_order(c),
+            s,
+        })
+    }
+
+    // VRF output beta = SHA-512(suite || 0x03 || cofactor * gamma || 0x00)
+    pub fn to_hash(&self) -> [u8; 64] {
+        Sha512::new()
+            .chain_update([SUITE_STRING, PROOF_TO_HASH_DOMAIN])
+            .chain_update(self.gamma.mul_by_cofactor().compress().as_bytes())
+            .chain_update([0x00])
+            .finalize()
+            .into()
+    }
+}
+
+// Ed25519 suite, the secret key is a 32 bytes seed like in RFC 8032
+pub struct Ed25519Sha512 {
+    // None if the instance only verifies proofs
+    secret_key: Option<[u8; 32]>,
+    public_key: EdwardsPoint,
+}
+
+impl Ed25519Sha512 {
+    // Create new instance from a secret key, the public key is the Ed25519 one
+    pub fn new(secret_key: [u8; 32]) -> Self {
+        let (mut x, _) = expand_secret_key(&secret_key);
+        let public_key = EdwardsPoint::mul_base(&x);
+        clear(&mut x);
+        Self {
+            secret_key: Some(secret_key),
+            public_key,
+        }
+    }
+
+    // Create an instance that only verifies proofs of the given public key, proving fails
+    // Small order keys are rejected, they would let anyone forge proofs
+    pub fn new_verifier(public_key: &[u8; 32]) -> Result<Self, VrfError> {
+        let public_key = CompressedEdwardsY(*public_key)
+            .decompress()
+            .filter(|p| !p.is_small_order())
+            .ok_or(VrfError::InvalidPublicKey)?;
+        Ok(Self {
+            secret_key: None,
+            public_key,
+        })
+    }
+
+    // Compressed public key
+    pub fn public_key(&self) -> [u8; 32] {
+        self.public_key.compress().to_bytes()
+    }
+
+    // Try and increment, H = cofactor * string_to_point(SHA-512(suite || 0x01 || pk ||
+    // alpha || ctr || 0x00)[0..32]) with the first ctr that yields a point
+    fn hash_to_curve_tai(&self, alpha: &[u8]) -> Result<EdwardsPoint, VrfError> {
+        let public_key = self.public_key.compress();
+        for ctr in 0..=u8::MAX {
+            let hash = Sha512::new()
+                .chain_update([SUITE_STRING, HASH_TO_CURVE_DOMAIN])
+                .chain_update(public_key.as_bytes())
+                .chain_update(alpha)
+                .chain_update([ctr, 0x00])
+                .finalize();
+            if let Some(point) = CompressedEdwardsY(hash[0..32].try_into().unwrap()).decompress() {
+                return Ok(point.mul_by_cofactor());
+            }
+        }
+        // Each attempt fails with probability about 1/2, it's an error rather than a panic
+        Err(VrfError::PointNotOnCurve)
+    }
+
+    // c = SHA-512(suite || 0x02 || points || 0x00)[0..16]
+    fn challenge(points: &[EdwardsPoint]) -> Scalar {
+        let mut hasher = Sha512::new();
+        hasher.update([SUITE_STRING, CHALLENGE_DOMAIN]);
+        for point in points {
+            hasher.update(point.compress().as_bytes());
+        }
+        hasher.update([0x00]);
+        let mut c = [0u8; 32];
+        c[0..CHALLENGE_SIZE].copy_from_slice(&hasher.finalize()[0..CHALLENGE_SIZE]);
+        Scalar::from_bytes_mod_order(c)
+    }
+}
+
+// Secret scalar and nonce prefix of RFC 8032
+fn expand_secret_key(secret_key: &[u8; 32]) -> (Scalar, [u8; 32]) {
+    let hash: [u8; 64] = Sha512::digest(secret_key).into();
+    let x = Scalar::from_bytes_mod_order(clamp_integer(hash[0..32].try_into().unwrap()));
+    (x, hash[32..64].try_into().unwrap())
+}
+
+// Overwrite a secret scalar, like Scalar::clear() of libsecp256k1
+fn clear(scalar: &mut Scalar) {
+    unsafe {
+        core::ptr::write_volatile(scalar, Scalar::ZERO);
+    }
+    core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+}
+
+impl VrfSuite for Ed25519Sha512 {
+    type Point = EdwardsPoint;
+    type Scalar = Scalar;
+    type Proof = Ed25519Proof;
+
+    fn hash_to_curve(&self, alpha: &[u8]) -> Result<EdwardsPoint, VrfError> {
+        self.hash_to_curve_tai(alpha)
+    }
+
+    fn hash_points(&self, points: &[EdwardsPoint]) -> Result<Scalar, VrfError> {
+        Ok(Self::challenge(points))
+    }
+
+    fn prove(&self, alpha: &[u8]) -> Result<Ed25519Proof, VrfError> {
+        let secret_key = self.secret_key.ok_or(VrfError::MissingSecretKey)?;
+        // H = ECVRF_hash_to_curve(pk, alpha)
+        let h = self.hash_to_curve_tai(alpha)?;
+        let (mut x, prefix) = expand_secret_key(&secret_key);
+
+        // gamma = x * H
+        let gamma = h * x;
+
+        // k = SHA-512(prefix || H) mod q, the nonce of RFC 8032
+        let mut k = Scalar::from_bytes_mod_order_wide(
+            &Sha512::new()
+                .chain_update(prefix)
+                .chain_update(h.compress().as_bytes())
+                .finalize()
+                .into(),
+        );
+
+        // c = ECVRF_challenge_generation(pk, H, gamma, k * B, k * H)
+        let c = Self::challenge(&[self.public_key, h, gamma, EdwardsPoint::mul_base(&k), h * k]);
+
+        // s = (k + c * x) mod q
+        let s = k + c * x;
+        clear(&mut x);
+        clear(&mut k);
+
+        Ok(Ed25519Proof { gamma, c, s })
+    }
+
+    fn verify(&self, alpha: &[u8], proof: &Ed25519Proof) -> Result<bool, VrfError> {
+        // H = ECVRF_hash_to_curve(pk, alpha)
+        let h = self.hash_to_curve_tai(alpha)?;
+
+        // U = s * B - c * pk
+        //   = (k + c * x) * B - c * x * B
+        //   = k * B
+        let u = EdwardsPoint::vartime_double_scalar_mul_basepoint(
+            &-proof.c,
+            &self.public_key,
+            &proof.s,
+        );
+
+        // V = s * H - c * gamma
+        //   = k * H
+        let v = h * proof.s - proof.gamma * proof.c;
+
+        // c_prime = ECVRF_challenge_generation(pk, H, gamma, U, V)
+        Ok(Self::challenge(&[self.public_key, h, proof.gamma, u, v]) == proof.c)
+    }
+}
+
+impl Drop for Ed25519Sha512 {
+    // Scrub the secret key so it does not linger in freed memory
+    fn drop(&mut self) {
+        if let Some(secret_key) = self.secret_key.as_mut() {
+            unsafe {
+                core::ptr::write_volatile(secret_key, [0u8; 32]);
+            }
+        }
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Ed25519Proof, Ed25519Sha512, PROOF_SIZE};
+    use crate::{VrfError, VrfSuite};
+
+    fn from_hex<const N: usize>(s: &str) -> [u8; N] {
+        hex::decode(s).unwrap().try_into().unwrap()
+    }
+
+    // Example 16 of RFC 9381
+    #[test]
+    fn verify_known_answer_proof() {
+        let vrf = Ed25519Sha512::new(from_hex(
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+        ));
+        assert_eq!(
+            hex::encode(vrf.public_key()),
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+        );
+        let proof = vrf.prove(b"").unwrap();
+        assert_eq!(
+            hex::encode(proof.to_bytes()),
+            "8657106690b5526245a92b003bb079ccd1a92130477671f6fc01ad16f26f723f\
+             26f8a57ccaed74ee1b190bed1f479d97\
+             27d2d0f9b005a6e456a35d4fb0daab1268a1b0db10836d9826a528ca76567805"
+        );
+        assert_eq!(
+            hex::encode(proof.to_hash()),
+            "90cf1df3b703cce59e2a35b925d411164068269d7b2d29f3301c03dd757876ff\
+             66b71dda49d2de59d03450451af026798e8f81cd2e333de5cdf4f3e140fdd8ae"
+        );
+        let verifier = Ed25519Sha512::new_verifier(&vrf.public_key()).unwrap();
+        assert!(verifier.verify(b"", &proof).unwrap());
+    }
+
+    #[test]
+    fn proof_bytes_round_trip() {
+        let vrf = Ed25519Sha512::new([1u8; 32]);
+        let proof = vrf.prove(b"block hash").unwrap();
+        let decoded = Ed25519Proof::from_bytes(&proof.to_bytes()).unwrap();
+        assert_eq!(decoded, proof);
+        assert!(vrf.verify(b"block hash", &decoded).unwrap());
+
+        assert_eq!(
+            Ed25519Proof::from_bytes(&proof.to_bytes()[..PROOF_SIZE - 1]).unwrap_err(),
+            VrfError::InvalidLength
+        );
+        // s = q is not canonical
+        let mut buf = proof.to_bytes();
+        buf[48..].copy_from_slice(&from_hex::<32>(
+            "edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010",
+        ));
+        assert_eq!(
+            Ed25519Proof::from_bytes(&buf).unwrap_err(),
+            VrfError::InvalidScalar
+        );
+    }
+
+    #[test]
+    fn tampered_proof_is_rejected() {
+        let vrf = Ed25519Sha512::new([2u8; 32]);
+        let mut proof = vrf.prove(b"block hash").unwrap();
+        proof.s += curve25519_dalek::scalar::Scalar::ONE;
+        assert!(!vrf.verify(b"block hash", &proof).unwrap());
+        // Proof of another key
+        let other = Ed25519Sha512::new([3u8; 32]);
+        let proof = other.prove(b"block hash").unwrap();
+        assert!(!vrf.verify(b"block hash", &proof).unwrap());
+    }
+
+    #[test]
+    fn verifier_only_instance_can_not_prove() {
+        let vrf = Ed25519Sha512::new([4u8; 32]);
+        let verifier = Ed25519Sha512::new_verifier(&vrf.public_key()).unwrap();
+        assert_eq!(
+            verifier.prove(b"block hash").unwrap_err(),
+            VrfError::MissingSecretKey
+        );
+        // The identity is a small order point
+        let mut identity = [0u8; 32];
+        identity[0] = 1;
+        assert_eq!(
+            Ed25519Sha512::new_verifier(&identity).err(),
+            Some(VrfError::InvalidPublicKey)
+        );
+    }
+}
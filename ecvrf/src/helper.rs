@@ -1,9 +1,19 @@
+use crate::{HashMode, SchemeVersion, VrfError, HASH_TO_CURVE_DOMAIN, SUITE_STRING};
+use alloc::{format, string::String, vec, vec::Vec};
+use hmac::{Hmac, Mac};
 use libsecp256k1::{
     curve::{Affine, ECMultContext, ECMultGenContext, Field, Jacobian, Scalar},
-    util::{FULL_PUBLIC_KEY_SIZE, SECRET_KEY_SIZE},
+    util::{
+        COMPRESSED_PUBLIC_KEY_SIZE, FULL_PUBLIC_KEY_SIZE, SECRET_KEY_SIZE, TAG_PUBKEY_EVEN,
+        TAG_PUBKEY_ODD,
+    },
     PublicKey, SecretKey,
 };
-use rand::{thread_rng, RngCore};
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
 use tiny_keccak::{Hasher, Keccak};
 
 pub struct KeyPair {
@@ -36,11 +46,57 @@ pub fn affine_composer(x: &Field, y: &Field) -> Affine {
     r
 }
 
+// Compressed SEC1 encoding of a point: parity tag || x
+pub fn affine_to_compressed(a: &Affine) -> [u8; COMPRESSED_PUBLIC_KEY_SIZE] {
+    let mut point = *a;
+    point.x.normalize();
+    point.y.normalize();
+    let mut buf = [0u8; COMPRESSED_PUBLIC_KEY_SIZE];
+    buf[0] = if point.y.is_odd() {
+        TAG_PUBKEY_ODD
+    } else {
+        TAG_PUBKEY_EVEN
+    };
+    buf[1..].copy_from_slice(&point.x.b32());
+    buf
+}
+
+// Decode a compressed SEC1 point, y is recovered from x and the parity tag
+// x must be a field element of a point on the curve
+pub fn affine_from_compressed(b: &[u8]) -> Result<Affine, VrfError> {
+    if b.len() != COMPRESSED_PUBLIC_KEY_SIZE {
+        return Err(VrfError::InvalidLength);
+    }
+    let odd = match b[0] {
+        TAG_PUBKEY_EVEN => false,
+        TAG_PUBKEY_ODD => true,
+        _ => return Err(VrfError::PointNotOnCurve),
+    };
+    let mut x_b32 = [0u8; 32];
+    x_b32.copy_from_slice(&b[1..]);
+    let mut x = Field::default();
+    if !x.set_b32(&x_b32) {
+        return Err(VrfError::PointNotOnCurve);
+    }
+    let (mut y, is_square) = y_squared(&x).sqrt();
+    if !is_square {
+        return Err(VrfError::PointNotOnCurve);
+    }
+    y.normalize();
+    if y.is_odd() != odd {
+        y = y.neg(1);
+        y.normalize();
+    }
+    Ok(affine_composer(&x, &y))
+}
+
 // Projective sub, cost optimization for EVM
 pub fn projective_sub(a: &Affine, b: &Affine) -> Affine {
-    let mut c = Affine::default();
-    c.x = b.y * a.x + a.y * b.x.neg(1);
-    c.y = a.y * b.y;
+    let mut c = Affine {
+        x: b.y * a.x + a.y * b.x.neg(1),
+        y: a.y * b.y,
+        ..Default::default()
+    };
     c.x.normalize();
     c.y.normalize();
     c
@@ -48,9 +104,11 @@ pub fn projective_sub(a: &Affine, b: &Affine) -> Affine {
 
 // Projective mul, cost optimization of EVM
 pub fn projective_mul(a: &Affine, b: &Affine) -> Affine {
-    let mut c = Affine::default();
-    c.x = a.x * b.x;
-    c.y = a.y * b.y;
+    let mut c = Affine {
+        x: a.x * b.x,
+        y: a.y * b.y,
+        ..Default::default()
+    };
     c.x.normalize();
     c.y.normalize();
     c
@@ -96,7 +154,59 @@ pub fn jacobian_to_affine(j: &Jacobian) -> Affine {
     ra
 }
 
-// Perform multiplication between a point and a scalar: a * P
+// Transform many Jacobian points to normalized Affine with a single field inversion
+// (Montgomery's trick), the point at infinity stays at infinity
+pub fn jacobians_to_affines(points: &[Jacobian]) -> Vec<Affine> {
+    let mut acc = Field::from_int(1);
+    let mut prefix = Vec::with_capacity(points.len());
+    for point in points {
+        prefix.push(acc);
+        if !point.is_infinity() {
+            acc *= &point.z;
+        }
+    }
+
+    let mut inv = acc.inv();
+    let mut result = vec![Affine::default(); points.len()];
+    for i in (0..points.len()).rev() {
+        if points[i].is_infinity() {
+            result[i].set_gej(&points[i]);
+            continue;
+        }
+        // inv = (z_0 * ... * z_i)^-1, prefix[i] = z_0 * ... * z_(i-1)
+        let z_inv = inv * prefix[i];
+        inv *= &points[i].z;
+        result[i].set_gej_zinv(&points[i], &z_inv);
+        result[i].x.normalize();
+        result[i].y.normalize();
+    }
+    result
+}
+
+// Secret scalar, e.g. a secret key or a nonce. It's cleared on drop.
+// ecmult and ecmult_gen only take a plain Scalar, so a secret can't be passed to them
+// by mistake, it has to go through ecmult_ct and ecmult_gen_ct or be exposed explicitly
+pub struct SecretScalar(Scalar);
+
+impl SecretScalar {
+    pub fn new(scalar: Scalar) -> Self {
+        Self(scalar)
+    }
+
+    // Only for scalar arithmetic, never pass it to ecmult or ecmult_gen
+    pub fn expose(&self) -> &Scalar {
+        &self.0
+    }
+}
+
+impl Drop for SecretScalar {
+    fn drop(&mut self) {
+        self.0.clear();
+    }
+}
+
+// Perform multiplication between a point and a public scalar: a * P
+// It's variable time (wNAF), use ecmult_ct for secrets
 pub fn ecmult(context: &ECMultContext, a: &Affine, na: &Scalar) -> Affine {
     let mut rj = Jacobian::default();
     context.ecmult(&mut rj, &Jacobian::from_ge(a), na, &Scalar::from_int(0));
@@ -106,10 +216,73 @@ pub fn ecmult(context: &ECMultContext, a: &Affine, na: &Scalar) -> Affine {
 // Perform multiplication between a value and G: a * G
 pub fn ecmult_gen(context: &ECMultGenContext, ng: &Scalar) -> Affine {
     let mut rj = Jacobian::default();
-    context.ecmult_gen(&mut rj, &ng);
+    context.ecmult_gen(&mut rj, ng);
     jacobian_to_affine(&rj)
 }
 
+// Constant time a * P for a secret scalar, the windows are selected without branches
+pub fn ecmult_ct(context: &ECMultContext, a: &Affine, na: &SecretScalar) -> Affine {
+    let mut rj = Jacobian::default();
+    context.ecmult_const(&mut rj, a, na.expose());
+    jacobian_to_affine(&rj)
+}
+
+// Constant time a * G for a secret scalar
+// libsecp256k1 ecmult_gen is already constant time, it's the same multiplication
+pub fn ecmult_gen_ct(context: &ECMultGenContext, ng: &SecretScalar) -> Affine {
+    ecmult_gen(context, ng.expose())
+}
+
+// Hash to curve: alpha * G + y, it doesn't need the secret key so verifiers can use it directly
+// Since version 2 alpha is tagged: keccak256(SUITE_STRING || 0x01 || alpha)
+pub fn hash_to_curve(
+    ctx_gen: &ECMultGenContext,
+    version: SchemeVersion,
+    alpha: &Scalar,
+    y: Option<&Affine>,
+) -> Affine {
+    let alpha = match version {
+        SchemeVersion::V1 => *alpha,
+        SchemeVersion::V2 => keccak256_vec_scalar(
+            &[SUITE_STRING, &[HASH_TO_CURVE_DOMAIN], alpha.b32().as_ref()].concat(),
+        ),
+    };
+    let mut r = Jacobian::default();
+    ctx_gen.ecmult_gen(&mut r, &alpha);
+    if let Some(v) = y {
+        r = r.add_ge(v);
+    }
+    jacobian_to_affine(&r)
+}
+
+// Suite string of ECVRF-SECP256K1-SHA256-TAI, RFC 9381 has no secp256k1 suite so it's the
+// value other secp256k1 implementations of the draft use
+#[cfg(feature = "rfc9381")]
+pub const TAI_SUITE_STRING: u8 = 0xFE;
+
+// Try and increment of RFC 9381 section 5.4.1.1 with SHA-256, H = string_to_point(0x02 ||
+// SHA-256(suite || 0x01 || pk || alpha || ctr || 0x00)) with the first ctr that yields a point
+// It interoperates with standard verifiers, unlike hash_to_curve()
+#[cfg(feature = "rfc9381")]
+pub fn hash_to_curve_tai(alpha: &[u8], pk: &PublicKey) -> Result<Affine, VrfError> {
+    let pk = pk.serialize_compressed();
+    let mut candidate = [TAG_PUBKEY_EVEN; COMPRESSED_PUBLIC_KEY_SIZE];
+    for ctr in 0..=u8::MAX {
+        let hash = Sha256::new()
+            .chain_update([TAI_SUITE_STRING, HASH_TO_CURVE_DOMAIN])
+            .chain_update(pk)
+            .chain_update(alpha)
+            .chain_update([ctr, 0x00])
+            .finalize();
+        candidate[1..].copy_from_slice(&hash);
+        if let Ok(point) = affine_from_compressed(&candidate) {
+            return Ok(point);
+        }
+    }
+    // Each attempt fails with probability about 1/2
+    Err(VrfError::PointNotOnCurve)
+}
+
 // Check point is on curve or not
 pub fn is_on_curve(point: &Affine) -> bool {
     y_squared(&point.x) == point.y * point.y
@@ -124,22 +297,97 @@ pub fn keccak256_affine(a: &Affine) -> [u8; 32] {
     output
 }
 
-// Keccak a point to scalar
-pub fn keccak256_affine_scalar(a: &Affine) -> Scalar {
+// keccak256 of the compressed SEC1 encoding of a point, parity tag || x
+pub fn keccak256_affine_compressed(a: &Affine) -> [u8; 32] {
+    let mut output = [0u8; 32];
+    let mut hasher = Keccak::v256();
+    hasher.update(&affine_to_compressed(a));
+    hasher.finalize(&mut output);
+    output
+}
+
+// Incremental hasher of a hash mode, every digest is 32 bytes
+pub(crate) enum ModeHasher {
+    Keccak256(Keccak),
+    Sha256(Sha256),
+}
+
+impl ModeHasher {
+    pub(crate) fn new(hash_mode: HashMode) -> Self {
+        match hash_mode {
+            HashMode::Keccak256 | HashMode::Keccak256Compressed => {
+                ModeHasher::Keccak256(Keccak::v256())
+            }
+            HashMode::Sha256 => ModeHasher::Sha256(Sha256::new()),
+        }
+    }
+
+    pub(crate) fn update(&mut self, b: &[u8]) {
+        match self {
+            ModeHasher::Keccak256(hasher) => hasher.update(b),
+            ModeHasher::Sha256(hasher) => Digest::update(hasher, b),
+        }
+    }
+
+    pub(crate) fn finalize(self) -> [u8; 32] {
+        match self {
+            ModeHasher::Keccak256(hasher) => {
+                let mut output = [0u8; 32];
+                hasher.finalize(&mut output);
+                output
+            }
+            ModeHasher::Sha256(hasher) => hasher.finalize().into(),
+        }
+    }
+}
+
+// Hash a point with the given hash mode, keccak256_affine() for HashMode::Keccak256
+// and keccak256_affine_compressed() for HashMode::Keccak256Compressed
+pub fn hash_affine(hash_mode: HashMode, a: &Affine) -> [u8; 32] {
+    if hash_mode == HashMode::Keccak256Compressed {
+        return keccak256_affine_compressed(a);
+    }
+    let mut hasher = ModeHasher::new(hash_mode);
+    hasher.update(a.x.b32().as_ref());
+    hasher.update(a.y.b32().as_ref());
+    hasher.finalize()
+}
+
+// Hash a point to scalar mod the group order with the given hash mode
+pub fn hash_affine_scalar(hash_mode: HashMode, a: &Affine) -> Scalar {
+    scalar_from_bytes_reduced(&hash_affine(hash_mode, a))
+}
+
+// Parse a 32 bytes big-endian scalar, values that overflow the group order are rejected
+pub fn scalar_from_b32(b: &[u8; 32]) -> Result<Scalar, VrfError> {
     let mut r = Scalar::default();
-    r.set_b32(&keccak256_affine(&a)).unwrap_u8();
+    if bool::from(r.set_b32(b)) {
+        return Err(VrfError::InvalidScalar);
+    }
+    Ok(r)
+}
+
+// 32 bytes big-endian value mod the group order, e.g. a hash that becomes a scalar
+// 2^256 is less than twice the order, so subtracting it once on overflow is a full reduction
+pub fn scalar_from_bytes_reduced(bytes: &[u8; 32]) -> Scalar {
+    let mut r = Scalar::default();
+    // set_b32() subtracts the order if the value overflows it
+    let _ = r.set_b32(bytes);
     r
 }
 
-// Keccak a vector to scalar
-pub fn keccak256_vec_scalar(a: &Vec<u8>) -> Scalar {
+// Keccak a point to scalar, the on-chain verifier uses the hash as is so overflow is an error
+pub fn keccak256_affine_scalar(a: &Affine) -> Result<Scalar, VrfError> {
+    scalar_from_b32(&keccak256_affine(a))
+}
+
+// Keccak a vector to scalar mod the group order
+pub fn keccak256_vec_scalar(a: &[u8]) -> Scalar {
     let mut output = [0u8; 32];
     let mut hasher = Keccak::v256();
-    hasher.update(a.as_slice());
+    hasher.update(a);
     hasher.finalize(&mut output);
-    let mut r = Scalar::default();
-    r.set_b32(&output).unwrap_u8();
-    r
+    scalar_from_bytes_reduced(&output)
 }
 
 // Calculate witness address from a point
@@ -150,12 +398,9 @@ pub fn calculate_witness_address(witness: &Affine) -> [u8; 20] {
 // Convert address to Scalar type
 pub fn address_to_scalar(witness_address: &[u8; 20]) -> Scalar {
     let mut temp_bytes = [0u8; 32];
-    let mut scalar_address = Scalar::default();
-    for i in 0..20 {
-        temp_bytes[12 + i] = witness_address[i];
-    }
-    scalar_address.set_b32(&temp_bytes).unwrap_u8();
-    scalar_address
+    temp_bytes[12..32].copy_from_slice(witness_address);
+    // 160 bits never overflow the group order
+    scalar_from_bytes_reduced(&temp_bytes)
 }
 
 // Has a Public Key and return a Ethereum address
@@ -171,27 +416,49 @@ pub fn get_address(pub_key: PublicKey) -> [u8; 20] {
     output[12..32].try_into().unwrap()
 }
 
-// Hash bytes array to a field
-pub fn field_hash(b: &Vec<u8>) -> Field {
-    let mut s = Scalar::default();
+// Parse a SEC1 encoded public key from hex, 0x prefix is optional
+// The form is decided by the length, 33 bytes compressed or 65 bytes uncompressed
+pub fn parse_public_key(s: &str) -> Result<PublicKey, VrfError> {
+    let bytes =
+        hex::decode(s.strip_prefix("0x").unwrap_or(s)).map_err(|_| VrfError::InvalidPublicKey)?;
+    match bytes.len() {
+        COMPRESSED_PUBLIC_KEY_SIZE => PublicKey::parse_compressed(bytes[..].try_into().unwrap()),
+        FULL_PUBLIC_KEY_SIZE => PublicKey::parse(bytes[..].try_into().unwrap()),
+        _ => return Err(VrfError::InvalidLength),
+    }
+    .map_err(|_| VrfError::InvalidPublicKey)
+}
+
+// Encode a public key as 0x prefixed SEC1 hex
+pub fn public_key_to_hex(pk: &PublicKey, compressed: bool) -> String {
+    if compressed {
+        format!("0x{}", hex::encode(pk.serialize_compressed()))
+    } else {
+        format!("0x{}", hex::encode(pk.serialize()))
+    }
+}
+
+// Hash bytes array to a field, hashes that overflow the field are hashed again
+// like fieldHash() of the on-chain verifier
+pub fn field_hash(b: &[u8]) -> Field {
     let mut output = [0u8; 32];
     let mut hasher = Keccak::v256();
     hasher.update(b);
     hasher.finalize(&mut output);
-    s.set_b32(&output).unwrap_u8();
-    if scalar_is_gte(&s, &FIELD_SIZE) {
+    let mut f = Field::default();
+    while !f.set_b32(&output) {
         let mut hasher = Keccak::v256();
         hasher.update(&output);
         hasher.finalize(&mut output);
-        s.set_b32(&output).unwrap_u8();
-    }
-    let mut f = Field::default();
-    if !f.set_b32(&s.b32()) {
-        f.normalize();
     }
     f
 }
 
+// Compare two scalars in constant time, based on their 32 bytes representation
+pub fn ct_eq(a: &Scalar, b: &Scalar) -> bool {
+    a.b32().ct_eq(&b.b32()).into()
+}
+
 // Return true if a > b
 
 pub fn scalar_is_gt(a: &Scalar, b: &Scalar) -> bool {
@@ -221,10 +488,12 @@ pub fn scalar_is_gte(a: &Scalar, b: &Scalar) -> bool {
 }
 
 // Try to generate a point on the curve based on hashes
-pub fn new_candidate_point(b: &Vec<u8>) -> Affine {
-    let mut r = Affine::default();
-    // X is a digest of field
-    r.x = field_hash(b);
+pub fn new_candidate_point(b: &[u8]) -> Affine {
+    let mut r = Affine {
+        // X is a digest of field
+        x: field_hash(b),
+        ..Default::default()
+    };
     // Y is a coordinate point, corresponding to x
     (r.y, _) = y_squared(&r.x).sqrt();
     r.x.normalize();
@@ -239,7 +508,7 @@ pub fn new_candidate_point(b: &Vec<u8>) -> Affine {
 
 // Y squared, it was calculate by evaluate X
 pub fn y_squared(x: &Field) -> Field {
-    let mut t = x.clone();
+    let mut t = *x;
     // y^2 = x^3 + 7
     t = t * t * t + Field::from_int(7);
     t.normalize();
@@ -247,21 +516,70 @@ pub fn y_squared(x: &Field) -> Field {
 }
 
 // Random bytes array
+#[cfg(feature = "std")]
 pub fn random_bytes(buf: &mut [u8]) {
     let mut rng = thread_rng();
     rng.fill_bytes(buf);
 }
 
-// Random Scalar
+// Random Scalar, it's safe to call from many threads since thread_rng() is thread local
+#[cfg(feature = "std")]
 pub fn randomize() -> Scalar {
-    let mut result = Scalar::default();
+    randomize_with(&mut thread_rng())
+}
+
+// Random Scalar drawn from the given rng, e.g. a seeded one for reproducible tests
+pub fn randomize_with<R: RngCore>(rng: &mut R) -> Scalar {
     let mut buf = [0u8; 32];
-    random_bytes(&mut buf);
-    result.set_b32(&buf).unwrap_u8();
-    result
+    loop {
+        rng.fill_bytes(&mut buf);
+        // Reject values out of the group order rather than reducing them, it keeps k uniform
+        if let Ok(result) = scalar_from_b32(&buf) {
+            return result;
+        }
+    }
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+// HMAC-SHA256 over the concatenation of all given parts
+fn hmac_sha256(key: &[u8; 32], parts: &[&[u8]]) -> [u8; 32] {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key size");
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+// Deterministic nonce generation, RFC 6979 section 3.2 with HMAC-SHA256
+// secret_key is x and message is the already hashed message h1, both are
+// reduced modulo the group order so int2octets and bits2octets are b32()
+pub fn rfc6979_nonce(secret_key: &Scalar, message: &Scalar) -> Scalar {
+    let x = secret_key.b32();
+    let h1 = message.b32();
+    let mut v = [1u8; 32];
+    let mut k = [0u8; 32];
+
+    k = hmac_sha256(&k, &[&v, &[0x00], &x, &h1]);
+    v = hmac_sha256(&k, &[&v]);
+    k = hmac_sha256(&k, &[&v, &[0x01], &x, &h1]);
+    v = hmac_sha256(&k, &[&v]);
+
+    loop {
+        // qlen equal to hlen, a single block of V is enough
+        v = hmac_sha256(&k, &[&v]);
+        let mut nonce = Scalar::default();
+        let overflow = bool::from(nonce.set_b32(&v));
+        if !overflow && !nonce.is_zero() {
+            return nonce;
+        }
+        k = hmac_sha256(&k, &[&v, &[0x00]]);
+        v = hmac_sha256(&k, &[&v]);
+    }
 }
 
 // Generate a new libsecp256k1 key pair
+#[cfg(feature = "std")]
 pub fn generate_keypair() -> KeyPair {
     let mut rng = thread_rng();
     let secret_key = SecretKey::random(&mut rng);
@@ -273,6 +591,7 @@ pub fn generate_keypair() -> KeyPair {
 }
 
 // Generate raw key pair in bytes array
+#[cfg(feature = "std")]
 pub fn generate_raw_keypair() -> RawKeyPair {
     let mut rng = thread_rng();
     let secret = SecretKey::random(&mut rng);
@@ -286,20 +605,88 @@ pub fn generate_raw_keypair() -> RawKeyPair {
 
 #[cfg(test)]
 mod tests {
-    use crate::ECVRF;
-    use libsecp256k1::{curve::Scalar, SecretKey};
+    use libsecp256k1::curve::Scalar;
+    use num_bigint::BigUint;
+    use sha2::{Digest, Sha256};
 
     use super::{
-        is_on_curve, new_candidate_point, random_bytes, randomize, scalar_is_gt, scalar_is_gte,
+        affine_from_compressed, affine_to_compressed, ct_eq, ecmult, ecmult_ct, ecmult_gen,
+        ecmult_gen_ct, generate_keypair, hash_to_curve, is_on_curve, new_candidate_point,
+        parse_public_key, public_key_to_hex, random_bytes, randomize, rfc6979_nonce,
+        scalar_from_b32, scalar_from_bytes_reduced, scalar_is_gt, scalar_is_gte, SecretScalar,
+        GROUP_ORDER,
     };
+    use crate::{SchemeVersion, VrfError, ECVRF};
+    use libsecp256k1::{curve::Affine, SecretKey, ECMULT_CONTEXT, ECMULT_GEN_CONTEXT};
+
+    #[test]
+    fn constant_time_ecmult_matches_ecmult() {
+        let h = hash_to_curve(
+            &ECMULT_GEN_CONTEXT,
+            SchemeVersion::CURRENT,
+            &randomize(),
+            None,
+        );
+        // n - 1, the largest scalar
+        let mut n_minus_one = Scalar::from_int(1);
+        n_minus_one.cond_neg_assign(1.into());
+        for scalar in [randomize(), randomize(), Scalar::from_int(1), n_minus_one] {
+            let secret = SecretScalar::new(scalar);
+            assert_eq!(
+                ecmult(&ECMULT_CONTEXT, &h, &scalar),
+                ecmult_ct(&ECMULT_CONTEXT, &h, &secret)
+            );
+            assert_eq!(
+                ecmult_gen(&ECMULT_GEN_CONTEXT, &scalar),
+                ecmult_gen_ct(&ECMULT_GEN_CONTEXT, &secret)
+            );
+        }
+    }
+
+    #[test]
+    fn compressed_point_recovers_y() {
+        // Points of both parities
+        for _ in 0..8 {
+            let public_key = generate_keypair().public_key;
+            let compressed = public_key.serialize_compressed();
+            let point: Affine = public_key.into();
+            assert_eq!(affine_to_compressed(&point), compressed);
+            let decoded = affine_from_compressed(&compressed).unwrap();
+            assert_eq!(decoded.x, point.x);
+            assert_eq!(decoded.y, point.y);
+        }
+        let compressed = generate_keypair().public_key.serialize_compressed();
+        assert_eq!(
+            affine_from_compressed(&compressed[..32]).unwrap_err(),
+            VrfError::InvalidLength
+        );
+        let mut bad_tag = compressed;
+        bad_tag[0] = 0x04;
+        assert_eq!(
+            affine_from_compressed(&bad_tag).unwrap_err(),
+            VrfError::PointNotOnCurve
+        );
+        // x = p is not a field element, x = 5 has no point on the curve
+        let field_size = [0xffu8; 32];
+        let mut x_overflow = [0x02u8; 33];
+        x_overflow[1..].copy_from_slice(&field_size);
+        assert!(affine_from_compressed(&x_overflow).is_err());
+        let mut off_curve = [0u8; 33];
+        off_curve[0] = 0x02;
+        off_curve[32] = 5;
+        assert_eq!(
+            affine_from_compressed(&off_curve).unwrap_err(),
+            VrfError::PointNotOnCurve
+        );
+    }
 
     #[test]
     fn point_must_be_on_curve() {
         let mut buf = [0u8; 32];
         random_bytes(&mut buf);
-        let mut rv = new_candidate_point(buf.to_vec().as_ref());
+        let mut rv = new_candidate_point(buf.as_ref());
         while !is_on_curve(&rv) {
-            rv = new_candidate_point(&rv.x.b32().to_vec());
+            rv = new_candidate_point(rv.x.b32().as_ref());
         }
         assert!(is_on_curve(&rv));
     }
@@ -365,4 +752,172 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn rfc6979_nonce_known_answer() {
+        // Well known secp256k1 vector, private key = 1, message = "Satoshi Nakamoto"
+        let mut h1 = Scalar::default();
+        h1.set_b32(&Sha256::digest(b"Satoshi Nakamoto").into())
+            .unwrap_u8();
+        let nonce = rfc6979_nonce(&Scalar::from_int(1), &h1);
+        assert_eq!(
+            hex::encode(nonce.b32()),
+            "8f8a276c19f4149656b280621e358cce24f5f52542772691ee69063b74f15d15"
+        );
+    }
+
+    #[test]
+    fn test_ct_eq() {
+        let a = Scalar([1, 2, 3, 4, 5, 6, 7, 8]);
+        assert!(ct_eq(&a, &a));
+        assert!(ct_eq(&a, &Scalar([1, 2, 3, 4, 5, 6, 7, 8])));
+        assert!(!ct_eq(&a, &Scalar([1, 2, 3, 4, 5, 6, 7, 9])));
+        assert!(!ct_eq(&a, &Scalar([0, 2, 3, 4, 5, 6, 7, 8])));
+        assert!(!ct_eq(&a, &Scalar::default()));
+    }
+
+    #[test]
+    fn hash_to_curve_matches_method() {
+        let secret_key = SecretKey::parse(&randomize().b32()).unwrap();
+        let alpha = randomize();
+        for version in [SchemeVersion::V1, SchemeVersion::V2] {
+            let ecvrf = ECVRF::new_with_version(secret_key, version);
+            let mut pk: Affine = ecvrf.public_key.into();
+            pk.x.normalize();
+            pk.y.normalize();
+            for y in [None, Some(&pk)] {
+                assert_eq!(
+                    hash_to_curve(&ECMULT_GEN_CONTEXT, version, &alpha, y),
+                    ecvrf.hash_to_curve(&alpha, y)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn public_key_round_trips_in_both_forms() {
+        let public_key = generate_keypair().public_key;
+
+        let compressed = public_key_to_hex(&public_key, true);
+        assert_eq!(compressed.len(), 2 + 33 * 2);
+        assert_eq!(parse_public_key(&compressed).unwrap(), public_key);
+
+        let uncompressed = public_key_to_hex(&public_key, false);
+        assert_eq!(uncompressed.len(), 2 + 65 * 2);
+        assert_eq!(parse_public_key(&uncompressed).unwrap(), public_key);
+        // Keyring rows are stored without prefix
+        assert_eq!(parse_public_key(&uncompressed[2..]).unwrap(), public_key);
+
+        assert!(matches!(
+            parse_public_key(&compressed[..compressed.len() - 2]),
+            Err(VrfError::InvalidLength)
+        ));
+        assert!(matches!(
+            parse_public_key("0xzz"),
+            Err(VrfError::InvalidPublicKey)
+        ));
+        // 0x05 is not a SEC1 tag
+        assert!(matches!(
+            parse_public_key(&format!("0x05{}", &compressed[4..])),
+            Err(VrfError::InvalidPublicKey)
+        ));
+    }
+
+    #[test]
+    fn scalar_overflow_is_reported() {
+        // Keccak outputs above the group order can't be found on purpose, so the
+        // conversion that hash_points_prefix() and keccak256_affine_scalar() use is fed directly
+        assert!(matches!(
+            scalar_from_b32(&[0xff; 32]),
+            Err(VrfError::InvalidScalar)
+        ));
+        assert!(matches!(
+            scalar_from_b32(&GROUP_ORDER.b32()),
+            Err(VrfError::InvalidScalar)
+        ));
+        // n - 1 is the largest scalar
+        let mut largest = GROUP_ORDER.b32();
+        largest[31] -= 1;
+        assert!(scalar_from_b32(&largest).is_ok());
+        // Reduction is explicit
+        assert!(scalar_from_bytes_reduced(&GROUP_ORDER.b32()).is_zero());
+    }
+
+    #[test]
+    fn reduced_scalar_matches_big_integer_reference() {
+        let order = BigUint::from_bytes_be(&GROUP_ORDER.b32());
+        let mut values = vec![[0u8; 32], [0xff; 32], GROUP_ORDER.b32()];
+        // Around the order and a few hashes above it
+        for k in [1u32, 2, 0xffff] {
+            for value in [&order - k, &order + k] {
+                let mut b = [0u8; 32];
+                let bytes = value.to_bytes_be();
+                b[32 - bytes.len()..].copy_from_slice(&bytes);
+                values.push(b);
+            }
+        }
+        for i in 0u8..8 {
+            let mut b: [u8; 32] = Sha256::digest([i]).into();
+            b[0] = 0xff;
+            values.push(b);
+        }
+        for b in values {
+            let expected = BigUint::from_bytes_be(&b) % &order;
+            assert_eq!(
+                BigUint::from_bytes_be(&scalar_from_bytes_reduced(&b).b32()),
+                expected,
+                "{}",
+                hex::encode(b)
+            );
+        }
+    }
+
+    // Regression vectors, RFC 9381 has no secp256k1 suite so they were produced by this
+    // implementation and only pin its output
+    #[cfg(feature = "rfc9381")]
+    #[test]
+    fn hash_to_curve_tai_regression() {
+        use super::hash_to_curve_tai;
+        use libsecp256k1::PublicKey;
+
+        for (secret_key, alpha, public_key, h) in [
+            (
+                "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721",
+                "73616d706c65",
+                "032c8c31fc9f990c6b55e3865a184a4ce50e09481f2eaeb3e60ec1cea13a6ae645",
+                "0221ceb1ce22cd34d8b73a619164ed64e917ca31fd454075d02e4bdfa9c5ce0b48",
+            ),
+            (
+                "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721",
+                "74657374",
+                "032c8c31fc9f990c6b55e3865a184a4ce50e09481f2eaeb3e60ec1cea13a6ae645",
+                "02594b108580b3735a97736d141720b8f2ddee2bcbef6319d057fef7e9972292ce",
+            ),
+            (
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "",
+                "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+                "024192220588c4ef502f5d2ab75552edfbe0256cebb0424efb9c4c58f438c3dcb4",
+            ),
+            (
+                "1e99423a4ed27608a15a2616a2b0e9e52ced330ac530edcc32c8ffc6a526aedd",
+                "4f726f636869",
+                "03f028892bad7ed57d2fb57bf33081d5cfcf6f9ed3d3d7f159c2e2fff579dc341a",
+                "022e64d03631ba7d4b295750c769366ef3abf75581511b17dc7a24e262bb121dcc",
+            ),
+            (
+                "1e99423a4ed27608a15a2616a2b0e9e52ced330ac530edcc32c8ffc6a526aedd",
+                "73616d706c65",
+                "03f028892bad7ed57d2fb57bf33081d5cfcf6f9ed3d3d7f159c2e2fff579dc341a",
+                "02b4078ebc4ea28209a0b04fff6ccb5eca337a13e3ff612a1ca77bbff37e1ee824",
+            ),
+        ] {
+            let secret_key = SecretKey::parse_slice(&hex::decode(secret_key).unwrap()).unwrap();
+            let pk = PublicKey::from_secret_key(&secret_key);
+            assert_eq!(hex::encode(pk.serialize_compressed()), public_key);
+            let point = hash_to_curve_tai(&hex::decode(alpha).unwrap(), &pk).unwrap();
+            assert!(is_on_curve(&point));
+            assert_eq!(hex::encode(affine_to_compressed(&point)), h);
+        }
+    }
 }
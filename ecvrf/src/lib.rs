@@ -1,48 +1,285 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
 use crate::{
-    ecproof::{ECVRFContractProof, ECVRFProof},
+    ecproof::ECVRFContractProof,
     helper::{
-        address_to_scalar, calculate_witness_address, ecmult, ecmult_gen, is_on_curve,
-        jacobian_to_affine, keccak256_affine_scalar, new_candidate_point, projective_ec_add,
-        randomize, scalar_is_gte, GROUP_ORDER,
+        address_to_scalar, calculate_witness_address, ecmult, keccak256_affine_scalar,
+        projective_ec_add, randomize, scalar_is_gte, GROUP_ORDER,
     },
+    signature::{epoch_digest, payload_digest, RecoverableSignature},
 };
+use crate::{
+    ecproof::ECVRFProof,
+    helper::{
+        ct_eq, ecmult_ct, ecmult_gen_ct, hash_affine_scalar, is_on_curve, jacobian_to_affine,
+        jacobians_to_affines, keccak256_vec_scalar, new_candidate_point, randomize_with,
+        rfc6979_nonce, scalar_from_b32, scalar_from_bytes_reduced, ModeHasher, SecretScalar,
+    },
+};
+use alloc::{vec, vec::Vec};
 use libsecp256k1::{
     curve::{Affine, ECMultContext, ECMultGenContext, Field, Jacobian, Scalar, AFFINE_G},
     PublicKey, SecretKey, ECMULT_CONTEXT, ECMULT_GEN_CONTEXT,
 };
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
 use tiny_keccak::{Hasher, Keccak};
 
 pub mod ecproof;
+#[cfg(feature = "ed25519")]
+pub mod ed25519;
 pub mod helper;
 pub mod secp256k1 {
     pub use libsecp256k1::*;
 }
+#[cfg(feature = "std")]
 pub mod random {
     pub use rand::thread_rng;
 }
+pub mod signature;
+pub mod suite;
+pub mod verifier;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use suite::VrfSuite;
+pub use verifier::Verifier;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VrfError {
+    // Public key is not a valid point
+    InvalidPublicKey,
+    // Gamma is not a valid point
+    InvalidGamma,
+    // Decoded point does not lie on the curve
+    PointNotOnCurve,
+    // Input has an unexpected length
+    InvalidLength,
+    // Scalar is not in range of the group order
+    InvalidScalar,
+    // Instance was created by new_verifier(), it can't prove
+    MissingSecretKey,
+    // Signature can not be decoded or no key can be recovered from it
+    InvalidSignature,
+    // Proof was encoded with an unknown hash mode
+    InvalidHashMode,
+    // Encoded proof is malformed or misses a field
+    InvalidEncoding,
+}
+
+impl core::fmt::Display for VrfError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            VrfError::InvalidPublicKey => write!(f, "invalid public key"),
+            VrfError::InvalidGamma => write!(f, "invalid gamma point"),
+            VrfError::PointNotOnCurve => write!(f, "point is not on the curve"),
+            VrfError::InvalidLength => write!(f, "invalid input length"),
+            VrfError::InvalidScalar => write!(f, "scalar overflows the group order"),
+            VrfError::MissingSecretKey => write!(f, "secret key is required to prove"),
+            VrfError::InvalidSignature => write!(f, "invalid signature"),
+            VrfError::InvalidHashMode => write!(f, "unknown hash mode"),
+            VrfError::InvalidEncoding => write!(f, "malformed proof encoding"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VrfError {}
+
+// Result of verify_detailed(), why a proof was rejected
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerifyOutcome {
+    Valid,
+    // c wasn't recomputed from the witnesses, or the proof was hashed with another hash mode
+    ChallengeMismatch,
+    // c matches but y isn't the hash of gamma
+    OutputMismatch,
+    // Public key or gamma is infinity or not on the curve
+    InvalidPoint,
+}
+
+// Suite string, it separates the hashes of the ordinary prover from other
+// protocols that reuse the same key
+pub const SUITE_STRING: &[u8] = b"ORAND-ECVRF-SECP256K1-KECCAK256";
+
+// Domain separator of hash_to_curve and hash_points
+pub(crate) const HASH_TO_CURVE_DOMAIN: u8 = 0x01;
+const HASH_POINTS_DOMAIN: u8 = 0x02;
+
+// Inputs of the challenge c of the ordinary scheme, k * G and k * H are U and V when verifying
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChallengePoint {
+    G,
+    H,
+    PublicKey,
+    Gamma,
+    // k * G
+    Kg,
+    // k * H
+    Kh,
+}
+
+// Order in which hash_points feeds the points to the hash, a different order changes c of
+// every proof so none of the proofs that were produced would verify
+pub const HASH_POINTS_ORDER: [ChallengePoint; 6] = [
+    ChallengePoint::G,
+    ChallengePoint::H,
+    ChallengePoint::PublicKey,
+    ChallengePoint::Gamma,
+    ChallengePoint::Kg,
+    ChallengePoint::Kh,
+];
+
+// Points of a challenge, they're named so they can't be passed in the wrong order
+#[derive(Clone, Copy, Debug)]
+pub struct ChallengePoints<'a> {
+    pub g: &'a Affine,
+    pub h: &'a Affine,
+    pub pk: &'a Affine,
+    pub gamma: &'a Affine,
+    pub kg: &'a Affine,
+    pub kh: &'a Affine,
+}
+
+impl ChallengePoints<'_> {
+    pub fn point(&self, point: ChallengePoint) -> &Affine {
+        match point {
+            ChallengePoint::G => self.g,
+            ChallengePoint::H => self.h,
+            ChallengePoint::PublicKey => self.pk,
+            ChallengePoint::Gamma => self.gamma,
+            ChallengePoint::Kg => self.kg,
+            ChallengePoint::Kh => self.kh,
+        }
+    }
+
+    // Points in HASH_POINTS_ORDER
+    pub fn ordered(&self) -> [Affine; 6] {
+        HASH_POINTS_ORDER.map(|point| *self.point(point))
+    }
+}
 
-pub struct ECVRF<'a> {
-    secret_key: SecretKey,
+// Version of the ordinary prove/verify scheme
+// The on-chain prover (prove_contract) isn't affected
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SchemeVersion {
+    // Untagged hashes, it's kept to verify epochs created before version 2
+    V1,
+    // hash_to_curve and hash_points are tagged with SUITE_STRING
+    #[default]
+    V2,
+}
+
+impl SchemeVersion {
+    pub const CURRENT: SchemeVersion = SchemeVersion::V2;
+}
+
+// Hash function of hash_points and the VRF output y, each proof records its mode
+// keccak256 is cheaper on Ethereum, sha256 is for chains without a keccak precompile
+// hash_to_curve and the on-chain prover (prove_contract) always use keccak256
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashMode {
+    #[default]
+    Keccak256,
+    Sha256,
+    // keccak256 but y hashes the compressed 33-byte gamma, for contracts that hash SEC1 points
+    #[serde(rename = "keccak256_compressed")]
+    Keccak256Compressed,
+}
+
+// secp256k1 suite with Keccak-256, its proofs are verified on-chain
+pub struct Secp256k1Keccak<'a> {
+    // None if the instance only verifies proofs
+    secret_key: Option<SecretKey>,
+    // Scalar form of secret_key, converted once instead of on every proof
+    secret_scalar: Option<Scalar>,
     public_key: PublicKey,
+    version: SchemeVersion,
+    hash_mode: HashMode,
     ctx_mul: &'a ECMultContext,
     ctx_gen: &'a ECMultGenContext,
 }
 
-impl ECVRF<'_> {
+// Kept for source compatibility, it was the only suite before VrfSuite
+pub type ECVRF<'a> = Secp256k1Keccak<'a>;
+
+impl Secp256k1Keccak<'_> {
     // Create new instance of ECVRF from a secret key
     pub fn new(secret_key: SecretKey) -> Self {
-        ECVRF {
-            secret_key: secret_key,
+        Self::new_with_version(secret_key, SchemeVersion::CURRENT)
+    }
+
+    // Create new instance of ECVRF for the given scheme version,
+    // SchemeVersion::V1 is used to verify epochs that were created without domain separation
+    pub fn new_with_version(secret_key: SecretKey, version: SchemeVersion) -> Self {
+        Secp256k1Keccak {
+            secret_key: Some(secret_key),
+            secret_scalar: Some(secret_key.into()),
             public_key: PublicKey::from_secret_key(&secret_key),
+            version,
+            hash_mode: HashMode::Keccak256,
+            ctx_gen: &ECMULT_GEN_CONTEXT,
+            ctx_mul: &ECMULT_CONTEXT,
+        }
+    }
+
+    // Create new instance of ECVRF that hashes with the given hash mode
+    pub fn new_with_hash(secret_key: SecretKey, hash_mode: HashMode) -> Self {
+        let mut vrf = Self::new(secret_key);
+        vrf.hash_mode = hash_mode;
+        vrf
+    }
+
+    // Scheme version of this instance
+    pub fn version(&self) -> SchemeVersion {
+        self.version
+    }
+
+    // Hash mode of this instance, it only accepts proofs of the same mode
+    pub fn hash_mode(&self) -> HashMode {
+        self.hash_mode
+    }
+
+    // Copy of the cached secret scalar, it's cleared when the copy is dropped
+    fn secret_scalar(&self) -> Result<SecretScalar, VrfError> {
+        self.secret_scalar
+            .map(SecretScalar::new)
+            .ok_or(VrfError::MissingSecretKey)
+    }
+
+    // Create an instance that only verifies proofs of the given public key, proving fails
+    pub fn new_verifier(public_key: PublicKey) -> Self {
+        Self::new_verifier_with_version(public_key, SchemeVersion::CURRENT)
+    }
+
+    // Create an instance that only verifies proofs of the given public key and scheme version
+    pub fn new_verifier_with_version(public_key: PublicKey, version: SchemeVersion) -> Self {
+        Secp256k1Keccak {
+            secret_key: None,
+            secret_scalar: None,
+            public_key,
+            version,
+            hash_mode: HashMode::Keccak256,
             ctx_gen: &ECMULT_GEN_CONTEXT,
             ctx_mul: &ECMULT_CONTEXT,
         }
     }
 
+    // Create an instance that only verifies proofs of the given public key and hash mode
+    pub fn new_verifier_with_hash(public_key: PublicKey, hash_mode: HashMode) -> Self {
+        let mut vrf = Self::new_verifier(public_key);
+        vrf.hash_mode = hash_mode;
+        vrf
+    }
+
     // Hash to curve with prefix
     // HASH_TO_CURVE_HASH_PREFIX = 1
     pub fn hash_to_curve_prefix(&self, alpha: &Scalar, pk: &Affine) -> Affine {
-        let mut tpk = pk.clone();
+        let mut tpk = *pk;
         tpk.x.normalize();
         tpk.y.normalize();
         let packed = [
@@ -56,30 +293,19 @@ impl ECVRF<'_> {
         .concat();
         let mut rv = new_candidate_point(&packed);
         while !is_on_curve(&rv) {
-            rv = new_candidate_point(&rv.x.b32().to_vec());
+            rv = new_candidate_point(rv.x.b32().as_ref());
         }
         rv
     }
 
     // Hash to curve
+    // Since version 2 alpha is tagged: keccak256(SUITE_STRING || 0x01 || alpha)
     pub fn hash_to_curve(&self, alpha: &Scalar, y: Option<&Affine>) -> Affine {
-        let mut r = Jacobian::default();
-        self.ctx_gen.ecmult_gen(&mut r, alpha);
-        let mut p = Affine::default();
-        match y {
-            Some(v) => {
-                r = r.add_ge(v);
-                r
-            }
-            None => r,
-        };
-        p.set_gej(&r);
-        p.x.normalize();
-        p.y.normalize();
-        p
+        helper::hash_to_curve(self.ctx_gen, self.version, alpha, y)
     }
 
-    // Hash point to Scalar
+    // Hash point to Scalar mod the group order, they're hashed in HASH_POINTS_ORDER
+    // Since version 2 points are prefixed by SUITE_STRING || 0x02
     pub fn hash_points(
         &self,
         g: &Affine,
@@ -89,22 +315,34 @@ impl ECVRF<'_> {
         kg: &Affine,
         kh: &Affine,
     ) -> Scalar {
-        let mut output = [0u8; 32];
-        let mut hasher = Keccak::v256();
-        let all_points = [g, h, pk, gamma, kg, kh];
-        for point in all_points {
+        let points = ChallengePoints {
+            g,
+            h,
+            pk,
+            gamma,
+            kg,
+            kh,
+        };
+        self.hash_point_slice(&points.ordered())
+    }
+
+    // hash_points over any number of points, VrfSuite::hash_points uses it directly
+    fn hash_point_slice(&self, points: &[Affine]) -> Scalar {
+        let mut hasher = ModeHasher::new(self.hash_mode);
+        if self.version == SchemeVersion::V2 {
+            hasher.update(SUITE_STRING);
+            hasher.update(&[HASH_POINTS_DOMAIN]);
+        }
+        for point in points {
             hasher.update(point.x.b32().as_ref());
             hasher.update(point.y.b32().as_ref());
         }
-
-        hasher.finalize(&mut output);
-        let mut r = Scalar::default();
-        r.set_b32(&output).unwrap_u8();
-        r
+        scalar_from_bytes_reduced(&hasher.finalize())
     }
 
     // Hash points with prefix
     // SCALAR_FROM_CURVE_POINTS_HASH_PREFIX = 2
+    // Overflow is an error rather than reduced, the contract compares c as a plain uint256
     pub fn hash_points_prefix(
         &self,
         hash: &Affine,
@@ -112,7 +350,7 @@ impl ECVRF<'_> {
         gamma: &Affine,
         u_witness: &[u8; 20],
         v: &Affine,
-    ) -> Scalar {
+    ) -> Result<Scalar, VrfError> {
         let mut output = [0u8; 32];
         let mut hasher = Keccak::v256();
         let all_points = [hash, pk, gamma, v];
@@ -123,27 +361,26 @@ impl ECVRF<'_> {
         }
         hasher.update(u_witness);
         hasher.finalize(&mut output);
-        let mut r = Scalar::default();
-        r.set_b32(&output).unwrap_u8();
-        r
+        scalar_from_b32(&output)
     }
 
     // We use this method to prove a randomness for L1 smart contract
     // This prover was optimized for on-chain verification
     // u_witness is a represent of u, used ecrecover to minimize gas cost
     // we're also add projective EC add to make the proof compatible with
-    // on-chain verifier.
-    pub fn prove_contract(self, alpha: &Scalar) -> ECVRFContractProof {
+    // on-chain verifier. It always hashes with keccak256, whatever the hash mode is
+    #[cfg(feature = "std")]
+    pub fn prove_contract(self, alpha: &Scalar) -> Result<ECVRFContractProof, VrfError> {
+        let secret_key = self.secret_scalar()?;
         let mut pub_affine: Affine = self.public_key.into();
-        let mut secret_key: Scalar = self.secret_key.into();
         pub_affine.x.normalize();
         pub_affine.y.normalize();
 
         // On-chain compatible HASH_TO_CURVE_PREFIX
         let h = self.hash_to_curve_prefix(alpha, &pub_affine);
 
-        // gamma = H * sk
-        let gamma = ecmult(self.ctx_mul, &h, &secret_key);
+        // gamma = H * sk, secret input
+        let gamma = ecmult_ct(self.ctx_mul, &h, &secret_key);
 
         // k = random()
         // We need to make sure that k < GROUP_ORDER
@@ -151,9 +388,10 @@ impl ECVRF<'_> {
         while scalar_is_gte(&k, &GROUP_ORDER) || k.is_zero() {
             k = randomize();
         }
+        let k = SecretScalar::new(k);
 
-        // Calculate k * G = u
-        let kg = ecmult_gen(self.ctx_gen, &k);
+        // Calculate k * G = u, secret input
+        let kg = ecmult_gen_ct(self.ctx_gen, &k);
         // U = c * pk + s * G
         // u_witness = ecrecover(c * pk + s * G)
         // this value equal to address(keccak256(U))
@@ -161,18 +399,17 @@ impl ECVRF<'_> {
         // https://ethresear.ch/t/you-can-kinda-abuse-ecrecover-to-do-ecmul-in-secp256k1-today/2384
         let u_witness = calculate_witness_address(&kg);
 
-        // Calculate k * H = v
-        let kh = ecmult(self.ctx_mul, &h, &k);
+        // Calculate k * H = v, secret input
+        let kh = ecmult_ct(self.ctx_mul, &h, &k);
 
         // c = ECVRF_hash_points_prefix(H, pk, gamma, u_witness, k * H)
-        let c = self.hash_points_prefix(&h, &pub_affine, &gamma, &u_witness, &kh);
+        let c = self.hash_points_prefix(&h, &pub_affine, &gamma, &u_witness, &kh)?;
 
         // s = (k - c * sk)
         // Based on Schnorr signature
-        let mut neg_c = c.clone();
+        let mut neg_c = c;
         neg_c.cond_neg_assign(1.into());
-        let s = k + neg_c * secret_key;
-        secret_key.clear();
+        let s = *k.expose() + neg_c * *secret_key.expose();
 
         // Gamma witness
         // witness_gamma = gamma * c
@@ -193,24 +430,61 @@ impl ECVRF<'_> {
         let mut inverse_z = v.z.inv();
         inverse_z.normalize();
 
-        ECVRFContractProof {
+        Ok(ECVRFContractProof {
             pk: self.public_key,
             gamma,
             c,
             s,
-            y: keccak256_affine_scalar(&gamma),
+            y: keccak256_affine_scalar(&gamma)?,
             alpha: *alpha,
             witness_address: address_to_scalar(&u_witness),
             witness_gamma,
             witness_hash,
             inverse_z,
-        }
+        })
     }
 
     // Ordinary prover
-    pub fn prove(&self, alpha: &Scalar) -> ECVRFProof {
+    #[cfg(feature = "std")]
+    pub fn prove(&self, alpha: &Scalar) -> Result<ECVRFProof, VrfError> {
+        self.prove_with_rng(alpha, &mut rand::thread_rng())
+    }
+
+    // Ordinary prover with k drawn from the given rng, a seeded rng yields the same proof
+    // The rng must be cryptographically secure, a predictable k leaks the secret key
+    pub fn prove_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        alpha: &Scalar,
+        rng: &mut R,
+    ) -> Result<ECVRFProof, VrfError> {
+        // k = random(), randomize_with() makes sure that k < GROUP_ORDER
+        let mut k = randomize_with(rng);
+        while k.is_zero() {
+            k = randomize_with(rng);
+        }
+        self.prove_with_nonce(alpha, SecretScalar::new(k))
+    }
+
+    // Prove each alpha on the rayon thread pool, proofs are in the order of alphas
+    // Nonces come from thread_rng(), it's thread local so threads don't share it
+    #[cfg(feature = "parallel")]
+    pub fn prove_many(&self, alphas: &[Scalar]) -> Result<Vec<ECVRFProof>, VrfError> {
+        use rayon::prelude::*;
+        alphas.par_iter().map(|alpha| self.prove(alpha)).collect()
+    }
+
+    // Deterministic prover, k is derived from secret key and alpha (RFC 6979)
+    // the same key and alpha always yield the same proof
+    pub fn prove_deterministic(&self, alpha: &Scalar) -> Result<ECVRFProof, VrfError> {
+        let secret_key = self.secret_scalar()?;
+        let k = SecretScalar::new(rfc6979_nonce(secret_key.expose(), alpha));
+        self.prove_with_nonce(alpha, k)
+    }
+
+    // Every multiplication by the secret key or k is constant time (ecmult_ct, ecmult_gen_ct)
+    fn prove_with_nonce(&self, alpha: &Scalar, k: SecretScalar) -> Result<ECVRFProof, VrfError> {
+        let secret_key = self.secret_scalar()?;
         let mut pub_affine: Affine = self.public_key.into();
-        let mut secret_key: Scalar = self.secret_key.into();
         pub_affine.x.normalize();
         pub_affine.y.normalize();
 
@@ -218,91 +492,319 @@ impl ECVRF<'_> {
         let h = self.hash_to_curve(alpha, Some(&pub_affine));
 
         // gamma = H * secret_key
-        let gamma = ecmult(self.ctx_mul, &h, &secret_key);
-
-        // k = random()
-        // We need to make sure that k < GROUP_ORDER
-        let mut k = randomize();
-        while scalar_is_gte(&k, &GROUP_ORDER) || k.is_zero() {
-            k = randomize();
-        }
+        let gamma = ecmult_ct(self.ctx_mul, &h, &secret_key);
 
         // Calculate k * G <=> u
-        let kg = ecmult_gen(self.ctx_gen, &k);
+        let kg = ecmult_gen_ct(self.ctx_gen, &k);
 
         // Calculate k * H <=> v
-        let kh = ecmult(self.ctx_mul, &h, &k);
+        let kh = ecmult_ct(self.ctx_mul, &h, &k);
 
         // c = ECVRF_hash_points(G, H, public_key, gamma, k * G, k * H)
         let c = self.hash_points(&AFFINE_G, &h, &pub_affine, &gamma, &kg, &kh);
 
         // s = (k - c * secret_key) mod p
-        let mut neg_c = c.clone();
+        let mut neg_c = c;
         neg_c.cond_neg_assign(1.into());
-        let s = k + neg_c * secret_key;
-        secret_key.clear();
+        let s = *k.expose() + neg_c * *secret_key.expose();
 
-        // y = keccak256(gama.encode())
-        let y = keccak256_affine_scalar(&gamma);
+        // y = hash(gama.encode())
+        let y = hash_affine_scalar(self.hash_mode, &gamma);
 
-        ECVRFProof::new(gamma, c, s, y, self.public_key)
+        Ok(ECVRFProof::new_with_hash(
+            gamma,
+            c,
+            s,
+            y,
+            self.public_key,
+            self.hash_mode,
+        ))
+    }
+
+    // ECDSA signature of keccak256(network || epoch || y) for consumers that don't verify
+    // VRF proofs, RecoverableSignature::recover_address() returns the oracle address
+    #[cfg(feature = "std")]
+    pub fn sign_epoch(
+        &self,
+        network: i64,
+        epoch: i64,
+        y: &[u8; 32],
+    ) -> Result<RecoverableSignature, VrfError> {
+        let secret_key = self.secret_key.ok_or(VrfError::MissingSecretKey)?;
+        let message = libsecp256k1::Message::parse(&epoch_digest(network, epoch, y));
+        let (signature, recovery_id) = libsecp256k1::sign(&message, &secret_key);
+        Ok(RecoverableSignature {
+            signature,
+            recovery_id,
+        })
+    }
+
+    // ECDSA signature of keccak256(payload), the server signs its responses with it so
+    // cached or relayed ones can be checked with verify_response_signature()
+    #[cfg(feature = "std")]
+    pub fn sign_payload(&self, payload: &[u8]) -> Result<RecoverableSignature, VrfError> {
+        let secret_key = self.secret_key.ok_or(VrfError::MissingSecretKey)?;
+        let message = libsecp256k1::Message::parse(&payload_digest(payload));
+        let (signature, recovery_id) = libsecp256k1::sign(&message, &secret_key);
+        Ok(RecoverableSignature {
+            signature,
+            recovery_id,
+        })
+    }
+
+    // Prove over an arbitrary byte string, e.g. a block hash
+    // alpha = keccak256(alpha) mod n, this is the recommended entry point,
+    // the Scalar based methods are kept for low level use
+    #[cfg(feature = "std")]
+    pub fn prove_bytes(&self, alpha: &[u8]) -> Result<ECVRFProof, VrfError> {
+        self.prove(&keccak256_vec_scalar(alpha))
+    }
+
+    // Verify a proof that was created by prove_bytes()
+    pub fn verify_bytes(&self, alpha: &[u8], vrf_proof: &ECVRFProof) -> Result<bool, VrfError> {
+        self.verify(&keccak256_vec_scalar(alpha), vrf_proof)
     }
 
     // Ordinary verifier
-    pub fn verify(self, alpha: &Scalar, vrf_proof: &ECVRFProof) -> bool {
+    pub fn verify(&self, alpha: &Scalar, vrf_proof: &ECVRFProof) -> Result<bool, VrfError> {
+        self.verify_outcome(alpha, vrf_proof)
+            .map(|outcome| outcome == VerifyOutcome::Valid)
+    }
+
+    // Ordinary verifier that tells which check failed, e.g. to settle a dispute
+    pub fn verify_detailed(&self, alpha: &Scalar, vrf_proof: &ECVRFProof) -> VerifyOutcome {
+        self.verify_outcome(alpha, vrf_proof)
+            .unwrap_or(VerifyOutcome::InvalidPoint)
+    }
+
+    // Invalid points are errors so verify() can tell the public key from gamma
+    fn verify_outcome(
+        &self,
+        alpha: &Scalar,
+        vrf_proof: &ECVRFProof,
+    ) -> Result<VerifyOutcome, VrfError> {
         let mut pub_affine: Affine = self.public_key.into();
         pub_affine.x.normalize();
         pub_affine.y.normalize();
 
-        assert!(pub_affine.is_valid_var());
-        assert!(vrf_proof.gamma.is_valid_var());
+        // Infinity has no coordinates, y of an infinity gamma would be a hash of zeros
+        if pub_affine.is_infinity() || !pub_affine.is_valid_var() {
+            return Err(VrfError::InvalidPublicKey);
+        }
+        if vrf_proof.gamma.is_infinity() || !vrf_proof.gamma.is_valid_var() {
+            return Err(VrfError::InvalidGamma);
+        }
 
         // H = ECVRF_hash_to_curve(alpha, pk)
         let h = self.hash_to_curve(alpha, Some(&pub_affine));
-        let mut jh = Jacobian::default();
-        jh.set_ge(&h);
+        let (u, v) = self.witnesses(&h, &Jacobian::from_ge(&pub_affine), vrf_proof);
+
+        Ok(self.check_challenge(
+            &h,
+            &pub_affine,
+            vrf_proof,
+            &jacobian_to_affine(&u),
+            &jacobian_to_affine(&v),
+        ))
+    }
+
+    // Verifier of proofs created by prove_contract, it checks the relation of the on-chain
+    // verifier: H is HASH_TO_CURVE_PREFIX, U is compared by its address and y is keccak256
+    // of gamma, whatever the hash mode is
+    #[cfg(feature = "std")]
+    pub fn verify_contract(
+        &self,
+        alpha: &Scalar,
+        vrf_proof: &ECVRFProof,
+    ) -> Result<bool, VrfError> {
+        let mut pub_affine: Affine = self.public_key.into();
+        pub_affine.x.normalize();
+        pub_affine.y.normalize();
+
+        if pub_affine.is_infinity() || !pub_affine.is_valid_var() {
+            return Err(VrfError::InvalidPublicKey);
+        }
+        if vrf_proof.gamma.is_infinity() || !vrf_proof.gamma.is_valid_var() {
+            return Err(VrfError::InvalidGamma);
+        }
+        let mut gamma = vrf_proof.gamma;
+        gamma.x.normalize();
+        gamma.y.normalize();
+
+        let h = self.hash_to_curve_prefix(alpha, &pub_affine);
+        let (u, v) = self.witnesses(&h, &Jacobian::from_ge(&pub_affine), vrf_proof);
+        let u_witness = calculate_witness_address(&jacobian_to_affine(&u));
+        let computed_c =
+            self.hash_points_prefix(&h, &pub_affine, &gamma, &u_witness, &jacobian_to_affine(&v))?;
+        let computed_y = keccak256_affine_scalar(&gamma)?;
+        Ok(ct_eq(&computed_c, &vrf_proof.c) & ct_eq(&computed_y, &vrf_proof.y))
+    }
+
+    // Compute the witnesses U and V of a proof in Jacobian coordinates
+    fn witnesses(
+        &self,
+        h: &Affine,
+        pub_jacobian: &Jacobian,
+        vrf_proof: &ECVRFProof,
+    ) -> (Jacobian, Jacobian) {
+        let zero = Scalar::from_int(0);
 
         // U = c * pk + s * G
         //   = c * sk * G + (k - c * sk) * G
         //   = k * G
         let mut u = Jacobian::default();
-        let pub_jacobian = Jacobian::from_ge(&pub_affine);
         self.ctx_mul
-            .ecmult(&mut u, &pub_jacobian, &vrf_proof.c, &vrf_proof.s);
-
-        // Gamma witness
-        let witness_gamma = ecmult(self.ctx_mul, &vrf_proof.gamma, &vrf_proof.c);
-        // Hash witness
-        let witness_hash = ecmult(self.ctx_mul, &h, &vrf_proof.s);
+            .ecmult(&mut u, pub_jacobian, &vrf_proof.c, &vrf_proof.s);
 
-        // V = c * gamma + s * H = witness_gamma + witness_hash
+        // V = c * gamma + s * H
         //   = c * sk * H + (k - c * sk) * H
-        //   = k *. H
-        let v = Jacobian::from_ge(&witness_gamma).add_ge(&witness_hash);
+        //   = k * H
+        // H isn't the generator so s * H needs its own multiplication
+        let mut c_gamma = Jacobian::default();
+        self.ctx_mul.ecmult(
+            &mut c_gamma,
+            &Jacobian::from_ge(&vrf_proof.gamma),
+            &vrf_proof.c,
+            &zero,
+        );
+        let mut s_h = Jacobian::default();
+        self.ctx_mul
+            .ecmult(&mut s_h, &Jacobian::from_ge(h), &vrf_proof.s, &zero);
+
+        (u, c_gamma.add_var(&s_h, None))
+    }
+
+    // Verify a hash chain of epochs starting from genesis alpha
+    // alpha[0] = genesis_alpha, alpha[i + 1] = proofs[i].next_alpha()
+    pub fn verify_chain(
+        &self,
+        genesis_alpha: &Scalar,
+        proofs: &[ECVRFProof],
+    ) -> Result<bool, VrfError> {
+        let mut alpha = *genesis_alpha;
+        for vrf_proof in proofs {
+            if !self.verify(&alpha, vrf_proof)? {
+                return Ok(false);
+            }
+            alpha = vrf_proof.next_alpha();
+        }
+        Ok(true)
+    }
+
+    // Verify many proofs under this public key, the result of each item is
+    // reported separately. Invalid items are reported as false.
+    // It shares the public key preparation and normalizes every U, V with
+    // a single field inversion instead of four inversions per proof.
+    pub fn verify_batch(&self, items: &[(Scalar, ECVRFProof)]) -> Vec<bool> {
+        let mut pub_affine: Affine = self.public_key.into();
+        pub_affine.x.normalize();
+        pub_affine.y.normalize();
+        if pub_affine.is_infinity() || !pub_affine.is_valid_var() {
+            return vec![false; items.len()];
+        }
+        let pub_jacobian = Jacobian::from_ge(&pub_affine);
+
+        let mut hashes = Vec::with_capacity(items.len());
+        let mut witnesses = Vec::with_capacity(items.len() * 2);
+        for (alpha, vrf_proof) in items {
+            // Witnesses of an invalid gamma can't be computed, ecmult asserts on infinity
+            if vrf_proof.gamma.is_infinity() || !vrf_proof.gamma.is_valid_var() {
+                hashes.push(None);
+                continue;
+            }
+            // H = ECVRF_hash_to_curve(alpha, pk)
+            let h = self.hash_to_curve(alpha, Some(&pub_affine));
+            let (u, v) = self.witnesses(&h, &pub_jacobian, vrf_proof);
+            hashes.push(Some(h));
+            witnesses.push(u);
+            witnesses.push(v);
+        }
+
+        let witnesses = jacobians_to_affines(&witnesses);
+        let mut uvs = witnesses.chunks(2);
+        items
+            .iter()
+            .zip(hashes.iter())
+            .map(|((_, vrf_proof), h)| {
+                // Witnesses are only computed for items with a hash
+                let (h, uv) = match (h, h.and_then(|_| uvs.next())) {
+                    (Some(h), Some(uv)) => (h, uv),
+                    _ => return false,
+                };
+                self.check_challenge(h, &pub_affine, vrf_proof, &uv[0], &uv[1])
+                    == VerifyOutcome::Valid
+            })
+            .collect()
+    }
+
+    // Recompute c and y from the witnesses and compare them to the proof
+    fn check_challenge(
+        &self,
+        h: &Affine,
+        pub_affine: &Affine,
+        vrf_proof: &ECVRFProof,
+        u: &Affine,
+        v: &Affine,
+    ) -> VerifyOutcome {
+        // A proof hashed with another function has another output for the same alpha
+        if vrf_proof.hash_mode != self.hash_mode {
+            return VerifyOutcome::ChallengeMismatch;
+        }
 
         // c_prime = ECVRF_hash_points(G, H, pk, gamma, U, V)
-        let computed_c = self.hash_points(
-            &AFFINE_G,
-            &h,
-            &pub_affine,
-            &vrf_proof.gamma,
-            &jacobian_to_affine(&u),
-            &jacobian_to_affine(&v),
-        );
+        let computed_c = self.hash_points(&AFFINE_G, h, pub_affine, &vrf_proof.gamma, u, v);
 
-        // y = keccak256(gama.encode())
-        let computed_y = keccak256_affine_scalar(&vrf_proof.gamma);
+        // y = hash(gama.encode())
+        let computed_y = hash_affine_scalar(self.hash_mode, &vrf_proof.gamma);
 
         // computed values should equal to the real one
-        computed_c.eq(&vrf_proof.c) && computed_y.eq(&vrf_proof.y)
+        // both comparisons are evaluated in constant time, without short-circuit
+        let c_matches = ct_eq(&computed_c, &vrf_proof.c);
+        let y_matches = ct_eq(&computed_y, &vrf_proof.y);
+        if !c_matches {
+            VerifyOutcome::ChallengeMismatch
+        } else if !y_matches {
+            VerifyOutcome::OutputMismatch
+        } else {
+            VerifyOutcome::Valid
+        }
+    }
+}
+
+impl Drop for Secp256k1Keccak<'_> {
+    // Scrub the secret key and its cached scalar so they do not linger in freed memory
+    // SecretKey is Copy and hides its scalar, so we overwrite the whole value
+    // with a volatile write, like Scalar::clear() does
+    fn drop(&mut self) {
+        if let Some(secret_key) = self.secret_key.as_mut() {
+            unsafe {
+                core::ptr::write_volatile(secret_key, SecretKey::default());
+            }
+        }
+        if let Some(secret_scalar) = self.secret_scalar.as_mut() {
+            secret_scalar.clear();
+        }
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{helper::randomize, ECVRF};
-    use libsecp256k1::SecretKey;
-    use rand::thread_rng;
+    use crate::{
+        ecproof::ECVRFProof,
+        helper::{
+            keccak256_affine, keccak256_affine_compressed, keccak256_vec_scalar, randomize,
+            scalar_from_bytes_reduced,
+        },
+        ChallengePoint, HashMode, SchemeVersion, VerifyOutcome, VrfError, ECVRF, HASH_POINTS_ORDER,
+    };
+    use libsecp256k1::{
+        curve::{Affine, Scalar},
+        PublicKey, SecretKey,
+    };
+    use rand::{thread_rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+    use std::mem::ManuallyDrop;
 
     #[test]
     fn we_should_able_to_prove_and_verify() {
@@ -316,11 +818,454 @@ mod tests {
         let alpha = randomize();
 
         //Prove
-        let r1 = ecvrf.prove(&alpha);
+        let r1 = ecvrf.prove(&alpha).unwrap();
 
         // Verify
-        let r2 = ecvrf.verify(&alpha, &r1);
+        assert!(ecvrf.verify(&alpha, &r1).unwrap());
+    }
 
-        assert!(r2);
+    #[test]
+    fn prove_deterministic_is_reproducible() {
+        let secret_key = SecretKey::random(&mut thread_rng());
+        let ecvrf = ECVRF::new(secret_key);
+        let alpha = randomize();
+
+        let r1 = ecvrf.prove_deterministic(&alpha).unwrap();
+        let r2 = ecvrf.prove_deterministic(&alpha).unwrap();
+
+        assert_eq!(r1.gamma.x.b32(), r2.gamma.x.b32());
+        assert_eq!(r1.gamma.y.b32(), r2.gamma.y.b32());
+        assert_eq!(r1.c.b32(), r2.c.b32());
+        assert_eq!(r1.s.b32(), r2.s.b32());
+        assert!(ecvrf.verify(&alpha, &r1).unwrap());
+    }
+
+    #[test]
+    fn secret_key_is_scrubbed_on_drop() {
+        let secret_key = SecretKey::random(&mut thread_rng());
+        let mut ecvrf = ManuallyDrop::new(ECVRF::new(secret_key));
+        assert_eq!(
+            ecvrf.secret_key.unwrap().serialize(),
+            secret_key.serialize()
+        );
+        assert_eq!(ecvrf.secret_scalar.unwrap(), secret_key.into());
+
+        // Run the destructor but keep the memory around to inspect it
+        unsafe { ManuallyDrop::drop(&mut ecvrf) };
+        assert_ne!(
+            ecvrf.secret_key.unwrap().serialize(),
+            secret_key.serialize()
+        );
+        assert_eq!(
+            ecvrf.secret_key.unwrap().serialize(),
+            SecretKey::default().serialize()
+        );
+        assert!(ecvrf.secret_scalar.unwrap().is_zero());
+    }
+
+    #[test]
+    fn verifier_only_instance_verifies_but_can_not_prove() {
+        let secret_key = SecretKey::random(&mut thread_rng());
+        let oracle = ECVRF::new(secret_key);
+        let verifier = ECVRF::new_verifier(PublicKey::from_secret_key(&secret_key));
+        let alpha = randomize();
+
+        let proof = oracle.prove(&alpha).unwrap();
+        assert!(verifier.verify(&alpha, &proof).unwrap());
+        assert!(matches!(
+            verifier.prove(&alpha),
+            Err(VrfError::MissingSecretKey)
+        ));
+        assert!(matches!(
+            verifier.prove_deterministic(&alpha),
+            Err(VrfError::MissingSecretKey)
+        ));
+        assert!(matches!(
+            verifier.prove_contract(&alpha),
+            Err(VrfError::MissingSecretKey)
+        ));
+    }
+
+    #[test]
+    fn prove_and_verify_byte_string_alpha() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let block_hash = [0xabu8; 40];
+
+        let proof = ecvrf.prove_bytes(&block_hash).unwrap();
+        assert!(ecvrf.verify_bytes(&block_hash, &proof).unwrap());
+        assert!(!ecvrf.verify_bytes(&block_hash[..39], &proof).unwrap());
+    }
+
+    #[test]
+    fn verify_batch_reports_each_item() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let mut items: Vec<_> = (0..8)
+            .map(|_| {
+                let alpha = randomize();
+                (alpha, ecvrf.prove(&alpha).unwrap())
+            })
+            .collect();
+        // Tamper the 4th proof
+        items[3].0 = randomize();
+
+        let results = ecvrf.verify_batch(&items);
+        for (i, (alpha, proof)) in items.iter().enumerate() {
+            assert_eq!(results[i], ecvrf.verify(alpha, proof).unwrap());
+            assert_eq!(results[i], i != 3);
+        }
+    }
+
+    #[test]
+    fn contract_proof_is_verified_like_on_chain() {
+        let secret_key = SecretKey::random(&mut thread_rng());
+        let alpha = randomize();
+        let contract_proof = ECVRF::new(secret_key).prove_contract(&alpha).unwrap();
+        let proof = ECVRFProof::new(
+            contract_proof.gamma,
+            contract_proof.c,
+            contract_proof.s,
+            contract_proof.y,
+            contract_proof.pk,
+        );
+        let verifier = ECVRF::new_verifier(contract_proof.pk);
+        assert!(verifier.verify_contract(&alpha, &proof).unwrap());
+        assert!(!verifier.verify_contract(&randomize(), &proof).unwrap());
+        // Ordinary proofs are hashed to the curve in another way
+        assert!(!verifier.verify(&alpha, &proof).unwrap());
+        let ordinary = ECVRF::new(secret_key).prove(&alpha).unwrap();
+        assert!(!verifier.verify_contract(&alpha, &ordinary).unwrap());
+    }
+
+    #[test]
+    fn seeded_rng_yields_the_same_proof() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let alpha = randomize();
+        let prove = |seed: u64| {
+            ecvrf
+                .prove_with_rng(&alpha, &mut ChaCha20Rng::seed_from_u64(seed))
+                .unwrap()
+        };
+        let proof = prove(7);
+        assert!(ecvrf.verify(&alpha, &proof).unwrap());
+        assert_eq!(proof.to_bytes(), prove(7).to_bytes());
+        // Another nonce changes c and s but not the output
+        let other = prove(8);
+        assert_ne!(proof.c, other.c);
+        assert_eq!(proof.y, other.y);
+    }
+
+    #[test]
+    fn verify_detailed_tells_why_a_proof_is_rejected() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let alpha = randomize();
+        let proof = ecvrf.prove(&alpha).unwrap();
+        assert_eq!(ecvrf.verify_detailed(&alpha, &proof), VerifyOutcome::Valid);
+
+        let mut tampered = proof;
+        tampered.c += Scalar::from_int(1);
+        assert_eq!(
+            ecvrf.verify_detailed(&alpha, &tampered),
+            VerifyOutcome::ChallengeMismatch
+        );
+        assert_eq!(ecvrf.verify(&alpha, &tampered), Ok(false));
+
+        let mut tampered = proof;
+        tampered.y += Scalar::from_int(1);
+        assert_eq!(
+            ecvrf.verify_detailed(&alpha, &tampered),
+            VerifyOutcome::OutputMismatch
+        );
+        assert_eq!(ecvrf.verify(&alpha, &tampered), Ok(false));
+
+        let mut tampered = proof;
+        tampered.gamma = Affine {
+            infinity: true,
+            ..Affine::default()
+        };
+        assert_eq!(
+            ecvrf.verify_detailed(&alpha, &tampered),
+            VerifyOutcome::InvalidPoint
+        );
+    }
+
+    #[test]
+    fn infinity_gamma_is_rejected() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let alpha = randomize();
+        let mut forged = ecvrf.prove(&alpha).unwrap();
+        forged.gamma = Affine {
+            infinity: true,
+            ..Affine::default()
+        };
+        // y an unchecked verifier would derive from the coordinates of infinity
+        forged.y = keccak256_vec_scalar(&[0u8; 64]);
+
+        assert_eq!(ecvrf.verify(&alpha, &forged), Err(VrfError::InvalidGamma));
+        assert_eq!(
+            forged.verify_with_pubkey(&alpha, &forged.public_key()),
+            Err(VrfError::InvalidGamma)
+        );
+        // Other items of a batch are still verified
+        let honest = ecvrf.prove(&alpha).unwrap();
+        assert_eq!(
+            ecvrf.verify_batch(&[(alpha, honest), (alpha, forged), (alpha, honest)]),
+            vec![true, false, true]
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn prove_many_keeps_the_order_of_alphas() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let alphas: Vec<Scalar> = (0..32).map(|_| randomize()).collect();
+        let proofs = ecvrf.prove_many(&alphas).unwrap();
+        assert_eq!(proofs.len(), alphas.len());
+        for (alpha, proof) in alphas.iter().zip(proofs.iter()) {
+            assert!(ecvrf.verify(alpha, proof).unwrap());
+        }
+    }
+
+    #[test]
+    fn verify_chain_of_epochs() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let genesis_alpha = randomize();
+
+        let mut proofs = Vec::new();
+        let mut alpha = genesis_alpha;
+        for _ in 0..5 {
+            let proof = ecvrf.prove(&alpha).unwrap();
+            alpha = proof.next_alpha();
+            proofs.push(proof);
+        }
+        assert!(ecvrf.verify_chain(&genesis_alpha, &proofs).unwrap());
+
+        // A valid proof over an alpha outside of the chain breaks it
+        proofs[2] = ecvrf.prove(&randomize()).unwrap();
+        assert!(!ecvrf.verify_chain(&genesis_alpha, &proofs).unwrap());
+    }
+
+    // n * G for n in 1..=6, they're distinct so any reorder changes the digest
+    fn multiples_of_g() -> [Affine; 6] {
+        core::array::from_fn(|i| {
+            let mut n = [0u8; 32];
+            n[31] = i as u8 + 1;
+            let mut point: Affine =
+                PublicKey::from_secret_key(&SecretKey::parse(&n).unwrap()).into();
+            point.x.normalize();
+            point.y.normalize();
+            point
+        })
+    }
+
+    #[test]
+    fn hash_points_order_is_pinned() {
+        assert_eq!(
+            HASH_POINTS_ORDER,
+            [
+                ChallengePoint::G,
+                ChallengePoint::H,
+                ChallengePoint::PublicKey,
+                ChallengePoint::Gamma,
+                ChallengePoint::Kg,
+                ChallengePoint::Kh,
+            ]
+        );
+        let [g, h, pk, gamma, kg, kh] = multiples_of_g();
+        // keccak256(SUITE_STRING || 0x02 || x || y of each point) mod n and the untagged
+        // keccak256 of version 1, computed independently of this crate
+        for (version, digest) in [
+            (
+                SchemeVersion::V2,
+                "9c4076e0875dd0317dd17465360d0652784cf48556b924465d5678d3d748dbd3",
+            ),
+            (
+                SchemeVersion::V1,
+                "549746b81bd5b45dce2ce51bda65189026ba697966ec0f31029cac5d65dfd36b",
+            ),
+        ] {
+            let ecvrf = ECVRF::new_with_version(SecretKey::random(&mut thread_rng()), version);
+            let c = ecvrf.hash_points(&g, &h, &pk, &gamma, &kg, &kh);
+            assert_eq!(hex::encode(c.b32()), digest);
+            assert_ne!(ecvrf.hash_points(&g, &pk, &h, &gamma, &kg, &kh), c);
+        }
+    }
+
+    #[test]
+    fn verify_known_answer_proof() {
+        let secret_key = SecretKey::parse_slice(
+            &hex::decode("1e99423a4ed27608a15a2616a2b0e9e52ced330ac530edcc32c8ffc6a526aedd")
+                .unwrap(),
+        )
+        .unwrap();
+        let mut alpha = Scalar::default();
+        alpha
+            .set_b32(
+                &hex::decode("eabe8358ea6cedc4d3759a48648631369b9fb6d1c26da3adbc26eff564e032c4")
+                    .unwrap()
+                    .try_into()
+                    .unwrap(),
+            )
+            .unwrap_u8();
+        // Proof was generated by prove_deterministic() before V was refactored
+        let proof = ECVRFProof::from_bytes(
+            &hex::decode(concat!(
+                "03a520350b7ef1969c6eaedcd68db53c999da4424a7d26b15c9648a6967a7d1099",
+                "d46a7a81e679a32a0f55279928ed74e80c3d490f7b3a0f4a5ed030cb5e468b69",
+                "ad9dcc09df9ac84d11c1e22a2048812bf454bffb94eb67b11fb94d0242e2b64f",
+                "f9f6d4db01fddf50289b916d8c37faa8777ec8b1983777017ce17cda889dfd94",
+                "03f028892bad7ed57d2fb57bf33081d5cfcf6f9ed3d3d7f159c2e2fff579dc341a",
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let ecvrf = ECVRF::new_with_version(secret_key, SchemeVersion::V1);
+        assert!(ecvrf.verify(&alpha, &proof).unwrap());
+        assert_eq!(ecvrf.verify_batch(&[(alpha, proof)]), vec![true]);
+        assert_eq!(
+            ecvrf.prove_deterministic(&alpha).unwrap().to_bytes(),
+            proof.to_bytes()
+        );
+
+        let mut tampered = proof;
+        tampered.s += Scalar::from_int(1);
+        assert!(!ecvrf.verify(&alpha, &tampered).unwrap());
+    }
+
+    #[test]
+    fn scheme_versions_are_domain_separated() {
+        let secret_key = SecretKey::random(&mut thread_rng());
+        let tagged = ECVRF::new(secret_key);
+        let untagged = ECVRF::new_with_version(secret_key, SchemeVersion::V1);
+        let alpha = randomize();
+
+        let proof = tagged.prove(&alpha).unwrap();
+        assert!(tagged.verify(&alpha, &proof).unwrap());
+        assert!(!untagged.verify(&alpha, &proof).unwrap());
+
+        let legacy_proof = untagged.prove(&alpha).unwrap();
+        assert!(untagged.verify(&alpha, &legacy_proof).unwrap());
+        assert!(!tagged.verify(&alpha, &legacy_proof).unwrap());
+    }
+
+    #[test]
+    fn legacy_proofs_round_trip_with_their_version() {
+        let secret_key = SecretKey::random(&mut thread_rng());
+        let public_key = PublicKey::from_secret_key(&secret_key);
+        let alpha = randomize();
+        let proof = ECVRF::new_with_version(secret_key, SchemeVersion::V1)
+            .prove(&alpha)
+            .unwrap();
+        let decoded = ECVRFProof::from_bytes(&proof.to_bytes()).unwrap();
+
+        let verifier = ECVRF::new_verifier_with_version(public_key, SchemeVersion::V1);
+        assert_eq!(verifier.version(), SchemeVersion::V1);
+        assert!(verifier.verify(&alpha, &decoded).unwrap());
+        assert!(decoded
+            .verify_with_pubkey_and_version(&alpha, &public_key, SchemeVersion::V1)
+            .unwrap());
+        assert!(
+            ECVRFProof::verify_standalone_with_version(&alpha, &decoded, SchemeVersion::V1)
+                .unwrap()
+        );
+
+        // The current version doesn't accept them
+        assert!(!ECVRF::new_verifier(public_key)
+            .verify(&alpha, &decoded)
+            .unwrap());
+        assert!(!decoded.verify_with_pubkey(&alpha, &public_key).unwrap());
+        assert!(!ECVRFProof::verify_standalone(&alpha, &decoded).unwrap());
+    }
+
+    #[test]
+    fn proofs_round_trip_in_every_hash_mode() {
+        let secret_key = SecretKey::random(&mut thread_rng());
+        let public_key = PublicKey::from_secret_key(&secret_key);
+        let alpha = randomize();
+        for hash_mode in [
+            HashMode::Keccak256,
+            HashMode::Sha256,
+            HashMode::Keccak256Compressed,
+        ] {
+            let ecvrf = ECVRF::new_with_hash(secret_key, hash_mode);
+            let verifier = ECVRF::new_verifier_with_hash(public_key, hash_mode);
+            assert_eq!(verifier.hash_mode(), hash_mode);
+            for proof in [
+                ecvrf.prove(&alpha).unwrap(),
+                ecvrf.prove_deterministic(&alpha).unwrap(),
+            ] {
+                assert_eq!(proof.hash_mode, hash_mode);
+                assert!(verifier.verify(&alpha, &proof).unwrap());
+                assert!(proof.verify_with_pubkey(&alpha, &public_key).unwrap());
+
+                let decoded = ECVRFProof::from_bytes(&proof.to_tagged_bytes()).unwrap();
+                assert_eq!(decoded.hash_mode, hash_mode);
+                assert!(verifier.verify(&alpha, &decoded).unwrap());
+                let json = serde_json::to_string(&proof).unwrap();
+                let decoded: ECVRFProof = serde_json::from_str(&json).unwrap();
+                assert!(verifier.verify(&alpha, &decoded).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn proof_of_another_hash_mode_is_rejected() {
+        let secret_key = SecretKey::random(&mut thread_rng());
+        let public_key = PublicKey::from_secret_key(&secret_key);
+        let keccak = ECVRF::new(secret_key);
+        let sha256 = ECVRF::new_with_hash(secret_key, HashMode::Sha256);
+        let alpha = randomize();
+
+        let keccak_proof = keccak.prove_deterministic(&alpha).unwrap();
+        let sha256_proof = sha256.prove_deterministic(&alpha).unwrap();
+        assert_ne!(keccak_proof.y, sha256_proof.y);
+        assert!(!keccak.verify(&alpha, &sha256_proof).unwrap());
+        assert!(!sha256.verify(&alpha, &keccak_proof).unwrap());
+        assert_eq!(
+            keccak.verify_batch(&[(alpha, keccak_proof), (alpha, sha256_proof)]),
+            vec![true, false]
+        );
+
+        // The recorded mode can't be swapped, c and y were hashed with the other one
+        let mut relabeled = sha256_proof;
+        relabeled.hash_mode = HashMode::Keccak256;
+        assert!(!keccak.verify(&alpha, &relabeled).unwrap());
+        assert!(!relabeled.verify_with_pubkey(&alpha, &public_key).unwrap());
+    }
+
+    #[test]
+    fn compressed_gamma_hash_mode_has_its_own_output() {
+        let secret_key = SecretKey::random(&mut thread_rng());
+        let keccak = ECVRF::new(secret_key);
+        let compressed = ECVRF::new_with_hash(secret_key, HashMode::Keccak256Compressed);
+        let alpha = randomize();
+
+        let keccak_proof = keccak.prove_deterministic(&alpha).unwrap();
+        let compressed_proof = compressed.prove_deterministic(&alpha).unwrap();
+        assert_eq!(keccak_proof.gamma, compressed_proof.gamma);
+        assert_ne!(keccak_proof.y, compressed_proof.y);
+        let gamma = compressed_proof.gamma;
+        assert_eq!(
+            keccak_proof.y,
+            scalar_from_bytes_reduced(&keccak256_affine(&gamma))
+        );
+        assert_eq!(
+            compressed_proof.y,
+            scalar_from_bytes_reduced(&keccak256_affine_compressed(&gamma))
+        );
+        assert!(keccak.verify(&alpha, &keccak_proof).unwrap());
+        assert!(compressed.verify(&alpha, &compressed_proof).unwrap());
+        assert!(!keccak.verify(&alpha, &compressed_proof).unwrap());
+        assert!(!compressed.verify(&alpha, &keccak_proof).unwrap());
+
+        // Both hash the points with keccak256, only y tells a relabeled proof apart
+        let decoded = ECVRFProof::from_bytes(&compressed_proof.to_tagged_bytes()).unwrap();
+        assert_eq!(decoded.hash_mode, HashMode::Keccak256Compressed);
+        let mut relabeled = compressed_proof;
+        relabeled.hash_mode = HashMode::Keccak256;
+        assert_eq!(
+            keccak.verify_detailed(&alpha, &relabeled),
+            VerifyOutcome::OutputMismatch
+        );
     }
 }
@@ -0,0 +1,164 @@
+use crate::{helper::get_address, VrfError};
+use libsecp256k1::{recover, Message, RecoveryId, Signature};
+use tiny_keccak::{Hasher, Keccak};
+
+// Size of a signature in wire format: r (32) || s (32) || v (1)
+pub const SIGNATURE_SIZE: usize = 65;
+
+// ecrecover() expects v to be 27 or 28
+const ETH_RECOVERY_OFFSET: u8 = 27;
+
+// ECDSA signature of an epoch, the signer is recovered from it like with ecrecover()
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecoverableSignature {
+    pub signature: Signature,
+    pub recovery_id: RecoveryId,
+}
+
+// keccak256(network || epoch || y), network and epoch are uint256 big-endian words
+// so it's keccak256(abi.encodePacked(uint256(network), uint256(epoch), y)) on-chain
+pub fn epoch_digest(network: i64, epoch: i64, y: &[u8; 32]) -> [u8; 32] {
+    let word = |v: i64| {
+        // Sign extended, non-negative values are the same as uint256
+        let mut buf = [if v < 0 { 0xff } else { 0 }; 32];
+        buf[24..32].copy_from_slice(&v.to_be_bytes());
+        buf
+    };
+    let mut output = [0u8; 32];
+    let mut hasher = Keccak::v256();
+    hasher.update(&word(network));
+    hasher.update(&word(epoch));
+    hasher.update(y);
+    hasher.finalize(&mut output);
+    output
+}
+
+// keccak256(payload) of a signed response, payloads are JSON so they're never mistaken
+// for the network || epoch || y of an epoch digest
+pub fn payload_digest(payload: &[u8]) -> [u8; 32] {
+    let mut output = [0u8; 32];
+    let mut hasher = Keccak::v256();
+    hasher.update(payload);
+    hasher.finalize(&mut output);
+    output
+}
+
+// Check a signed response of the server was signed by the oracle address, payload is the
+// exact string of the envelope and signature its r || s || v
+pub fn verify_response_signature(
+    payload: &[u8],
+    signature: &[u8],
+    address: &[u8; 20],
+) -> Result<bool, VrfError> {
+    Ok(RecoverableSignature::from_bytes(signature)?.recover_payload_signer(payload)? == *address)
+}
+
+impl RecoverableSignature {
+    // Encode signature as r || s || v with v = 27 + recovery id
+    pub fn to_bytes(&self) -> [u8; SIGNATURE_SIZE] {
+        let mut buf = [0u8; SIGNATURE_SIZE];
+        buf[0..64].copy_from_slice(&self.signature.serialize());
+        buf[64] = self.recovery_id.serialize() + ETH_RECOVERY_OFFSET;
+        buf
+    }
+
+    // Decode signature from its wire format, v may be 0, 1, 27 or 28
+    pub fn from_bytes(buf: &[u8]) -> Result<RecoverableSignature, VrfError> {
+        if buf.len() != SIGNATURE_SIZE {
+            return Err(VrfError::InvalidLength);
+        }
+        let v = match buf[64] {
+            v @ (0 | 1) => v,
+            v @ (27 | 28) => v - ETH_RECOVERY_OFFSET,
+            _ => return Err(VrfError::InvalidSignature),
+        };
+        Ok(RecoverableSignature {
+            signature: Signature::parse_standard_slice(&buf[0..64])
+                .map_err(|_| VrfError::InvalidSignature)?,
+            recovery_id: RecoveryId::parse(v).map_err(|_| VrfError::InvalidSignature)?,
+        })
+    }
+
+    // Ethereum address of the key that signed the given epoch
+    pub fn recover_address(
+        &self,
+        network: i64,
+        epoch: i64,
+        y: &[u8; 32],
+    ) -> Result<[u8; 20], VrfError> {
+        let message = Message::parse(&epoch_digest(network, epoch, y));
+        recover(&message, &self.signature, &self.recovery_id)
+            .map(get_address)
+            .map_err(|_| VrfError::InvalidSignature)
+    }
+
+    // Ethereum address of the key that signed the given response payload
+    pub fn recover_payload_signer(&self, payload: &[u8]) -> Result<[u8; 20], VrfError> {
+        let message = Message::parse(&payload_digest(payload));
+        recover(&message, &self.signature, &self.recovery_id)
+            .map(get_address)
+            .map_err(|_| VrfError::InvalidSignature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify_response_signature, RecoverableSignature, SIGNATURE_SIZE};
+    use crate::{helper::get_address, VrfError, ECVRF};
+    use libsecp256k1::{PublicKey, SecretKey};
+    use rand::thread_rng;
+
+    #[test]
+    fn signer_address_is_recovered() {
+        let secret_key = SecretKey::random(&mut thread_rng());
+        let address = get_address(PublicKey::from_secret_key(&secret_key));
+        let ecvrf = ECVRF::new(secret_key);
+        let signature = ecvrf.sign_epoch(56, 7, &[9u8; 32]).unwrap();
+
+        let decoded = RecoverableSignature::from_bytes(&signature.to_bytes()).unwrap();
+        assert_eq!(decoded, signature);
+        assert_eq!(decoded.recover_address(56, 7, &[9u8; 32]).unwrap(), address);
+        // Any other epoch recovers another address
+        assert_ne!(decoded.recover_address(56, 8, &[9u8; 32]).unwrap(), address);
+        assert_ne!(decoded.recover_address(97, 7, &[9u8; 32]).unwrap(), address);
+    }
+
+    #[test]
+    fn response_payload_is_verified() {
+        let secret_key = SecretKey::random(&mut thread_rng());
+        let address = get_address(PublicKey::from_secret_key(&secret_key));
+        let payload = br#"{"jsonrpc":"2.0","id":1,"result":{"epoch":7}}"#;
+        let signature = ECVRF::new(secret_key)
+            .sign_payload(payload)
+            .unwrap()
+            .to_bytes();
+        assert!(verify_response_signature(payload, &signature, &address).unwrap());
+
+        let tampered = br#"{"jsonrpc":"2.0","id":1,"result":{"epoch":8}}"#;
+        assert!(!verify_response_signature(tampered, &signature, &address).unwrap());
+        let other = get_address(PublicKey::from_secret_key(&SecretKey::random(
+            &mut thread_rng(),
+        )));
+        assert!(!verify_response_signature(payload, &signature, &other).unwrap());
+        assert_eq!(
+            verify_response_signature(payload, &signature[..64], &address).unwrap_err(),
+            VrfError::InvalidLength
+        );
+    }
+
+    #[test]
+    fn signature_bytes_reject_malformed_input() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let mut buf = ecvrf.sign_epoch(56, 7, &[9u8; 32]).unwrap().to_bytes();
+        assert!(buf[64] == 27 || buf[64] == 28);
+        assert_eq!(
+            RecoverableSignature::from_bytes(&buf[..SIGNATURE_SIZE - 1]).unwrap_err(),
+            VrfError::InvalidLength
+        );
+        buf[64] = 29;
+        assert_eq!(
+            RecoverableSignature::from_bytes(&buf).unwrap_err(),
+            VrfError::InvalidSignature
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use crate::{ecproof::ECVRFProof, helper::keccak256_vec_scalar, Secp256k1Keccak, VrfError};
+use libsecp256k1::curve::{Affine, Scalar};
+
+// A VRF over some curve and hash function, it lets callers write curve-generic code
+// alpha is a byte string in every suite, each suite maps it to its own input
+pub trait VrfSuite {
+    type Point;
+    type Scalar;
+    type Proof;
+
+    // Hash alpha to a point of the prime order group, it's bound to the public key
+    fn hash_to_curve(&self, alpha: &[u8]) -> Result<Self::Point, VrfError>;
+
+    // Challenge over the given points
+    fn hash_points(&self, points: &[Self::Point]) -> Result<Self::Scalar, VrfError>;
+
+    // Proofs are deterministic, the same key and alpha always yield the same proof
+    fn prove(&self, alpha: &[u8]) -> Result<Self::Proof, VrfError>;
+
+    fn verify(&self, alpha: &[u8], proof: &Self::Proof) -> Result<bool, VrfError>;
+}
+
+// alpha is mapped to keccak256(alpha) mod n like prove_bytes(), so proofs of this
+// trait are verified by verify_bytes() and the other way around
+impl VrfSuite for Secp256k1Keccak<'_> {
+    type Point = Affine;
+    type Scalar = Scalar;
+    type Proof = ECVRFProof;
+
+    fn hash_to_curve(&self, alpha: &[u8]) -> Result<Affine, VrfError> {
+        let mut pub_affine: Affine = self.public_key.into();
+        pub_affine.x.normalize();
+        pub_affine.y.normalize();
+        Ok(Secp256k1Keccak::hash_to_curve(
+            self,
+            &keccak256_vec_scalar(alpha),
+            Some(&pub_affine),
+        ))
+    }
+
+    fn hash_points(&self, points: &[Affine]) -> Result<Scalar, VrfError> {
+        Ok(self.hash_point_slice(points))
+    }
+
+    fn prove(&self, alpha: &[u8]) -> Result<ECVRFProof, VrfError> {
+        self.prove_deterministic(&keccak256_vec_scalar(alpha))
+    }
+
+    fn verify(&self, alpha: &[u8], proof: &ECVRFProof) -> Result<bool, VrfError> {
+        self.verify_bytes(alpha, proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VrfSuite;
+    use crate::ECVRF;
+    use libsecp256k1::{curve::AFFINE_G, SecretKey};
+    use rand::thread_rng;
+
+    // Curve-generic caller
+    fn prove_and_verify<S: VrfSuite>(suite: &S) {
+        let proof = suite.prove(b"block hash").unwrap();
+        assert!(suite.verify(b"block hash", &proof).unwrap());
+        assert!(!suite.verify(b"other block hash", &proof).unwrap());
+    }
+
+    #[test]
+    fn secp256k1_keccak_is_a_suite() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        prove_and_verify(&ecvrf);
+
+        // Same proofs as the concrete API
+        let proof = VrfSuite::prove(&ecvrf, b"block hash").unwrap();
+        assert!(ecvrf.verify_bytes(b"block hash", &proof).unwrap());
+        let h = VrfSuite::hash_to_curve(&ecvrf, b"block hash").unwrap();
+        assert!(h.is_valid_var());
+        let points = [AFFINE_G, h, AFFINE_G, h, AFFINE_G, h];
+        assert_eq!(
+            VrfSuite::hash_points(&ecvrf, &points).unwrap(),
+            ecvrf.hash_points(&AFFINE_G, &h, &AFFINE_G, &h, &AFFINE_G, &h)
+        );
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn ed25519_sha512_is_a_suite() {
+        prove_and_verify(&crate::ed25519::Ed25519Sha512::new([7u8; 32]));
+    }
+}
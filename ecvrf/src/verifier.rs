@@ -0,0 +1,104 @@
+use crate::{ecproof::ECVRFProof, SchemeVersion, Secp256k1Keccak, VrfError};
+use libsecp256k1::{
+    curve::{ECMultContext, ECMultGenContext, Scalar},
+    PublicKey, ECMULT_CONTEXT, ECMULT_GEN_CONTEXT,
+};
+
+// Contexts of the secp256k1 suite without a key, one verifier checks proofs of any key
+// Proofs are checked with their recorded hash mode like ECVRFProof::verify_with_pubkey
+#[derive(Clone, Copy)]
+pub struct Verifier<'a> {
+    version: SchemeVersion,
+    ctx_mul: &'a ECMultContext,
+    ctx_gen: &'a ECMultGenContext,
+}
+
+impl Default for Verifier<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Verifier<'_> {
+    // Verifier of the current scheme version with the static contexts
+    pub fn new() -> Self {
+        Self::new_with_version(SchemeVersion::CURRENT)
+    }
+
+    // Verifier of the given scheme version, V1 verifies epochs created without domain separation
+    pub fn new_with_version(version: SchemeVersion) -> Self {
+        Verifier {
+            version,
+            ctx_mul: &ECMULT_CONTEXT,
+            ctx_gen: &ECMULT_GEN_CONTEXT,
+        }
+    }
+
+    pub fn version(&self) -> SchemeVersion {
+        self.version
+    }
+
+    // Verify a proof of the given public key, a proof that embeds another key is rejected
+    pub fn verify(
+        &self,
+        public_key: &PublicKey,
+        alpha: &Scalar,
+        vrf_proof: &ECVRFProof,
+    ) -> Result<bool, VrfError> {
+        if vrf_proof.pk != *public_key {
+            return Ok(false);
+        }
+        // Verifier view of the suite, it only borrows the contexts
+        Secp256k1Keccak {
+            secret_key: None,
+            secret_scalar: None,
+            public_key: *public_key,
+            version: self.version,
+            hash_mode: vrf_proof.hash_mode,
+            ctx_mul: self.ctx_mul,
+            ctx_gen: self.ctx_gen,
+        }
+        .verify(alpha, vrf_proof)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::Verifier;
+    use crate::{helper::randomize, HashMode, SchemeVersion, ECVRF};
+    use libsecp256k1::SecretKey;
+    use rand::thread_rng;
+
+    #[test]
+    fn one_verifier_serves_many_keys() {
+        let verifier = Verifier::new();
+        let chiro = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let rotated = ECVRF::new_with_hash(SecretKey::random(&mut thread_rng()), HashMode::Sha256);
+        let alpha = randomize();
+        let chiro_proof = chiro.prove(&alpha).unwrap();
+        let rotated_proof = rotated.prove(&alpha).unwrap();
+
+        assert!(verifier
+            .verify(&chiro_proof.public_key(), &alpha, &chiro_proof)
+            .unwrap());
+        assert!(verifier
+            .verify(&rotated_proof.public_key(), &alpha, &rotated_proof)
+            .unwrap());
+        // Proofs are bound to their key and alpha
+        assert!(!verifier
+            .verify(&chiro_proof.public_key(), &alpha, &rotated_proof)
+            .unwrap());
+        assert!(!verifier
+            .verify(&chiro_proof.public_key(), &randomize(), &chiro_proof)
+            .unwrap());
+
+        let v1 = ECVRF::new_with_version(SecretKey::random(&mut thread_rng()), SchemeVersion::V1);
+        let v1_proof = v1.prove(&alpha).unwrap();
+        assert!(!verifier
+            .verify(&v1_proof.public_key(), &alpha, &v1_proof)
+            .unwrap());
+        assert!(Verifier::new_with_version(SchemeVersion::V1)
+            .verify(&v1_proof.public_key(), &alpha, &v1_proof)
+            .unwrap());
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{ecproof::ECVRFProof, helper::scalar_from_b32, VrfError, ECVRF};
+use libsecp256k1::{curve::Scalar, PublicKey, SecretKey};
+use wasm_bindgen::prelude::*;
+
+// Decode hex string, 0x prefix is optional
+fn decode_hex(name: &str, value: &str) -> Result<Vec<u8>, JsValue> {
+    hex::decode(value.strip_prefix("0x").unwrap_or(value))
+        .map_err(|e| JsValue::from_str(&format!("{}: {}", name, e)))
+}
+
+fn vrf_error(name: &str, e: VrfError) -> JsValue {
+    JsValue::from_str(&format!("{}: {}", name, e))
+}
+
+// Alpha must be a 32 bytes scalar that doesn't overflow the group order
+fn decode_alpha(alpha_hex: &str) -> Result<Scalar, JsValue> {
+    let packed: [u8; 32] = decode_hex("alpha", alpha_hex)?
+        .try_into()
+        .map_err(|_| vrf_error("alpha", VrfError::InvalidLength))?;
+    scalar_from_b32(&packed).map_err(|e| vrf_error("alpha", e))
+}
+
+// Verify a proof in its binary encoding (ECVRFProof::to_bytes) against a public key
+// Public key could be compressed or uncompressed SEC1
+#[wasm_bindgen]
+pub fn wasm_verify(
+    public_key_hex: &str,
+    alpha_hex: &str,
+    proof_hex: &str,
+) -> Result<bool, JsValue> {
+    let public_key = PublicKey::parse_slice(&decode_hex("public_key", public_key_hex)?, None)
+        .map_err(|_| vrf_error("public_key", VrfError::InvalidPublicKey))?;
+    let alpha = decode_alpha(alpha_hex)?;
+    let proof = ECVRFProof::from_bytes(&decode_hex("proof", proof_hex)?)
+        .map_err(|e| vrf_error("proof", e))?;
+    proof
+        .verify_with_pubkey(&alpha, &public_key)
+        .map_err(|e| vrf_error("proof", e))
+}
+
+// Prove alpha and return the proof in its binary encoding as hex
+// The nonce is deterministic so it doesn't depend on the browser's RNG
+#[wasm_bindgen]
+pub fn wasm_prove(secret_hex: &str, alpha_hex: &str) -> Result<String, JsValue> {
+    let secret_key = SecretKey::parse_slice(&decode_hex("secret_key", secret_hex)?)
+        .map_err(|_| JsValue::from_str("secret_key: invalid secret key"))?;
+    let alpha = decode_alpha(alpha_hex)?;
+    let proof = ECVRF::new(secret_key)
+        .prove_deterministic(&alpha)
+        .map_err(|e| vrf_error("proof", e))?;
+    Ok(hex::encode(proof.to_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{wasm_prove, wasm_verify};
+    use libsecp256k1::{PublicKey, SecretKey};
+
+    // Error paths create a JsValue, they could only run under wasm32
+    #[test]
+    fn wasm_prove_then_verify() {
+        let secret_hex = "1e99423a4ed27608a15a2616a2b0e9e52ced330ac530edcc32c8ffc6a526aedd";
+        let alpha_hex = "0xeabe8358ea6cedc4d3759a48648631369b9fb6d1c26da3adbc26eff564e032c4";
+        let secret_key = SecretKey::parse_slice(&hex::decode(secret_hex).unwrap()).unwrap();
+        let public_key = PublicKey::from_secret_key(&secret_key);
+
+        let proof_hex = wasm_prove(secret_hex, alpha_hex).unwrap();
+        assert_eq!(proof_hex, wasm_prove(secret_hex, alpha_hex).unwrap());
+        assert!(wasm_verify(
+            &hex::encode(public_key.serialize_compressed()),
+            alpha_hex,
+            &proof_hex
+        )
+        .unwrap());
+        assert!(wasm_verify(&hex::encode(public_key.serialize()), alpha_hex, &proof_hex).unwrap());
+    }
+}
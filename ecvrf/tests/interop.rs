@@ -0,0 +1,71 @@
+use ecvrf::{
+    ecproof::ECVRFProof, helper::scalar_from_b32, secp256k1::curve::Scalar, VrfError, ECVRF,
+};
+use serde_json::{json, Value};
+
+// Proof of epoch 21 of network 56 as the Solidity verifier takes it,
+// see optimus of contracts/test/001-ecvrf.spec.ts
+const SDK_PROOF: &str = include_str!("fixtures/orand_sdk_proof.json");
+const Y: &str = "a0c64323bd3aa4c8a3a4d80f0bde8b510c1fc2c3b6631008bd1f3d0d67f4dae3";
+
+fn scalar(hex_string: &str) -> Scalar {
+    let b32: [u8; 32] = hex::decode(hex_string.trim_start_matches("0x"))
+        .unwrap()
+        .try_into()
+        .unwrap();
+    scalar_from_b32(&b32).unwrap()
+}
+
+#[test]
+fn proof_of_the_sdk_is_verified() {
+    let json: Value = serde_json::from_str(SDK_PROOF).unwrap();
+    let proof = ECVRFProof::from_orand_json(&json).unwrap();
+    let alpha = scalar(json["seed"].as_str().unwrap());
+    assert_eq!(hex::encode(proof.y.b32()), Y);
+
+    let verifier = ECVRF::new_verifier(proof.public_key());
+    assert!(verifier.verify_contract(&alpha, &proof).unwrap());
+    assert!(!verifier.verify_contract(&scalar(Y), &proof).unwrap());
+
+    // Witnesses of the on-chain verifier are rebuilt from the proof
+    let contract_proof = proof.to_contract_proof(&alpha);
+    assert_eq!(
+        hex::encode(&contract_proof.witness_address.b32()[12..]),
+        json["uWitness"].as_str().unwrap().trim_start_matches("0x")
+    );
+    assert_eq!(
+        contract_proof.inverse_z.b32(),
+        scalar(json["zInv"].as_str().unwrap()).b32()
+    );
+}
+
+#[test]
+fn sdk_encodings_are_equivalent() {
+    let json: Value = serde_json::from_str(SDK_PROOF).unwrap();
+    let proof = ECVRFProof::from_orand_json(&json).unwrap();
+    // Same proof with named coordinates, a SEC1 public key, decimal c and an explicit y
+    let decimal_c =
+        "100170075557046274098895169138232535595075758616090167615886408297256828480275";
+    let named = json!({
+        "gamma": {"x": json["gamma"][0], "y": json["gamma"][1]},
+        "c": decimal_c,
+        "s": json["s"],
+        "y": format!("0x{}", Y),
+        "public_key": format!("0x{}", hex::encode(proof.public_key().serialize_compressed())),
+    });
+    let decoded = ECVRFProof::from_orand_json(&named).unwrap();
+    assert_eq!(decoded.to_bytes(), proof.to_bytes());
+
+    let mut off_curve = json.clone();
+    off_curve["gamma"][1] = json["gamma"][0].clone();
+    assert_eq!(
+        ECVRFProof::from_orand_json(&off_curve).unwrap_err(),
+        VrfError::InvalidGamma
+    );
+    let mut missing = json.clone();
+    missing.as_object_mut().unwrap().remove("c");
+    assert_eq!(
+        ECVRFProof::from_orand_json(&missing).unwrap_err(),
+        VrfError::InvalidEncoding
+    );
+}
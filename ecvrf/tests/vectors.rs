@@ -0,0 +1,126 @@
+use ecvrf::{
+    ecproof::ECVRFProof,
+    secp256k1::{
+        curve::{Affine, Field, Scalar},
+        PublicKey, SecretKey,
+    },
+    SchemeVersion, ECVRF,
+};
+
+// Known answer vectors of prove_deterministic()
+// They were generated once, any change of them means hash_to_curve(),
+// hash_points() or the point encoding was changed
+struct Vector {
+    version: SchemeVersion,
+    secret_key: &'static str,
+    alpha: &'static str,
+    gamma_x: &'static str,
+    gamma_y: &'static str,
+    c: &'static str,
+    s: &'static str,
+    y: &'static str,
+}
+
+const VECTORS: [Vector; 5] = [
+    Vector {
+        version: SchemeVersion::V1,
+        secret_key: "1e99423a4ed27608a15a2616a2b0e9e52ced330ac530edcc32c8ffc6a526aedd",
+        alpha: "eabe8358ea6cedc4d3759a48648631369b9fb6d1c26da3adbc26eff564e032c4",
+        gamma_x: "a520350b7ef1969c6eaedcd68db53c999da4424a7d26b15c9648a6967a7d1099",
+        gamma_y: "429a643d1282f44478e54528b0cf5e030472ac42c1cc4e49a79bd58ee7f953bb",
+        c: "d46a7a81e679a32a0f55279928ed74e80c3d490f7b3a0f4a5ed030cb5e468b69",
+        s: "ad9dcc09df9ac84d11c1e22a2048812bf454bffb94eb67b11fb94d0242e2b64f",
+        y: "f9f6d4db01fddf50289b916d8c37faa8777ec8b1983777017ce17cda889dfd94",
+    },
+    // secret key = 1 and alpha = 1, H = G + G so gamma = 2G
+    Vector {
+        version: SchemeVersion::V1,
+        secret_key: "0000000000000000000000000000000000000000000000000000000000000001",
+        alpha: "0000000000000000000000000000000000000000000000000000000000000001",
+        gamma_x: "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+        gamma_y: "1ae168fea63dc339a3c58419466ceaeef7f632653266d0e1236431a950cfe52a",
+        c: "8359987393d6527b62570063c3de6f21374d70e689ada02720b47593e9c64502",
+        s: "16e7053771c02dde77e7face79e80d75bae6e6350cb23370f01590b011f150aa",
+        y: "eedf1a9c68b3f4a8b1a1032b2b5ad5c4795c026514f8317c7a215e218dccd6cf",
+    },
+    Vector {
+        version: SchemeVersion::V1,
+        secret_key: "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721",
+        alpha: "a0c64323bd3aa4c8a3a4d80f0bde8b510c1fc2c3b6631008bd1f3d0d67f4dae3",
+        gamma_x: "1b2ba567d8e76951abe1fa3a3242bf3a042a1387b9d9bce8246079f0b51629f7",
+        gamma_y: "3b678ac162a6f819cefeaeac1609cbe9b8d951c10a6c8673856a0b033a40a773",
+        c: "70aa8d292cd415513c4f9b6048fa17d7e61dbc2ab9cdd7b4e8b58c0f39279fd8",
+        s: "32e35180207a14b04c5e6e30cb4505dc249d06ac0b8ce2d4daaf378963c93a37",
+        y: "87b8f40ec465eaef679eae4f178686f23e3ffefe5a731915abca8e5c338adee2",
+    },
+    Vector {
+        version: SchemeVersion::V2,
+        secret_key: "1e99423a4ed27608a15a2616a2b0e9e52ced330ac530edcc32c8ffc6a526aedd",
+        alpha: "eabe8358ea6cedc4d3759a48648631369b9fb6d1c26da3adbc26eff564e032c4",
+        gamma_x: "69c2661a9505cb80e647ea524573de4aae1f833e2202771ed54370f406cd1a8f",
+        gamma_y: "1e83abde587c16d289861ab6969cdf03277396d77431786278547e4ccd8cd596",
+        c: "1ab349c8e2b985ce82c88a9349c721c7f4c4f9b1e6dcf69c028e1ab3b630585c",
+        s: "60fb360f3fe21c47df6718b88009674b8829bf8cfcd7e330a7759627304d1173",
+        y: "abfe809562804351ace648f5486bba8fd0eaf8876c0f08b2c21a3e9e5657ac1c",
+    },
+    Vector {
+        version: SchemeVersion::V2,
+        secret_key: "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721",
+        alpha: "a0c64323bd3aa4c8a3a4d80f0bde8b510c1fc2c3b6631008bd1f3d0d67f4dae3",
+        gamma_x: "6faae23e9ca276b46a815dc5eb2f54cd6886c902d188701810e81b907c344200",
+        gamma_y: "b4881a4829c465737df207c978efb9d1327274e8c842966679f7db9216183fb9",
+        c: "236079df36b1746d44a88c76a5804ee4b9d9a6b3456147ea0828d8a47a9df07a",
+        s: "7f3eaf14734caeb5e2c3cd08b035cbe461f9205f4c581d6c23e231a7305f3746",
+        y: "1f4b3dd02e05758b754538d8839b74b1c9b1e8f992c7b43a1fce9e521a40e354",
+    },
+];
+
+fn bytes32(s: &str) -> [u8; 32] {
+    hex::decode(s).unwrap().try_into().unwrap()
+}
+
+fn scalar(s: &str) -> Scalar {
+    let mut r = Scalar::default();
+    assert!(!bool::from(r.set_b32(&bytes32(s))));
+    r
+}
+
+fn field(s: &str) -> Field {
+    let mut r = Field::default();
+    assert!(r.set_b32(&bytes32(s)));
+    r
+}
+
+#[test]
+fn prove_deterministic_matches_vectors() {
+    for vector in VECTORS.iter() {
+        let secret_key = SecretKey::parse(&bytes32(vector.secret_key)).unwrap();
+        let ecvrf = ECVRF::new_with_version(secret_key, vector.version);
+        let proof = ecvrf.prove_deterministic(&scalar(vector.alpha)).unwrap();
+
+        assert_eq!(hex::encode(proof.gamma.x.b32()), vector.gamma_x);
+        assert_eq!(hex::encode(proof.gamma.y.b32()), vector.gamma_y);
+        assert_eq!(hex::encode(proof.c.b32()), vector.c);
+        assert_eq!(hex::encode(proof.s.b32()), vector.s);
+        assert_eq!(hex::encode(proof.y.b32()), vector.y);
+    }
+}
+
+#[test]
+fn verify_accepts_vectors() {
+    for vector in VECTORS.iter() {
+        let secret_key = SecretKey::parse(&bytes32(vector.secret_key)).unwrap();
+        let ecvrf = ECVRF::new_with_version(secret_key, vector.version);
+        let mut gamma = Affine::default();
+        gamma.set_xy(&field(vector.gamma_x), &field(vector.gamma_y));
+        let proof = ECVRFProof::new(
+            gamma,
+            scalar(vector.c),
+            scalar(vector.s),
+            scalar(vector.y),
+            PublicKey::from_secret_key(&secret_key),
+        );
+
+        assert!(ecvrf.verify(&scalar(vector.alpha), &proof).unwrap());
+    }
+}
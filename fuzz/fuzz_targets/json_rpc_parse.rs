@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use orochimaru::json_rpc::JSONRPCMethod;
+
+// Requests come from the network, parsing and validation must never panic
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        if let Ok(method) = JSONRPCMethod::from_json_string(s) {
+            let _ = method.validate();
+        }
+    }
+});
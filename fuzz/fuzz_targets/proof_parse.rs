@@ -0,0 +1,13 @@
+#![no_main]
+
+use ecvrf::ecproof::{ECVRFProof, PROOF_SIZE, TAGGED_PROOF_SIZE};
+use libfuzzer_sys::fuzz_target;
+
+// from_bytes parses untrusted input, it must return Ok or Err and never panic
+fuzz_target!(|data: &[u8]| {
+    if let Ok(proof) = ECVRFProof::from_bytes(data) {
+        // A decoded proof encodes back to the same bytes
+        assert!(data.len() == PROOF_SIZE || data.len() == TAGGED_PROOF_SIZE);
+        assert_eq!(proof.to_tagged_bytes(), data);
+    }
+});
@@ -2,6 +2,22 @@ pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table_keyring;
 mod m20221105_172637_create_table_randomness;
+mod m20221201_000001_create_index_network_epoch;
+mod m20221201_000002_create_index_latest_epoch;
+mod m20221210_000001_add_proof_to_randomness;
+mod m20221215_000001_add_block_number_to_randomness;
+mod m20221220_000001_add_address_to_keyring;
+mod m20221225_000001_create_table_api_key;
+mod m20221226_000001_add_derived_to_keyring;
+mod m20221227_000001_encrypt_keyring_secret_key;
+mod m20221227_000002_widen_network_epoch_of_randomness;
+mod m20221228_000001_add_signature_to_randomness;
+mod m20221229_000001_create_index_network_alpha;
+mod m20221230_000001_create_table_audit_log;
+mod m20221231_000001_create_table_network;
+mod m20230101_000001_add_caller_alpha_to_randomness;
+mod m20230102_000001_create_table_seen_alpha;
+mod m20230103_000001_create_index_network_created_date;
 
 pub struct Migrator;
 
@@ -11,6 +27,22 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20220101_000001_create_table_keyring::Migration),
             Box::new(m20221105_172637_create_table_randomness::Migration),
+            Box::new(m20221201_000001_create_index_network_epoch::Migration),
+            Box::new(m20221201_000002_create_index_latest_epoch::Migration),
+            Box::new(m20221210_000001_add_proof_to_randomness::Migration),
+            Box::new(m20221215_000001_add_block_number_to_randomness::Migration),
+            Box::new(m20221220_000001_add_address_to_keyring::Migration),
+            Box::new(m20221225_000001_create_table_api_key::Migration),
+            Box::new(m20221226_000001_add_derived_to_keyring::Migration),
+            Box::new(m20221227_000001_encrypt_keyring_secret_key::Migration),
+            Box::new(m20221227_000002_widen_network_epoch_of_randomness::Migration),
+            Box::new(m20221228_000001_add_signature_to_randomness::Migration),
+            Box::new(m20221229_000001_create_index_network_alpha::Migration),
+            Box::new(m20221230_000001_create_table_audit_log::Migration),
+            Box::new(m20221231_000001_create_table_network::Migration),
+            Box::new(m20230101_000001_add_caller_alpha_to_randomness::Migration),
+            Box::new(m20230102_000001_create_table_seen_alpha::Migration),
+            Box::new(m20230103_000001_create_index_network_created_date::Migration),
         ]
     }
 }
@@ -12,9 +12,9 @@ impl MigrationTrait for Migration {
                     .table(Keyring::Table)
                     .if_not_exists()
                     .col(
+                        // PostgreSQL can't auto increment unsigned columns, SQLite's DDL is the same
                         ColumnDef::new(Keyring::Id)
                             .integer()
-                            .unsigned()
                             .not_null()
                             .auto_increment()
                             .primary_key(),
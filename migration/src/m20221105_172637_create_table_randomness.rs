@@ -12,9 +12,9 @@ impl MigrationTrait for Migration {
                     .table(Randomness::Table)
                     .if_not_exists()
                     .col(
+                        // PostgreSQL can't auto increment unsigned columns, SQLite's DDL is the same
                         ColumnDef::new(Randomness::Id)
                             .integer()
-                            .unsigned()
                             .not_null()
                             .auto_increment()
                             .primary_key(),
@@ -57,7 +57,7 @@ impl MigrationTrait for Migration {
                             .not_null(),
                     )
                     .foreign_key(
-                        &mut ForeignKeyCreateStatement::new()
+                        ForeignKeyCreateStatement::new()
                             .name("link_randomness_to_keyring")
                             .from_tbl(Randomness::Table)
                             .from_col(Randomness::KeyringId)
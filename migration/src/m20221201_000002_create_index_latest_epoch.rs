@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Latest epoch of a network is read from the top of this index instead of a table scan,
+// lookups of (network, epoch) use the unique index_network_epoch
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .name("index_network_epoch_desc")
+                    .table(Randomness::Table)
+                    .col(Randomness::Network)
+                    .col((Randomness::Epoch, IndexOrder::Desc))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("index_network_epoch_desc")
+                    .table(Randomness::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Randomness {
+    Table,
+    Network,
+    Epoch,
+}
@@ -0,0 +1,122 @@
+use ecvrf::{
+    ecproof::ECVRFProof,
+    secp256k1::{curve::Scalar, PublicKey},
+};
+use sea_orm_migration::{prelude::*, sea_orm::ConnectionTrait};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Proof column holds ECVRFProof::to_bytes(), existing rows are backfilled from
+// Gamma, C, S, Y and the public key of their keyring
+// The string columns are kept for one more release
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Randomness::Table)
+                    .add_column(ColumnDef::new(Randomness::Proof).binary())
+                    .to_owned(),
+            )
+            .await?;
+
+        let connection = manager.get_connection();
+        let backend = manager.get_database_backend();
+        let select = Query::select()
+            .column((Randomness::Table, Randomness::Id))
+            .columns([
+                Randomness::Gamma,
+                Randomness::C,
+                Randomness::S,
+                Randomness::Y,
+            ])
+            .column((Keyring::Table, Keyring::PublicKey))
+            .from(Randomness::Table)
+            .inner_join(
+                Keyring::Table,
+                Expr::tbl(Randomness::Table, Randomness::KeyringId)
+                    .equals(Keyring::Table, Keyring::Id),
+            )
+            .to_owned();
+
+        for row in connection.query_all(backend.build(&select)).await? {
+            let id: i32 = row.try_get("", "id")?;
+            let column = |name: &str| -> Result<String, DbErr> { row.try_get("", name) };
+            let proof = proof_from_columns(
+                &column("gamma")?,
+                &column("c")?,
+                &column("s")?,
+                &column("y")?,
+                &column("public_key")?,
+            )
+            .ok_or_else(|| {
+                DbErr::Custom(format!("Can not reconstruct proof of randomness {}", id))
+            })?;
+            let update = Query::update()
+                .table(Randomness::Table)
+                .value(Randomness::Proof, proof.to_vec())
+                .and_where(Expr::col(Randomness::Id).eq(id))
+                .to_owned();
+            connection.execute(backend.build(&update)).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Randomness::Table)
+                    .drop_column(Randomness::Proof)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+fn scalar_from_hex(s: &str) -> Option<Scalar> {
+    let bytes: [u8; 32] = hex::decode(s).ok()?.try_into().ok()?;
+    let mut scalar = Scalar::default();
+    if bool::from(scalar.set_b32(&bytes)) {
+        return None;
+    }
+    Some(scalar)
+}
+
+// Gamma is stored as x || y, public key as uncompressed SEC1
+fn proof_from_columns(gamma: &str, c: &str, s: &str, y: &str, public_key: &str) -> Option<Vec<u8>> {
+    let gamma = [&[0x04], hex::decode(gamma).ok()?.as_slice()].concat();
+    let gamma = PublicKey::parse_slice(&gamma, None).ok()?.into();
+    let pk = PublicKey::parse_slice(&hex::decode(public_key).ok()?, None).ok()?;
+    let proof = ECVRFProof::new(
+        gamma,
+        scalar_from_hex(c)?,
+        scalar_from_hex(s)?,
+        scalar_from_hex(y)?,
+        pk,
+    );
+    Some(proof.to_bytes().to_vec())
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Keyring {
+    Table,
+    Id,
+    PublicKey,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Randomness {
+    Table,
+    Id,
+    KeyringId,
+    Gamma,
+    C,
+    S,
+    Y,
+    Proof,
+}
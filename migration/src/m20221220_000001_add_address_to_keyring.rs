@@ -0,0 +1,81 @@
+use ecvrf::{helper::get_address, secp256k1::PublicKey};
+use sea_orm_migration::{prelude::*, sea_orm::ConnectionTrait};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Ethereum address of the public key, contracts use it to authenticate the oracle
+// Existing keys are backfilled from their public key
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Keyring::Table)
+                    .add_column(ColumnDef::new(Keyring::Address).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        let connection = manager.get_connection();
+        let backend = manager.get_database_backend();
+        let select = Query::select()
+            .columns([Keyring::Id, Keyring::PublicKey])
+            .from(Keyring::Table)
+            .to_owned();
+        for row in connection.query_all(backend.build(&select)).await? {
+            let id: i32 = row.try_get("", "id")?;
+            let public_key: String = row.try_get("", "public_key")?;
+            let public_key = hex::decode(public_key)
+                .ok()
+                .and_then(|b| PublicKey::parse_slice(&b, None).ok())
+                .ok_or_else(|| DbErr::Custom(format!("Invalid public key of keyring {}", id)))?;
+            let update = Query::update()
+                .table(Keyring::Table)
+                .value(Keyring::Address, hex::encode(get_address(public_key)))
+                .and_where(Expr::col(Keyring::Id).eq(id))
+                .to_owned();
+            connection.execute(backend.build(&update)).await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("index_address")
+                    .table(Keyring::Table)
+                    .unique()
+                    .col(Keyring::Address)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("index_address")
+                    .table(Keyring::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Keyring::Table)
+                    .drop_column(Keyring::Address)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Keyring {
+    Table,
+    Id,
+    PublicKey,
+    Address,
+}
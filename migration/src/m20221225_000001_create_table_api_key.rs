@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ApiKey::Table)
+                    .if_not_exists()
+                    .col(
+                        // PostgreSQL serial doesn't support unsigned
+                        ColumnDef::new(ApiKey::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ApiKey::Name).string().not_null())
+                    // SHA-256 of the key, raw keys are never stored
+                    .col(ColumnDef::new(ApiKey::KeyHash).string().not_null())
+                    .col(
+                        ColumnDef::new(ApiKey::CreatedDate)
+                            .timestamp()
+                            .not_null()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_string()),
+                    )
+                    .index(
+                        Index::create()
+                            .name("index_api_key_name")
+                            .unique()
+                            .col(ApiKey::Name),
+                    )
+                    .index(
+                        Index::create()
+                            .name("index_api_key_hash")
+                            .unique()
+                            .col(ApiKey::KeyHash),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ApiKey::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum ApiKey {
+    Table,
+    Id,
+    Name,
+    KeyHash,
+    CreatedDate,
+}
@@ -0,0 +1,118 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use sea_orm_migration::{prelude::*, sea_orm::ConnectionTrait};
+use std::env;
+
+// Same variable and format as orochimaru::kek, hex(nonce || AES-256-GCM ciphertext)
+const KEK_ENV: &str = "ORAND_KEYRING_KEK";
+const NONCE_SIZE: usize = 12;
+const SECRET_KEY_SIZE: usize = 32;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+fn cipher() -> Result<Aes256Gcm, DbErr> {
+    let value = env::var(KEK_ENV)
+        .map_err(|_| DbErr::Custom(format!("{} is required to migrate secret keys", KEK_ENV)))?;
+    let key: [u8; 32] = hex::decode(value.strip_prefix("0x").unwrap_or(&value))
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| DbErr::Custom(format!("{} must be 32 bytes hex encoded", KEK_ENV)))?;
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
+
+// Rewrite the secret keys that are selected by the filter
+// The KEK is only needed if at least one key is selected
+async fn rewrite_secret_keys<F>(
+    manager: &SchemaManager<'_>,
+    filter: fn(&[u8]) -> bool,
+    f: F,
+) -> Result<(), DbErr>
+where
+    F: Fn(&Aes256Gcm, i32, Vec<u8>) -> Result<Vec<u8>, DbErr>,
+{
+    let connection = manager.get_connection();
+    let backend = manager.get_database_backend();
+    let select = Query::select()
+        .columns([Keyring::Id, Keyring::SecretKeyEnc])
+        .from(Keyring::Table)
+        .to_owned();
+    let mut secret_keys = Vec::new();
+    for row in connection.query_all(backend.build(&select)).await? {
+        let id: i32 = row.try_get("", "id")?;
+        let secret_key: String = row.try_get("", "secret_key_enc")?;
+        let bytes = hex::decode(secret_key)
+            .map_err(|_| DbErr::Custom(format!("Invalid secret key of keyring {}", id)))?;
+        if filter(&bytes) {
+            secret_keys.push((id, bytes));
+        }
+    }
+    if secret_keys.is_empty() {
+        return Ok(());
+    }
+    let cipher = cipher()?;
+    for (id, bytes) in secret_keys {
+        let update = Query::update()
+            .table(Keyring::Table)
+            .value(Keyring::SecretKeyEnc, hex::encode(f(&cipher, id, bytes)?))
+            .and_where(Expr::col(Keyring::Id).eq(id))
+            .to_owned();
+        connection.execute(backend.build(&update)).await?;
+    }
+    Ok(())
+}
+
+// Secret keys are encrypted at rest under the KEK from ORAND_KEYRING_KEK
+// Existing plaintext keys are encrypted, keys that are already encrypted are kept
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Keyring::Table)
+                    .rename_column(Keyring::SecretKey, Keyring::SecretKeyEnc)
+                    .to_owned(),
+            )
+            .await?;
+        let is_plaintext = |bytes: &[u8]| bytes.len() == SECRET_KEY_SIZE;
+        rewrite_secret_keys(manager, is_plaintext, |cipher, _, bytes| {
+            let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+            let ciphertext = cipher
+                .encrypt(&nonce, bytes.as_ref())
+                .map_err(|_| DbErr::Custom("Can not encrypt secret key".to_string()))?;
+            Ok([nonce.as_slice(), &ciphertext].concat())
+        })
+        .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let is_encrypted = |bytes: &[u8]| bytes.len() > NONCE_SIZE + SECRET_KEY_SIZE;
+        rewrite_secret_keys(manager, is_encrypted, |cipher, id, bytes| {
+            let (nonce, ciphertext) = bytes.split_at(NONCE_SIZE);
+            cipher
+                .decrypt(Nonce::from_slice(nonce), ciphertext)
+                .map_err(|_| DbErr::Custom(format!("Can not decrypt secret key of keyring {}", id)))
+        })
+        .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Keyring::Table)
+                    .rename_column(Keyring::SecretKeyEnc, Keyring::SecretKey)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Keyring {
+    Table,
+    Id,
+    SecretKey,
+    SecretKeyEnc,
+}
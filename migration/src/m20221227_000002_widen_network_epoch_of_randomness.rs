@@ -0,0 +1,48 @@
+use sea_orm_migration::{prelude::*, sea_orm::DbBackend};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Network and epoch were created by .big_integer().unsigned(), which is a 32-bit integer on
+// PostgreSQL, they're widened to bigint like the ids of the entities
+// SQLite integers are 64-bit already so it's left alone
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() != DbBackend::Postgres {
+            return Ok(());
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Randomness::Table)
+                    .modify_column(ColumnDef::new(Randomness::Network).big_integer().not_null())
+                    .modify_column(ColumnDef::new(Randomness::Epoch).big_integer().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() != DbBackend::Postgres {
+            return Ok(());
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Randomness::Table)
+                    .modify_column(ColumnDef::new(Randomness::Network).integer().not_null())
+                    .modify_column(ColumnDef::new(Randomness::Epoch).integer().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Randomness {
+    Table,
+    Network,
+    Epoch,
+}
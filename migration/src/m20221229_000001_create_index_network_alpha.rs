@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Alpha of an epoch is derived from the chain tip, a retried request finds the existing
+// record by (network, alpha) instead of creating a duplicate
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .name("index_network_alpha")
+                    .table(Randomness::Table)
+                    .unique()
+                    .col(Randomness::Network)
+                    .col(Randomness::Alpha)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("index_network_alpha")
+                    .table(Randomness::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Randomness {
+    Table,
+    Network,
+    Alpha,
+}
@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Append-only record of who triggered each epoch, rows are never updated or pruned
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditLog::Table)
+                    .if_not_exists()
+                    .col(
+                        // PostgreSQL serial doesn't support unsigned
+                        ColumnDef::new(AuditLog::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AuditLog::Network).big_integer().not_null())
+                    .col(ColumnDef::new(AuditLog::Epoch).big_integer().not_null())
+                    // Id of the API key, it's null for the scheduler and admin methods
+                    // There is no foreign key so revoked keys don't erase their records
+                    .col(ColumnDef::new(AuditLog::Actor).integer().null())
+                    .col(
+                        ColumnDef::new(AuditLog::Timestamp)
+                            .timestamp()
+                            .not_null()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_string()),
+                    )
+                    // SHA-256 of the JSON-RPC request, it's null for the scheduler
+                    .col(ColumnDef::new(AuditLog::RequestHash).string().null())
+                    .to_owned(),
+            )
+            .await?;
+        // SQLite doesn't support non-unique indexes in CREATE TABLE
+        manager
+            .create_index(
+                Index::create()
+                    .name("index_audit_log_network_epoch")
+                    .table(AuditLog::Table)
+                    .col(AuditLog::Network)
+                    .col(AuditLog::Epoch)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditLog::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum AuditLog {
+    Table,
+    Id,
+    Network,
+    Epoch,
+    Actor,
+    Timestamp,
+    RequestHash,
+}
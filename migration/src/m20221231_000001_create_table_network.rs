@@ -0,0 +1,242 @@
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, DbBackend, Statement},
+};
+
+// Cadence of the networks that already have epochs
+const DEFAULT_EPOCH_INTERVAL_SECS: i64 = 60;
+
+const FOREIGN_KEY_NAME: &str = "link_randomness_to_network";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Register every network that already has epochs, it's signed by the keyring of its latest epoch
+async fn backfill_networks(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    let connection = manager.get_connection();
+    let backend = manager.get_database_backend();
+    let select = Query::select()
+        .columns([Randomness::Network, Randomness::KeyringId])
+        .from(Randomness::Table)
+        .order_by(Randomness::Network, Order::Asc)
+        .order_by(Randomness::Epoch, Order::Desc)
+        .to_owned();
+    let mut latest: Option<i64> = None;
+    for row in connection.query_all(backend.build(&select)).await? {
+        let network: i64 = row.try_get("", "network")?;
+        if latest == Some(network) {
+            continue;
+        }
+        latest = Some(network);
+        let keyring_id: i32 = row.try_get("", "keyring_id")?;
+        let insert = Query::insert()
+            .into_table(Network::Table)
+            .columns([
+                Network::Name,
+                Network::ChainId,
+                Network::EpochIntervalSecs,
+                Network::KeyringId,
+            ])
+            .values_panic([
+                network.to_string().into(),
+                network.into(),
+                DEFAULT_EPOCH_INTERVAL_SECS.into(),
+                keyring_id.into(),
+            ])
+            .to_owned();
+        connection.execute(backend.build(&insert)).await?;
+    }
+    Ok(())
+}
+
+// SQLite can't add a foreign key to an existing table, the table is rebuilt from its own
+// schema with the given change, rows and indexes are kept
+async fn rebuild_sqlite_randomness<F>(manager: &SchemaManager<'_>, f: F) -> Result<(), DbErr>
+where
+    F: Fn(&str) -> Result<String, DbErr>,
+{
+    let connection = manager.get_connection();
+    let schema = |kind: &str| {
+        Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "SELECT sql FROM sqlite_master WHERE type = ? AND tbl_name = 'randomness' \
+             AND sql IS NOT NULL",
+            vec![kind.into()],
+        )
+    };
+    let table = connection
+        .query_one(schema("table"))
+        .await?
+        .ok_or_else(|| DbErr::Custom("Table randomness does not exist".to_string()))?;
+    let table_sql: String = table.try_get("", "sql")?;
+    let mut index_sqls = Vec::new();
+    for row in connection.query_all(schema("index")).await? {
+        index_sqls.push(row.try_get::<String>("", "sql")?);
+    }
+    let new_sql = f(&table_sql)?
+        .replacen("\"randomness\"", "\"randomness_new\"", 1)
+        .replacen("`randomness`", "`randomness_new`", 1);
+    let statements = [
+        new_sql,
+        "INSERT INTO randomness_new SELECT * FROM randomness".to_string(),
+        "DROP TABLE randomness".to_string(),
+        "ALTER TABLE randomness_new RENAME TO randomness".to_string(),
+    ];
+    for sql in statements.into_iter().chain(index_sqls) {
+        connection
+            .execute(Statement::from_string(DbBackend::Sqlite, sql))
+            .await?;
+    }
+    Ok(())
+}
+
+fn foreign_key_sql() -> String {
+    format!(
+        ", CONSTRAINT \"{}\" FOREIGN KEY (\"network\") REFERENCES \"network\" (\"chain_id\")",
+        FOREIGN_KEY_NAME
+    )
+}
+
+// Networks are configured per chain: cadence of the scheduler, the keyring that signs and
+// whether the scheduler creates epochs at all, every epoch belongs to a registered network
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Network::Table)
+                    .if_not_exists()
+                    .col(
+                        // PostgreSQL serial doesn't support unsigned
+                        ColumnDef::new(Network::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Network::Name)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    // Epochs refer to their network by chain id
+                    .col(
+                        ColumnDef::new(Network::ChainId)
+                            .big_integer()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Network::EpochIntervalSecs)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Network::KeyringId).integer().not_null())
+                    .col(
+                        ColumnDef::new(Network::Active)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(Network::CreatedDate)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_string())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKeyCreateStatement::new()
+                            .name("link_network_to_keyring")
+                            .from_tbl(Network::Table)
+                            .from_col(Network::KeyringId)
+                            .to_tbl(Keyring::Table)
+                            .to_col(Keyring::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        backfill_networks(manager).await?;
+        match manager.get_database_backend() {
+            DbBackend::Sqlite => {
+                rebuild_sqlite_randomness(manager, |sql| {
+                    let end = sql.rfind(')').ok_or_else(|| {
+                        DbErr::Custom("Unexpected schema of table randomness".to_string())
+                    })?;
+                    Ok(format!(
+                        "{}{}{}",
+                        &sql[..end],
+                        foreign_key_sql(),
+                        &sql[end..]
+                    ))
+                })
+                .await
+            }
+            _ => {
+                manager
+                    .create_foreign_key(
+                        ForeignKey::create()
+                            .name(FOREIGN_KEY_NAME)
+                            .from(Randomness::Table, Randomness::Network)
+                            .to(Network::Table, Network::ChainId)
+                            .to_owned(),
+                    )
+                    .await
+            }
+        }
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        match manager.get_database_backend() {
+            DbBackend::Sqlite => {
+                rebuild_sqlite_randomness(manager, |sql| {
+                    Ok(sql.replacen(&foreign_key_sql(), "", 1))
+                })
+                .await?
+            }
+            _ => {
+                manager
+                    .drop_foreign_key(
+                        ForeignKey::drop()
+                            .name(FOREIGN_KEY_NAME)
+                            .table(Randomness::Table)
+                            .to_owned(),
+                    )
+                    .await?
+            }
+        }
+        manager
+            .drop_table(Table::drop().table(Network::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum Network {
+    Table,
+    Id,
+    Name,
+    ChainId,
+    EpochIntervalSecs,
+    KeyringId,
+    Active,
+    CreatedDate,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Keyring {
+    Table,
+    Id,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Randomness {
+    Table,
+    Network,
+    KeyringId,
+    Epoch,
+}
@@ -0,0 +1,87 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+async fn replace_network_epoch_index(
+    manager: &SchemaManager<'_>,
+    columns: Vec<Randomness>,
+) -> Result<(), DbErr> {
+    manager
+        .drop_index(
+            Index::drop()
+                .name("index_network_epoch")
+                .table(Randomness::Table)
+                .to_owned(),
+        )
+        .await?;
+    let mut index = Index::create()
+        .name("index_network_epoch")
+        .table(Randomness::Table)
+        .unique()
+        .to_owned();
+    for column in columns {
+        index.col(column);
+    }
+    manager.create_index(index).await
+}
+
+// Proofs of alphas supplied by callers are stored with the epochs but they aren't part of
+// the hash chain, they're numbered per network on their own
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Randomness::Table)
+                    .add_column(
+                        ColumnDef::new(Randomness::CallerAlpha)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        replace_network_epoch_index(
+            manager,
+            vec![
+                Randomness::Network,
+                Randomness::CallerAlpha,
+                Randomness::Epoch,
+            ],
+        )
+        .await
+    }
+
+    // Proofs of caller alphas are deleted, they would collide with the epochs
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(Randomness::Table)
+                    .and_where(Expr::col(Randomness::CallerAlpha).eq(true))
+                    .to_owned(),
+            )
+            .await?;
+        replace_network_epoch_index(manager, vec![Randomness::Network, Randomness::Epoch]).await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Randomness::Table)
+                    .drop_column(Randomness::CallerAlpha)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Randomness {
+    Table,
+    Network,
+    Epoch,
+    CallerAlpha,
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Caller alphas seen within the replay window, rows older than the window are pruned
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SeenAlpha::Table)
+                    .if_not_exists()
+                    .col(
+                        // PostgreSQL serial doesn't support unsigned
+                        ColumnDef::new(SeenAlpha::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(SeenAlpha::Network).big_integer().not_null())
+                    .col(ColumnDef::new(SeenAlpha::Alpha).string().not_null())
+                    // Unix time in seconds, it's compared the same way by every backend
+                    .col(ColumnDef::new(SeenAlpha::SeenAt).big_integer().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("index_seen_alpha_network_alpha")
+                    .table(SeenAlpha::Table)
+                    .unique()
+                    .col(SeenAlpha::Network)
+                    .col(SeenAlpha::Alpha)
+                    .to_owned(),
+            )
+            .await?;
+        // Pruning deletes by time
+        manager
+            .create_index(
+                Index::create()
+                    .name("index_seen_alpha_seen_at")
+                    .table(SeenAlpha::Table)
+                    .col(SeenAlpha::SeenAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SeenAlpha::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum SeenAlpha {
+    Table,
+    Id,
+    Network,
+    Alpha,
+    SeenAt,
+}
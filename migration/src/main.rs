@@ -6,9 +6,12 @@ use std::{fs, path::Path};
 async fn main() {
     dotenv().ok();
     let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set.");
-    let file = database_url.replace("sqlite://", "./");
-    if !Path::new(&file).exists() {
-        fs::write(file, "").unwrap();
+    // SQLite needs the database file to exist, other backends are created by the server
+    if let Some(file) = database_url.strip_prefix("sqlite://") {
+        let file = format!("./{}", file);
+        if !Path::new(&file).exists() {
+            fs::write(file, "").unwrap();
+        }
     }
     cli::run_cli(migration::Migrator).await;
 }
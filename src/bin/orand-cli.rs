@@ -0,0 +1,163 @@
+#![deny(warnings)]
+
+use clap::{Parser, Subcommand};
+use ecvrf::{
+    ecproof::ECVRFProof,
+    helper::{generate_keypair, get_address, parse_public_key, scalar_from_b32},
+    secp256k1::{curve::Scalar, SecretKey},
+    ECVRF,
+};
+use std::{env, fs, path::PathBuf, process::ExitCode};
+
+// Offline proof generation and verification, e.g. to check a disputed epoch
+#[derive(Parser)]
+#[command(name = "orand-cli", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Generate a new key pair")]
+    Keygen,
+    // Proofs are deterministic so a disputed epoch can be reproduced
+    // The secret key is read from --secret-file or ORAND_SECRET_KEY
+    #[command(about = "Prove alpha with a secret key, print the hex encoded proof")]
+    Prove {
+        // Deprecated, the key ends up in shell history and process listings
+        #[arg(long, hide = true, conflicts_with = "secret_file")]
+        secret: Option<String>,
+        #[arg(long, help = "File that contains the hex encoded secret key")]
+        secret_file: Option<PathBuf>,
+        #[arg(long)]
+        alpha: String,
+    },
+    #[command(about = "Verify a proof of alpha, exit with 1 if it's invalid")]
+    Verify {
+        #[arg(long)]
+        pubkey: String,
+        #[arg(long)]
+        alpha: String,
+        #[arg(long)]
+        proof: String,
+    },
+}
+
+// Exit code of malformed input, it's the same as clap usage errors
+const EXIT_INVALID_INPUT: u8 = 2;
+
+// Hex encoded secret key of prove, if --secret-file isn't given
+const SECRET_KEY_ENV: &str = "ORAND_SECRET_KEY";
+
+// 0x prefix is optional
+fn decode_hex(s: &str) -> Result<Vec<u8>, String> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s)).map_err(|e| e.to_string())
+}
+
+fn parse_alpha(s: &str) -> Result<Scalar, String> {
+    let alpha: [u8; 32] = decode_hex(s)?
+        .try_into()
+        .map_err(|_| "alpha must be 32 bytes".to_string())?;
+    scalar_from_b32(&alpha).map_err(|e| e.to_string())
+}
+
+// Secret key from --secret-file, the deprecated --secret or ORAND_SECRET_KEY
+fn load_secret_key(
+    secret: Option<String>,
+    secret_file: Option<PathBuf>,
+) -> Result<SecretKey, String> {
+    let secret = match (secret_file, secret, env::var(SECRET_KEY_ENV).ok()) {
+        (Some(path), _, _) => read_secret_file(&path)?,
+        (None, Some(secret), _) => {
+            eprintln!(
+                "warning: --secret is deprecated, use --secret-file or {}",
+                SECRET_KEY_ENV
+            );
+            secret
+        }
+        (None, None, Some(secret)) => secret,
+        (None, None, None) => {
+            return Err(format!(
+                "secret key is required, use --secret-file or {}",
+                SECRET_KEY_ENV
+            ))
+        }
+    };
+    SecretKey::parse_slice(&decode_hex(secret.trim())?)
+        .map_err(|e| format!("invalid secret key: {}", e))
+}
+
+// Files that other users can read are rejected
+fn read_secret_file(path: &PathBuf) -> Result<String, String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?
+            .permissions()
+            .mode();
+        if mode & 0o004 != 0 {
+            return Err(format!(
+                "{} is world readable, restrict it with chmod 600",
+                path.display()
+            ));
+        }
+    }
+    fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn run(command: Command) -> Result<ExitCode, String> {
+    match command {
+        Command::Keygen => {
+            let keypair = generate_keypair();
+            println!(
+                "secret_key: {}",
+                hex::encode(keypair.secret_key.serialize())
+            );
+            println!(
+                "public_key: {}",
+                hex::encode(keypair.public_key.serialize())
+            );
+            println!("address: {}", hex::encode(get_address(keypair.public_key)));
+        }
+        Command::Prove {
+            secret,
+            secret_file,
+            alpha,
+        } => {
+            let secret_key = load_secret_key(secret, secret_file)?;
+            let proof = ECVRF::new(secret_key)
+                .prove_deterministic(&parse_alpha(&alpha)?)
+                .map_err(|e| e.to_string())?;
+            println!("{}", hex::encode(proof.to_bytes()));
+        }
+        Command::Verify {
+            pubkey,
+            alpha,
+            proof,
+        } => {
+            let public_key = parse_public_key(&pubkey).map_err(|e| e.to_string())?;
+            let proof = ECVRFProof::from_bytes(&decode_hex(&proof)?)
+                .map_err(|e| format!("invalid proof: {}", e))?;
+            let valid = proof
+                .verify_with_pubkey(&parse_alpha(&alpha)?, &public_key)
+                .map_err(|e| e.to_string())?;
+            println!("{}", if valid { "valid" } else { "invalid" });
+            if !valid {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse().command) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(EXIT_INVALID_INPUT)
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use crate::{database::DbConfig, ids::NetworkId, server::ServerConfig};
+use serde::Deserialize;
+use std::{collections::HashSet, env, fs, path::Path};
+
+// Cadence of networks that are registered without an epoch interval
+pub const DEFAULT_EPOCH_INTERVAL_SECS: i64 = 60;
+
+// Seconds epochs in flight are waited for on shutdown
+pub const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+
+// [database] section, DATABASE_URL overrides the URL
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    pub url: String,
+    #[serde(flatten)]
+    pub pool: DbConfig,
+}
+
+// [[network]] table, networks that are already registered keep their configuration
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct NetworkConfig {
+    pub chain_id: NetworkId,
+    // Chain id is the name if it isn't set
+    #[serde(default)]
+    pub name: Option<String>,
+    // epoch_interval_secs of the config if it isn't set
+    #[serde(default)]
+    pub epoch_interval_secs: Option<i64>,
+}
+
+impl NetworkConfig {
+    pub fn new(chain_id: NetworkId) -> Self {
+        Self {
+            chain_id,
+            name: None,
+            epoch_interval_secs: None,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| self.chain_id.to_string())
+    }
+}
+
+// Configuration of the node, it's read from a TOML file and environment variables override it
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    // Default cadence of networks in seconds, the scheduler only runs if it's set
+    pub epoch_interval_secs: Option<i64>,
+    // Epochs a network missed while the node was down are created at startup, at most this many
+    pub max_backfill_epochs: Option<u64>,
+    // Epochs being created on SIGTERM are committed within this many seconds before exiting
+    pub shutdown_timeout_secs: u64,
+    pub database: DatabaseConfig,
+    pub server: ServerConfig,
+    #[serde(rename = "network")]
+    pub networks: Vec<NetworkConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let server = ServerConfig::default();
+        Self {
+            epoch_interval_secs: None,
+            max_backfill_epochs: None,
+            shutdown_timeout_secs: DEFAULT_SHUTDOWN_TIMEOUT_SECS,
+            database: DatabaseConfig::default(),
+            networks: server
+                .networks
+                .iter()
+                .map(|id| NetworkConfig::new(*id))
+                .collect(),
+            server,
+        }
+    }
+}
+
+impl Config {
+    // Read a TOML config file, environment variables override its settings
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let toml = fs::read_to_string(path)
+            .map_err(|e| format!("Can not read {}: {}", path.display(), e))?;
+        Self::parse(&toml)?.with_env()?.validated()
+    }
+
+    // Default configuration overridden by environment variables, there is no config file
+    pub fn from_env() -> Result<Self, String> {
+        Self::default().with_env()?.validated()
+    }
+
+    // Parse a TOML config, environment variables aren't read
+    pub fn from_toml(toml: &str) -> Result<Self, String> {
+        Self::parse(toml)?.validated()
+    }
+
+    fn parse(toml: &str) -> Result<Self, String> {
+        toml::from_str(toml).map_err(|e| format!("Invalid config: {}", e))
+    }
+
+    // DATABASE_URL, ORAND_EPOCH_INTERVAL, ORAND_MAX_BACKFILL, ORAND_SHUTDOWN_TIMEOUT and
+    // ORAND_NETWORKS, a comma separated list of chain ids, and the variables of each section
+    // override the config
+    fn with_env(self) -> Result<Self, String> {
+        let networks = match env::var("ORAND_NETWORKS") {
+            // Listed networks keep their settings of the config, others are dropped
+            Ok(list) => {
+                list.split(',')
+                    .map(|network| {
+                        let chain_id = network.trim().parse().map_err(|_| {
+                            format!("Invalid network in ORAND_NETWORKS: {}", network)
+                        })?;
+                        Ok(self
+                            .networks
+                            .iter()
+                            .find(|n| n.chain_id == chain_id)
+                            .cloned()
+                            .unwrap_or_else(|| NetworkConfig::new(chain_id)))
+                    })
+                    .collect::<Result<_, String>>()?
+            }
+            Err(_) => self.networks,
+        };
+        Ok(Self {
+            epoch_interval_secs: match env::var("ORAND_EPOCH_INTERVAL") {
+                Ok(interval) => {
+                    Some(interval.parse().map_err(|_| {
+                        format!("Invalid value of ORAND_EPOCH_INTERVAL: {}", interval)
+                    })?)
+                }
+                Err(_) => self.epoch_interval_secs,
+            },
+            max_backfill_epochs: match env::var("ORAND_MAX_BACKFILL") {
+                Ok(max) => Some(
+                    max.parse()
+                        .map_err(|_| format!("Invalid value of ORAND_MAX_BACKFILL: {}", max))?,
+                ),
+                Err(_) => self.max_backfill_epochs,
+            },
+            shutdown_timeout_secs: match env::var("ORAND_SHUTDOWN_TIMEOUT") {
+                Ok(timeout) => timeout
+                    .parse()
+                    .map_err(|_| format!("Invalid value of ORAND_SHUTDOWN_TIMEOUT: {}", timeout))?,
+                Err(_) => self.shutdown_timeout_secs,
+            },
+            database: DatabaseConfig {
+                url: env::var("DATABASE_URL").unwrap_or(self.database.url),
+                pool: self.database.pool.with_env().map_err(|e| e.to_string())?,
+            },
+            server: self.server.with_env()?,
+            networks,
+        })
+    }
+
+    // Reject settings the node can't run with, networks of the server are the configured ones
+    fn validated(mut self) -> Result<Self, String> {
+        if self.database.url.is_empty() {
+            return Err("database.url or DATABASE_URL must be set".to_string());
+        }
+        if self.database.pool.min_connections > self.database.pool.max_connections {
+            return Err("min_connections must not exceed max_connections".to_string());
+        }
+        if let Some(rate_limit) = &self.server.rate_limit {
+            rate_limit.validate()?;
+        }
+        if self.server.replay_window_secs == Some(0) {
+            return Err("replay_window_secs must be greater than 0".to_string());
+        }
+        if self
+            .epoch_interval_secs
+            .is_some_and(|interval| interval <= 0)
+        {
+            return Err("epoch_interval_secs must be greater than 0".to_string());
+        }
+        if self.shutdown_timeout_secs == 0 {
+            return Err("shutdown_timeout_secs must be greater than 0".to_string());
+        }
+        let mut chain_ids = HashSet::new();
+        for network in self.networks.iter() {
+            if network.chain_id.0 <= 0 {
+                return Err(format!("Invalid chain id: {}", network.chain_id));
+            }
+            if !chain_ids.insert(network.chain_id) {
+                return Err(format!("Network {} is configured twice", network.chain_id));
+            }
+            if network
+                .epoch_interval_secs
+                .is_some_and(|interval| interval <= 0)
+            {
+                return Err(format!(
+                    "epoch_interval_secs of network {} must be greater than 0",
+                    network.chain_id
+                ));
+            }
+        }
+        self.server.networks = self.networks.iter().map(|n| n.chain_id).collect();
+        Ok(self)
+    }
+
+    // Seconds between epochs of a network that is registered with this config
+    pub fn epoch_interval_of(&self, network: &NetworkConfig) -> i64 {
+        network
+            .epoch_interval_secs
+            .or(self.epoch_interval_secs)
+            .unwrap_or(DEFAULT_EPOCH_INTERVAL_SECS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::SocketAddr, time::Duration};
+
+    const EXAMPLE: &str = include_str!("../../orand.example.toml");
+
+    #[test]
+    fn example_config_is_parsed() {
+        let config = Config::from_toml(EXAMPLE).unwrap();
+        assert_eq!(config.epoch_interval_secs, Some(60));
+        assert_eq!(config.max_backfill_epochs, None);
+        assert_eq!(config.shutdown_timeout_secs, 30);
+        assert_eq!(config.database.url, "sqlite://orand.db?mode=rwc");
+        assert_eq!(config.database.pool, DbConfig::default());
+        assert_eq!(config.database.pool.idle_timeout, Duration::from_secs(600));
+        assert_eq!(
+            config.server.bind_address,
+            "127.0.0.1:3000".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(config.server.keyring_name, "chiro");
+        assert_eq!(config.server.admin_token, None);
+        assert_eq!(config.server.networks, vec![56, 97]);
+        assert_eq!(config.server.rate_limit, ServerConfig::default().rate_limit);
+        let names: Vec<_> = config.networks.iter().map(NetworkConfig::name).collect();
+        assert_eq!(names, vec!["bnb", "bnb-testnet"]);
+        let intervals: Vec<_> = config
+            .networks
+            .iter()
+            .map(|network| config.epoch_interval_of(network))
+            .collect();
+        assert_eq!(intervals, vec![60, 30]);
+    }
+
+    #[test]
+    fn omitted_settings_are_defaults() {
+        let config = Config::from_toml("[database]\nurl = \"sqlite::memory:\"").unwrap();
+        assert_eq!(config.epoch_interval_secs, None);
+        assert_eq!(config.shutdown_timeout_secs, DEFAULT_SHUTDOWN_TIMEOUT_SECS);
+        assert_eq!(config.server, ServerConfig::default());
+        assert_eq!(config.networks, vec![NetworkConfig::new(NetworkId(56))]);
+        assert_eq!(
+            config.epoch_interval_of(&config.networks[0]),
+            DEFAULT_EPOCH_INTERVAL_SECS
+        );
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let url = "[database]\nurl = \"sqlite::memory:\"\n";
+        for toml in [
+            "[server]\nkeyring = \"chiro\"".to_string(),
+            format!("epoch_interval_secs = 0\n{}", url),
+            format!("shutdown_timeout_secs = 0\n{}", url),
+            format!(
+                "{}[[network]]\nchain_id = 56\nepoch_interval_secs = -1",
+                url
+            ),
+            format!(
+                "{}[[network]]\nchain_id = 56\n[[network]]\nchain_id = 56",
+                url
+            ),
+            format!("{}[[network]]\nchain_id = 0", url),
+            format!("{}min_connections = 5\nmax_connections = 2", url),
+            format!("{}[server.rate_limit]\nburst = 0", url),
+            format!("{}[server]\nreplay_window_secs = 0", url),
+            format!("{}[server]\nbind_address = \"localhost\"", url),
+        ] {
+            assert!(Config::from_toml(&toml).is_err(), "{}", toml);
+        }
+    }
+}
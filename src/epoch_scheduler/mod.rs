@@ -0,0 +1,1550 @@
+use crate::{
+    ids::{EpochId, NetworkId},
+    keyring::Model as ModelKeyring,
+    metrics,
+    randomness::Model as ModelRandomness,
+    shutdown::Shutdown,
+    sqlitedb::{is_unique_violation, SqliteDB},
+    store::{InsertError, RandomnessStore},
+};
+use ecvrf::{
+    ecproof::ECVRFProof,
+    helper::{random_bytes, scalar_from_b32, scalar_from_bytes_reduced},
+    secp256k1::{curve::Scalar, SecretKey},
+    ECVRF,
+};
+use sea_orm::DbErr;
+use serde_json::json;
+use std::{collections::BTreeMap, fmt, sync::Arc, time::Duration};
+use tokio::{
+    sync::{broadcast, mpsc, oneshot},
+    task::JoinHandle,
+    time::{self, Instant, MissedTickBehavior},
+};
+
+#[derive(Debug)]
+pub enum EpochError {
+    // Keyring with the given name does not exist
+    KeyringNotFound(String),
+    // Keyring or previous epoch can not be decoded
+    InvalidRecord(String),
+    // Network, epoch that was created concurrently
+    Conflict(NetworkId, EpochId),
+    // Network already has epochs
+    GenesisExists(NetworkId),
+    // Chain id that isn't in the network table
+    NetworkNotFound(NetworkId),
+    // Network, epoch that isn't stored
+    EpochNotFound(NetworkId, EpochId),
+    // Caller alpha that is an alpha or y of another record
+    AlphaTaken(String),
+    // Network whose tip is the largest epoch, e.g. after a bad import
+    EpochOverflow(NetworkId),
+    Database(DbErr),
+}
+
+impl fmt::Display for EpochError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EpochError::KeyringNotFound(name) => write!(f, "Keyring {} not found", name),
+            EpochError::InvalidRecord(e) => write!(f, "Invalid record: {}", e),
+            EpochError::Conflict(network, epoch) => {
+                write!(f, "epoch {} of network {} already exists", epoch, network)
+            }
+            EpochError::GenesisExists(network) => {
+                write!(f, "genesis of network {} already exists", network)
+            }
+            EpochError::NetworkNotFound(network) => write!(f, "Network {} not found", network),
+            EpochError::EpochNotFound(network, epoch) => {
+                write!(f, "epoch {} of network {} not found", epoch, network)
+            }
+            EpochError::AlphaTaken(alpha) => write!(f, "alpha {} is taken", alpha),
+            EpochError::EpochOverflow(network) => {
+                write!(f, "next epoch of network {} would overflow", network)
+            }
+            EpochError::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for EpochError {}
+
+impl From<DbErr> for EpochError {
+    fn from(e: DbErr) -> Self {
+        EpochError::Database(e)
+    }
+}
+
+// Who requested an epoch, it's written to the audit log with the epoch
+// Default is the scheduler, it has neither an API key nor a request
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trigger {
+    // Id of the API key
+    pub actor: Option<i32>,
+    // SHA-256 of the JSON-RPC request, hex encoded
+    pub request_hash: Option<String>,
+}
+
+// Keyring that signs the epochs of a network, its id is recorded with them
+pub struct Signer {
+    pub keyring_id: i32,
+    pub secret_key: SecretKey,
+}
+
+impl Signer {
+    // Keyring of the given name, the network must be registered
+    pub async fn load(
+        db: &SqliteDB,
+        keyring_name: &str,
+        network: NetworkId,
+    ) -> Result<Self, EpochError> {
+        let (keyring_record, secret_key) = load_signer(db, keyring_name, network).await?;
+        Ok(Self {
+            keyring_id: keyring_record.id,
+            secret_key,
+        })
+    }
+}
+
+// Record epoch 0 of a network with the given seed as alpha
+// Retrying with the same seed returns the recorded genesis
+pub async fn create_genesis_epoch(
+    db: &SqliteDB,
+    keyring_name: &str,
+    network: NetworkId,
+    seed: [u8; 32],
+    trigger: &Trigger,
+) -> Result<ModelRandomness, EpochError> {
+    let randomness = db.table_randomness().await;
+    let alpha = scalar_from_bytes_reduced(&seed);
+    if let Some(genesis) = find_proven_epoch(&randomness, network, &alpha).await? {
+        if genesis.epoch == 0 {
+            return Ok(genesis);
+        }
+    }
+    if randomness.find_latest_epoch(network).await?.is_some() {
+        return Err(EpochError::GenesisExists(network));
+    }
+    let signer = Signer::load(db, keyring_name, network).await?;
+    prove_epoch(
+        &randomness,
+        &signer,
+        network,
+        EpochId(0),
+        alpha,
+        None,
+        trigger,
+    )
+    .await
+}
+
+// Prove and store the next epoch of a network, alpha is the randomness of the previous epoch
+pub async fn new_epoch(
+    db: &SqliteDB,
+    keyring_name: &str,
+    network: NetworkId,
+    block_number: Option<i64>,
+    trigger: &Trigger,
+) -> Result<ModelRandomness, EpochError> {
+    let signer = Signer::load(db, keyring_name, network).await?;
+    chain_epoch(
+        &db.table_randomness().await,
+        &signer,
+        network,
+        block_number,
+        trigger,
+    )
+    .await
+}
+
+// Next epoch of a network in the store, its alpha is the y of the latest epoch
+pub async fn chain_epoch(
+    store: &dyn RandomnessStore,
+    signer: &Signer,
+    network: NetworkId,
+    block_number: Option<i64>,
+    trigger: &Trigger,
+) -> Result<ModelRandomness, EpochError> {
+    let latest_epoch_record = store.latest(network).await?;
+    // A retried request returns the epoch it created while that's the tip, the same request
+    // after other epochs is a new one, e.g. of a relayer that restarted its JSON-RPC ids
+    if let (Some(tip), Some(request_hash)) = (&latest_epoch_record, &trigger.request_hash) {
+        if let Some(record) = store
+            .find_by_request(network, trigger.actor, request_hash)
+            .await?
+        {
+            if record.epoch == tip.epoch {
+                return Ok(record);
+            }
+        }
+    }
+    let (alpha, epoch) = match latest_epoch_record {
+        Some(latest_epoch) => (
+            next_alpha(&latest_epoch)?,
+            next_epoch(network, latest_epoch.epoch)?,
+        ),
+        None => {
+            // Get alpha from random entropy
+            let mut buf = [0u8; 32];
+            random_bytes(&mut buf);
+            (scalar_from_bytes_reduced(&buf), EpochId(0))
+        }
+    };
+    prove_epoch(store, signer, network, epoch, alpha, block_number, trigger).await
+}
+
+// Epoch after the given one, it's an error rather than a wrap at the end of i64
+fn next_epoch(network: NetworkId, epoch: EpochId) -> Result<EpochId, EpochError> {
+    epoch
+        .checked_next()
+        .ok_or(EpochError::EpochOverflow(network))
+}
+
+// Alpha of the epoch after the given one, epochs without a proof blob fall back to y
+fn next_alpha(record: &ModelRandomness) -> Result<Scalar, EpochError> {
+    if let Some(proof) = &record.proof {
+        return ECVRFProof::from_bytes(proof)
+            .map(|proof| proof.next_alpha())
+            .map_err(|e| EpochError::InvalidRecord(e.to_string()));
+    }
+    let y: [u8; 32] = hex::decode(&record.y)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| {
+            EpochError::InvalidRecord("can not decode previous randomness".to_string())
+        })?;
+    Ok(scalar_from_bytes_reduced(&y))
+}
+
+// Epoch of the network that was already proven for alpha, e.g. by a retried request
+async fn find_proven_epoch(
+    store: &dyn RandomnessStore,
+    network: NetworkId,
+    alpha: &Scalar,
+) -> Result<Option<ModelRandomness>, DbErr> {
+    store
+        .find_by_network_alpha(network, &hex::encode(alpha.b32()))
+        .await
+}
+
+// Keyring that signs for a registered network
+async fn load_signer(
+    db: &SqliteDB,
+    keyring_name: &str,
+    network: NetworkId,
+) -> Result<(ModelKeyring, SecretKey), EpochError> {
+    if db
+        .table_network()
+        .await
+        .find_by_chain_id(network)
+        .await?
+        .is_none()
+    {
+        return Err(EpochError::NetworkNotFound(network));
+    }
+    db.table_keyring()
+        .await
+        .load_keyring(keyring_name)
+        .await?
+        .ok_or_else(|| EpochError::KeyringNotFound(keyring_name.to_string()))
+}
+
+// Prove alpha and build its randomness record, epochs of the hash chain are signed
+// Proofs of caller alphas aren't, the signature commits to the position in the chain
+fn proof_record(
+    secret_key: SecretKey,
+    keyring_id: i32,
+    network: NetworkId,
+    epoch: EpochId,
+    alpha: &Scalar,
+    block_number: Option<i64>,
+    caller_alpha: bool,
+) -> Result<serde_json::Value, EpochError> {
+    let vrf = ECVRF::new(secret_key);
+
+    // Only public values are recorded, the secret key must never be a field
+    let started = std::time::Instant::now();
+    let contract_proof =
+        tracing::debug_span!("prove", network = network.0, epoch = epoch.0, caller_alpha)
+            .in_scope(|| vrf.prove_contract(alpha))
+            .map_err(|e| EpochError::InvalidRecord(e.to_string()))?;
+    metrics::record_proof(network, started.elapsed());
+    let signature = if caller_alpha {
+        None
+    } else {
+        // prove_contract consumes its instance
+        let signature = ECVRF::new(secret_key)
+            .sign_epoch(network.0, epoch.0, &contract_proof.y.b32())
+            .map_err(|e| EpochError::InvalidRecord(e.to_string()))?;
+        Some(hex::encode(signature.to_bytes()))
+    };
+    let proof = ECVRFProof::new(
+        contract_proof.gamma,
+        contract_proof.c,
+        contract_proof.s,
+        contract_proof.y,
+        contract_proof.pk,
+    );
+    let gamma = [contract_proof.gamma.x.b32(), contract_proof.gamma.y.b32()].concat();
+    let witness_gamma = [
+        contract_proof.witness_gamma.x.b32(),
+        contract_proof.witness_gamma.y.b32(),
+    ]
+    .concat();
+    let witness_hash = [
+        contract_proof.witness_hash.x.b32(),
+        contract_proof.witness_hash.y.b32(),
+    ]
+    .concat();
+
+    Ok(json!({
+    "network": network,
+    "keyring_id": keyring_id,
+    "epoch": epoch,
+    "alpha": hex::encode(alpha.b32()),
+    "gamma": hex::encode(gamma),
+    "c": hex::encode(contract_proof.c.b32()),
+    "s": hex::encode(contract_proof.s.b32()),
+    "y": hex::encode(contract_proof.y.b32()),
+    "witness_address": hex::encode(contract_proof.witness_address.b32())[24..64],
+    "witness_gamma": hex::encode(witness_gamma),
+    "witness_hash": hex::encode(witness_hash),
+    "inverse_z": hex::encode(contract_proof.inverse_z.b32()),
+    "proof": hex::encode(proof.to_bytes()),
+    "block_number": block_number,
+    "signature": signature,
+    "caller_alpha": caller_alpha,
+    }))
+}
+
+// Alpha is derived from the chain tip, if it was proven already the recorded epoch is
+// returned instead of a duplicate, only new epochs are audited
+// Conflict is only returned if the epoch collides with a record that can't be reloaded
+async fn prove_epoch(
+    store: &dyn RandomnessStore,
+    signer: &Signer,
+    network: NetworkId,
+    epoch: EpochId,
+    alpha: Scalar,
+    block_number: Option<i64>,
+    trigger: &Trigger,
+) -> Result<ModelRandomness, EpochError> {
+    if let Some(record) = find_proven_epoch(store, network, &alpha).await? {
+        return Ok(record);
+    }
+    let record = proof_record(
+        signer.secret_key,
+        signer.keyring_id,
+        network,
+        epoch,
+        &alpha,
+        block_number,
+        false,
+    )?;
+
+    let inserted = store
+        .insert(record, trigger.actor, trigger.request_hash.clone())
+        .await;
+    match inserted {
+        Ok(record) => Ok(record),
+        // Another node created the epoch concurrently, the first record wins and it's
+        // returned instead, e.g. a genesis of another seed is found by its index
+        Err(InsertError::Conflict) => match find_proven_epoch(store, network, &alpha).await? {
+            Some(record) => Ok(record),
+            None => store
+                .get(network, epoch)
+                .await?
+                .ok_or(EpochError::Conflict(network, epoch)),
+        },
+        Err(InsertError::Database(e)) => Err(EpochError::Database(e)),
+    }
+}
+
+// Prove an alpha supplied by a caller, e.g. a commitment of an application, proving the
+// same alpha again returns the recorded proof
+// Alphas of epochs and y of epochs, which are the alphas of the next ones, are taken
+pub async fn prove_caller_alpha(
+    db: &SqliteDB,
+    keyring_name: &str,
+    network: NetworkId,
+    alpha: Scalar,
+) -> Result<ModelRandomness, EpochError> {
+    let randomness = db.table_randomness().await;
+    let alpha_hex = hex::encode(alpha.b32());
+    if let Some(record) = randomness.find_caller_alpha(network, &alpha_hex).await? {
+        return Ok(record);
+    }
+    let (keyring_record, secret_key) = load_signer(db, keyring_name, network).await?;
+    if randomness.is_alpha_taken(&alpha_hex).await? {
+        return Err(EpochError::AlphaTaken(alpha_hex));
+    }
+    let epoch = match randomness.find_latest_caller_alpha(network).await? {
+        Some(latest) => next_epoch(network, latest.epoch)?,
+        None => EpochId(0),
+    };
+    let record = proof_record(
+        secret_key,
+        keyring_record.id,
+        network,
+        epoch,
+        &alpha,
+        None,
+        true,
+    )?;
+    match randomness.insert_returning(record).await {
+        Ok(record) => Ok(record),
+        // Same alpha was proven concurrently
+        Err(e) if is_unique_violation(&e) => randomness
+            .find_caller_alpha(network, &alpha_hex)
+            .await?
+            .ok_or(EpochError::Conflict(network, epoch)),
+        Err(e) => Err(EpochError::Database(e)),
+    }
+}
+
+// Prove an alpha supplied by a caller without recording it, nothing is written
+// Taken alphas are rejected, y of the tip would give away the next epoch
+pub async fn simulate_prove(
+    db: &SqliteDB,
+    keyring_name: &str,
+    network: NetworkId,
+    alpha: Scalar,
+) -> Result<ECVRFProof, EpochError> {
+    let (_, secret_key) = load_signer(db, keyring_name, network).await?;
+    let alpha_hex = hex::encode(alpha.b32());
+    if db
+        .table_randomness()
+        .await
+        .is_alpha_taken(&alpha_hex)
+        .await?
+    {
+        return Err(EpochError::AlphaTaken(alpha_hex));
+    }
+    tracing::debug_span!("simulate_prove", network = network.0)
+        .in_scope(|| ECVRF::new(secret_key).prove(&alpha))
+        .map_err(|e| EpochError::InvalidRecord(e.to_string()))
+}
+
+// Prove a stored epoch again if it fails its integrity check and overwrite its columns with
+// the new proof, the change is audited. Gamma and y only depend on alpha and the keyring that
+// signed the epoch so its output is the one it had, c and s are those of the new proof
+// The record is returned with whether it was healed, an intact epoch isn't written
+// Alpha must still be the y of the previous epoch, a corrupted alpha would be proven as
+// if it were the right one
+pub async fn heal_epoch(
+    db: &SqliteDB,
+    network: NetworkId,
+    epoch: EpochId,
+    trigger: &Trigger,
+) -> Result<(ModelRandomness, bool), EpochError> {
+    let randomness = db.table_randomness().await;
+    let record = randomness
+        .find_epoch(network, epoch)
+        .await?
+        .ok_or(EpochError::EpochNotFound(network, epoch))?;
+    let keyring = db.table_keyring().await;
+    let keyring_record = keyring
+        .find_by_id(record.keyring_id)
+        .await?
+        .ok_or_else(|| EpochError::KeyringNotFound(record.keyring_id.to_string()))?;
+    let public_key = keyring_record
+        .parse_public_key()
+        .map_err(|e| EpochError::InvalidRecord(e.to_string()))?;
+    let corruption = match record.verify_integrity(&public_key) {
+        Ok(()) => return Ok((record, false)),
+        Err(e) => e,
+    };
+    let (_, secret_key) = keyring
+        .load_keyring(&keyring_record.username)
+        .await?
+        .ok_or_else(|| EpochError::KeyringNotFound(keyring_record.username.clone()))?;
+    let alpha = hex::decode(&record.alpha)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .and_then(|b: [u8; 32]| scalar_from_b32(&b).ok())
+        .ok_or_else(|| EpochError::InvalidRecord("can not decode alpha".to_string()))?;
+    // Genesis has no previous epoch, neither has the first one that is left after pruning
+    let previous = match epoch.0 {
+        0 => None,
+        n => randomness.find_epoch(network, EpochId(n - 1)).await?,
+    };
+    if let Some(previous) = previous {
+        if next_alpha(&previous)? != alpha {
+            return Err(EpochError::InvalidRecord(format!(
+                "alpha of epoch {} of network {} isn't the y of the previous epoch",
+                epoch, network
+            )));
+        }
+    }
+    let healed = proof_record(
+        secret_key,
+        record.keyring_id,
+        network,
+        epoch,
+        &alpha,
+        record.block_number,
+        record.caller_alpha,
+    )?;
+    // Nothing is written unless the new columns pass the check the old ones failed
+    serde_json::from_value::<ModelRandomness>(healed.clone())
+        .map_err(|e| EpochError::InvalidRecord(e.to_string()))?
+        .verify_integrity(&public_key)
+        .map_err(|e| {
+            EpochError::InvalidRecord(format!(
+                "epoch {} of network {} can not be healed: {}",
+                epoch, network, e
+            ))
+        })?;
+    tracing::warn!(
+        network = network.0,
+        epoch = epoch.0,
+        "healing epoch that failed its integrity check: {}",
+        corruption
+    );
+    let record = randomness
+        .update_audited(
+            record.id,
+            healed,
+            trigger.actor,
+            trigger.request_hash.clone(),
+        )
+        .await?;
+    Ok((record, true))
+}
+
+// Active networks with the name of their keyring, grouped by epoch interval in seconds
+// so each group can be run by its own scheduler, networks without a keyring are skipped
+pub async fn active_networks(
+    db: &SqliteDB,
+) -> Result<BTreeMap<u64, Vec<(NetworkId, String)>>, EpochError> {
+    let keyring = db.table_keyring().await;
+    let mut groups: BTreeMap<u64, Vec<(NetworkId, String)>> = BTreeMap::new();
+    for network in db.table_network().await.find_active().await? {
+        let keyring_record = match keyring.find_keyring_for_network(network.chain_id).await? {
+            Some(keyring_record) => keyring_record,
+            None => {
+                tracing::warn!(
+                    "Keyring {} of network {} not found, skipping it",
+                    network.keyring_id,
+                    network.chain_id
+                );
+                continue;
+            }
+        };
+        let interval = u64::try_from(network.epoch_interval_secs)
+            .ok()
+            .filter(|interval| *interval > 0)
+            .ok_or_else(|| {
+                EpochError::InvalidRecord(format!("epoch interval of network {}", network.chain_id))
+            })?;
+        groups
+            .entry(interval)
+            .or_default()
+            .push((network.chain_id, keyring_record.username));
+    }
+    Ok(groups)
+}
+
+// Epochs that were due since the tip created at the given unix time, at most max_backfill
+fn epochs_due(tip_created: i64, now: i64, interval: u64, max_backfill: u64) -> u64 {
+    u64::try_from(now - tip_created)
+        .map(|elapsed| elapsed / interval)
+        .unwrap_or_default()
+        .min(max_backfill)
+}
+
+// Generate a new epoch for each network periodically
+pub struct EpochScheduler {
+    db: Arc<SqliteDB>,
+    // Network and the name of the keyring that signs its epochs
+    networks: Vec<(NetworkId, String)>,
+    epochs: Option<broadcast::Sender<ModelRandomness>>,
+    shutdown: Shutdown,
+}
+
+// Stop the scheduler, the running round is finished first
+pub struct SchedulerHandle {
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl SchedulerHandle {
+    pub async fn stop(self) {
+        let _ = self.stop.send(());
+        let _ = self.task.await;
+    }
+}
+
+impl EpochScheduler {
+    pub fn new(db: Arc<SqliteDB>, networks: Vec<(NetworkId, String)>) -> Self {
+        Self {
+            db,
+            networks,
+            epochs: None,
+            shutdown: Shutdown::new(),
+        }
+    }
+
+    // Rounds stop once shutdown is triggered, the epoch that is being created is drained
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    // Publish created epochs, e.g. to WebSocket subscribers
+    pub fn with_notifier(mut self, epochs: broadcast::Sender<ModelRandomness>) -> Self {
+        self.epochs = Some(epochs);
+        self
+    }
+
+    // Generate epochs every interval, the first round starts right away
+    pub fn start(self, interval: Duration) -> SchedulerHandle {
+        let (clock, ticks) = mpsc::channel(1);
+        tokio::spawn(async move {
+            let mut interval = time::interval(interval);
+            // Skip rounds that were missed because proving took too long
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            loop {
+                let tick = interval.tick().await;
+                if clock.send(tick).await.is_err() {
+                    break;
+                }
+            }
+        });
+        self.start_with_clock(ticks)
+    }
+
+    // Generate epochs on every tick of the given clock until it's closed or stopped
+    pub fn start_with_clock(self, mut ticks: mpsc::Receiver<Instant>) -> SchedulerHandle {
+        let (stop, mut stopped) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let shutdown = self.shutdown.triggered();
+            tokio::pin!(shutdown);
+            loop {
+                tokio::select! {
+                    // Ticks that are already queued are handled before stopping, but not
+                    // after shutdown
+                    biased;
+                    _ = &mut shutdown => break,
+                    tick = ticks.recv() => match tick {
+                        Some(_) => self.run_once().await,
+                        None => break,
+                    },
+                    _ = &mut stopped => break,
+                }
+            }
+        });
+        SchedulerHandle { stop, task }
+    }
+
+    // Fill the epochs networks missed while the node was down, each is chained to the previous
+    // one, networks without epochs are left to the first round
+    pub async fn catch_up(&self, interval: u64, now: i64, max_backfill: u64) -> u64 {
+        let mut created = 0;
+        for (network, keyring_name) in self.networks.iter() {
+            let tip = match self
+                .db
+                .table_randomness()
+                .await
+                .find_latest_epoch(*network)
+                .await
+            {
+                Ok(Some(tip)) => tip,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::warn!("Can not catch up network {}: {}", network, e);
+                    continue;
+                }
+            };
+            let due = epochs_due(tip.created_date.timestamp(), now, interval, max_backfill);
+            let mut caught_up = 0;
+            for _ in 0..due {
+                let Some(_work) = self.shutdown.start_work() else {
+                    return created + caught_up;
+                };
+                match new_epoch(&self.db, keyring_name, *network, None, &Trigger::default()).await {
+                    Ok(record) => {
+                        caught_up += 1;
+                        if let Some(epochs) = &self.epochs {
+                            let _ = epochs.send(record);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("Can not catch up network {}: {}", network, e);
+                        break;
+                    }
+                }
+            }
+            if caught_up > 0 {
+                tracing::info!("Caught up {} epochs of network {}", caught_up, network);
+            }
+            created += caught_up;
+        }
+        created
+    }
+
+    // One round, a failing network doesn't stop the others, networks that are left once
+    // shutdown is triggered are skipped
+    pub async fn run_once(&self) {
+        for (network, keyring_name) in self.networks.iter() {
+            let Some(_work) = self.shutdown.start_work() else {
+                return;
+            };
+            match new_epoch(&self.db, keyring_name, *network, None, &Trigger::default()).await {
+                Ok(record) => {
+                    tracing::info!("Created epoch {} of network {}", record.epoch, network);
+                    if let Some(epochs) = &self.epochs {
+                        let _ = epochs.send(record);
+                    }
+                }
+                Err(e) => tracing::warn!("Can not create epoch of network {}: {}", network, e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{kek::Kek, randomness::IntegrityError, store::MemoryStore};
+    use ecvrf::{
+        helper::{
+            affine_composer, calculate_witness_address, ecmult, generate_keypair, get_address,
+            jacobian_to_affine, keccak256_affine_scalar, scalar_from_b32,
+        },
+        secp256k1::{
+            curve::{Affine, Field, Jacobian},
+            PublicKey, ECMULT_CONTEXT,
+        },
+        signature::RecoverableSignature,
+    };
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{ConnectionTrait, DbBackend, Statement, TransactionTrait};
+
+    async fn memory_db() -> SqliteDB {
+        let sqlite = SqliteDB::new("sqlite::memory:".to_string())
+            .await
+            .with_kek(Kek::new(&[0u8; 32]));
+        Migrator::up(sqlite.connection(), None).await.unwrap();
+        let keypair = generate_keypair();
+        sqlite
+            .table_keyring()
+            .await
+            .store_keyring(
+                json!({
+                    "username": "chiro",
+                    "hmac_secret": "00",
+                    "public_key": hex::encode(keypair.public_key.serialize()),
+                    "address": hex::encode(get_address(keypair.public_key)),
+                }),
+                &keypair.secret_key,
+            )
+            .await
+            .unwrap();
+        for chain_id in [56, 97] {
+            insert_network(&sqlite, chain_id, 60).await;
+        }
+        sqlite
+    }
+
+    async fn insert_network(sqlite: &SqliteDB, chain_id: i64, epoch_interval_secs: i64) {
+        sqlite
+            .table_network()
+            .await
+            .insert_returning(json!({
+                "name": format!("chain-{}", chain_id),
+                "chain_id": chain_id,
+                "epoch_interval_secs": epoch_interval_secs,
+                "keyring_id": 1,
+            }))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn scheduler_chains_epochs_on_every_tick() {
+        let db = Arc::new(memory_db().await);
+        // Keyring of network 97 is missing, it must not stop network 56
+        let scheduler = EpochScheduler::new(
+            db.clone(),
+            vec![
+                (NetworkId(97), "missing".to_string()),
+                (NetworkId(56), "chiro".to_string()),
+            ],
+        );
+        let (clock, ticks) = mpsc::channel(3);
+        let handle = scheduler.start_with_clock(ticks);
+        for _ in 0..3 {
+            clock.send(Instant::now()).await.unwrap();
+        }
+        handle.stop().await;
+
+        let randomness = db.table_randomness().await;
+        let epochs = randomness
+            .find_epoch_range(NetworkId(56), EpochId(0), EpochId(10))
+            .await
+            .unwrap();
+        assert_eq!(
+            epochs.iter().map(|e| e.epoch).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        for pair in epochs.windows(2) {
+            assert_eq!(pair[1].alpha, pair[0].y);
+        }
+        assert!(randomness
+            .find_latest_epoch(NetworkId(97))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn epoch_in_flight_is_committed_on_shutdown() {
+        let db = Arc::new(memory_db().await);
+        let shutdown = Shutdown::new();
+        let scheduler = EpochScheduler::new(db.clone(), vec![(NetworkId(56), "chiro".to_string())])
+            .with_shutdown(shutdown.clone());
+        let (clock, ticks) = mpsc::channel(1);
+        let handle = scheduler.start_with_clock(ticks);
+        // The in-memory database has one connection, the round waits for it while it's taken
+        let transaction = db.connection().begin().await.unwrap();
+        clock.send(Instant::now()).await.unwrap();
+        while shutdown.in_flight() == 0 {
+            tokio::task::yield_now().await;
+        }
+        let drain = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.drain(Duration::from_secs(10)).await }
+        });
+        while !shutdown.is_triggered() {
+            tokio::task::yield_now().await;
+        }
+        assert!(shutdown.start_work().is_none());
+        transaction.rollback().await.unwrap();
+        assert!(drain.await.unwrap());
+
+        // No round is started after shutdown
+        let _ = clock.send(Instant::now()).await;
+        handle.stop().await;
+        let epochs = db
+            .table_randomness()
+            .await
+            .find_epoch_range(NetworkId(56), EpochId(0), EpochId(10))
+            .await
+            .unwrap();
+        assert_eq!(epochs.iter().map(|e| e.epoch).collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[tokio::test]
+    async fn genesis_is_created_once() {
+        let db = memory_db().await;
+        let genesis =
+            create_genesis_epoch(&db, "chiro", NetworkId(56), [7u8; 32], &Trigger::default())
+                .await
+                .unwrap();
+        assert_eq!(genesis.epoch, 0);
+        assert_eq!(genesis.alpha, hex::encode([7u8; 32]));
+        assert!(matches!(
+            create_genesis_epoch(&db, "chiro", NetworkId(56), [8u8; 32], &Trigger::default()).await,
+            Err(EpochError::GenesisExists(NetworkId(56)))
+        ));
+        // Next epoch chains from the genesis proof
+        let next = new_epoch(&db, "chiro", NetworkId(56), None, &Trigger::default())
+            .await
+            .unwrap();
+        assert_eq!(next.epoch, 1);
+        assert_eq!(next.alpha, genesis.y);
+        // Other networks have their own genesis
+        assert!(
+            create_genesis_epoch(&db, "chiro", NetworkId(97), [8u8; 32], &Trigger::default())
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn epoch_signature_recovers_the_keyring_address() {
+        let db = memory_db().await;
+        let record = new_epoch(&db, "chiro", NetworkId(56), None, &Trigger::default())
+            .await
+            .unwrap();
+        let keyring = db
+            .table_keyring()
+            .await
+            .find_by_name("chiro".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let signature =
+            RecoverableSignature::from_bytes(&hex::decode(record.signature.unwrap()).unwrap())
+                .unwrap();
+        let y: [u8; 32] = hex::decode(&record.y).unwrap().try_into().unwrap();
+        let address = signature
+            .recover_address(record.network.0, record.epoch.0, &y)
+            .unwrap();
+        assert_eq!(Some(hex::encode(address)), keyring.address);
+    }
+
+    #[tokio::test]
+    async fn retried_epoch_returns_the_recorded_one() {
+        let db = memory_db().await;
+        let randomness = db.table_randomness().await;
+        let genesis =
+            create_genesis_epoch(&db, "chiro", NetworkId(56), [7u8; 32], &Trigger::default())
+                .await
+                .unwrap();
+        assert_eq!(
+            create_genesis_epoch(&db, "chiro", NetworkId(56), [7u8; 32], &Trigger::default())
+                .await
+                .unwrap(),
+            genesis
+        );
+
+        // A retry of the request that created the tip returns it
+        let request = |hash: &str| Trigger {
+            actor: Some(1),
+            request_hash: Some(hash.repeat(32)),
+        };
+        let next = new_epoch(&db, "chiro", NetworkId(56), None, &request("ab"))
+            .await
+            .unwrap();
+        let retried = new_epoch(&db, "chiro", NetworkId(56), None, &request("ab"))
+            .await
+            .unwrap();
+        assert_eq!(retried, next);
+        // Same request of another API key isn't a retry
+        let other_key = Trigger {
+            actor: Some(2),
+            ..request("ab")
+        };
+        let other = new_epoch(&db, "chiro", NetworkId(56), None, &other_key)
+            .await
+            .unwrap();
+        assert_eq!(other.epoch, 2);
+        // The request is a new one once other epochs were created after it
+        let again = new_epoch(&db, "chiro", NetworkId(56), None, &request("ab"))
+            .await
+            .unwrap();
+        assert_eq!(again.epoch, 3);
+        assert_eq!(
+            randomness
+                .find_epoch_range(NetworkId(56), EpochId(0), EpochId(10))
+                .await
+                .unwrap()
+                .len(),
+            4
+        );
+    }
+
+    #[tokio::test]
+    async fn every_new_epoch_is_audited() {
+        let db = memory_db().await;
+        let trigger = Trigger {
+            actor: Some(3),
+            request_hash: Some("ab".repeat(32)),
+        };
+        let genesis =
+            create_genesis_epoch(&db, "chiro", NetworkId(56), [7u8; 32], &Trigger::default())
+                .await
+                .unwrap();
+        let next = new_epoch(&db, "chiro", NetworkId(56), Some(100), &trigger)
+            .await
+            .unwrap();
+        // Retries return the recorded epoch, they aren't audited again
+        let retried = new_epoch(&db, "chiro", NetworkId(56), Some(100), &trigger)
+            .await
+            .unwrap();
+        assert_eq!(retried, next);
+        assert_eq!(next.alpha, genesis.y);
+
+        let audit_log = db
+            .table_audit_log()
+            .await
+            .find_epoch_range(NetworkId(56), EpochId(0), EpochId(10))
+            .await
+            .unwrap();
+        assert_eq!(
+            audit_log
+                .iter()
+                .map(|r| (r.epoch.0, r.actor, r.request_hash.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, None, None),
+                (next.epoch.0, trigger.actor, trigger.request_hash.clone()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn epochs_of_unknown_networks_are_rejected() {
+        let db = memory_db().await;
+        assert!(matches!(
+            new_epoch(&db, "chiro", NetworkId(1), None, &Trigger::default()).await,
+            Err(EpochError::NetworkNotFound(NetworkId(1)))
+        ));
+        assert!(db
+            .table_audit_log()
+            .await
+            .find_epoch_range(NetworkId(1), EpochId(0), EpochId(10))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn active_networks_are_grouped_by_interval() {
+        let db = memory_db().await;
+        insert_network(&db, 1, 600).await;
+        insert_network(&db, 57, 60).await;
+        assert!(db
+            .table_network()
+            .await
+            .set_active(NetworkId(97), false)
+            .await
+            .unwrap());
+
+        let groups = active_networks(&db).await.unwrap();
+        assert_eq!(
+            groups.into_iter().collect::<Vec<_>>(),
+            vec![
+                (
+                    60,
+                    vec![
+                        (NetworkId(56), "chiro".to_string()),
+                        (NetworkId(57), "chiro".to_string())
+                    ]
+                ),
+                (600, vec![(NetworkId(1), "chiro".to_string())]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn network_without_a_keyring_is_skipped() {
+        let db = Arc::new(memory_db().await);
+        // Foreign keys of SQLite aren't enforced on databases that were created without them
+        for sql in [
+            "PRAGMA foreign_keys = OFF",
+            "UPDATE network SET keyring_id = 99 WHERE chain_id = 56",
+        ] {
+            db.connection()
+                .execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
+                .await
+                .unwrap();
+        }
+        let keyring = db.table_keyring().await;
+        assert!(keyring
+            .find_keyring_for_network(NetworkId(56))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(keyring
+            .find_keyring_for_network(NetworkId(1))
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            keyring
+                .find_keyring_for_network(NetworkId(97))
+                .await
+                .unwrap()
+                .unwrap()
+                .username,
+            "chiro"
+        );
+
+        let groups = active_networks(&db).await.unwrap();
+        assert_eq!(
+            groups.get(&60).unwrap(),
+            &vec![(NetworkId(97), "chiro".to_string())]
+        );
+        for (_, networks) in groups {
+            EpochScheduler::new(db.clone(), networks).run_once().await;
+        }
+        let randomness = db.table_randomness().await;
+        assert!(randomness
+            .find_latest_epoch(NetworkId(56))
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            randomness
+                .find_latest_epoch(NetworkId(97))
+                .await
+                .unwrap()
+                .unwrap()
+                .epoch,
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn stale_tip_is_caught_up_in_order() {
+        let db = Arc::new(memory_db().await);
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        // Tip of 56 is five intervals and a bit old, tip of 97 is a day old
+        for (network, age) in [(56, 5 * 60 + 10), (97, 24 * 3600)] {
+            create_genesis_epoch(
+                &db,
+                "chiro",
+                NetworkId(network),
+                [network as u8; 32],
+                &Trigger::default(),
+            )
+            .await
+            .unwrap();
+            db.connection()
+                .execute(Statement::from_sql_and_values(
+                    DbBackend::Sqlite,
+                    "UPDATE randomness SET created_date = ? WHERE network = ?",
+                    vec![
+                        sea_orm::prelude::DateTime::from_timestamp_opt(now - age, 0)
+                            .unwrap()
+                            .into(),
+                        network.into(),
+                    ],
+                ))
+                .await
+                .unwrap();
+        }
+        let scheduler = EpochScheduler::new(
+            db.clone(),
+            vec![
+                (NetworkId(56), "chiro".to_string()),
+                (NetworkId(97), "chiro".to_string()),
+            ],
+        );
+        assert_eq!(scheduler.catch_up(60, now, 10).await, 15);
+
+        let randomness = db.table_randomness().await;
+        for (network, tip) in [(56, 5), (97, 10)] {
+            let epochs = randomness
+                .find_epoch_range(NetworkId(network), EpochId(0), EpochId(100))
+                .await
+                .unwrap();
+            assert_eq!(epochs.len(), tip + 1);
+            for pair in epochs.windows(2) {
+                assert_eq!(pair[1].epoch.0, pair[0].epoch.0 + 1);
+                assert_eq!(
+                    pair[1].alpha,
+                    hex::encode(next_alpha(&pair[0]).unwrap().b32())
+                );
+            }
+        }
+        // Caught up tips are fresh
+        assert_eq!(scheduler.catch_up(60, now, 10).await, 0);
+    }
+
+    #[tokio::test]
+    async fn epoch_after_the_largest_is_an_error() {
+        let db = memory_db().await;
+        create_genesis_epoch(&db, "chiro", NetworkId(56), [7u8; 32], &Trigger::default())
+            .await
+            .unwrap();
+        prove_caller_alpha(&db, "chiro", NetworkId(56), Scalar::from_int(42))
+            .await
+            .unwrap();
+        db.connection()
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                "UPDATE randomness SET epoch = ? WHERE network = 56",
+                vec![i64::MAX.into()],
+            ))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            new_epoch(&db, "chiro", NetworkId(56), None, &Trigger::default()).await,
+            Err(EpochError::EpochOverflow(NetworkId(56)))
+        ));
+        assert!(matches!(
+            prove_caller_alpha(&db, "chiro", NetworkId(56), Scalar::from_int(43)).await,
+            Err(EpochError::EpochOverflow(NetworkId(56)))
+        ));
+        assert_eq!(
+            db.table_randomness()
+                .await
+                .find_latest_epoch(NetworkId(56))
+                .await
+                .unwrap()
+                .unwrap()
+                .epoch,
+            i64::MAX
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_epoch_returns_the_winning_record() {
+        let db = memory_db().await;
+        let genesis =
+            create_genesis_epoch(&db, "chiro", NetworkId(56), [7u8; 32], &Trigger::default())
+                .await
+                .unwrap();
+        let winner = new_epoch(&db, "chiro", NetworkId(56), None, &Trigger::default())
+            .await
+            .unwrap();
+        // Losers proved other alphas, their insert hits the (network, epoch) index
+        let seed = Scalar::from_int(8);
+        let loser = prove_epoch(
+            &db.table_randomness().await,
+            &Signer::load(&db, "chiro", NetworkId(56)).await.unwrap(),
+            NetworkId(56),
+            EpochId(0),
+            seed,
+            None,
+            &Trigger::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(loser, genesis);
+        let loser = prove_epoch(
+            &db.table_randomness().await,
+            &Signer::load(&db, "chiro", NetworkId(56)).await.unwrap(),
+            NetworkId(56),
+            EpochId(1),
+            Scalar::from_int(9),
+            None,
+            &Trigger::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(loser, winner);
+
+        // Neither loser was stored or audited
+        assert_eq!(
+            db.table_randomness()
+                .await
+                .find_epoch_range(NetworkId(56), EpochId(0), EpochId(10))
+                .await
+                .unwrap(),
+            vec![genesis, winner]
+        );
+        assert_eq!(
+            db.table_audit_log()
+                .await
+                .find_epoch_range(NetworkId(56), EpochId(0), EpochId(10))
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_epoch_in_a_memory_store_returns_the_winning_record() {
+        let store = MemoryStore::new();
+        let signer = Signer {
+            keyring_id: 1,
+            secret_key: generate_keypair().secret_key,
+        };
+        let trigger = Trigger::default();
+        let winner = chain_epoch(&store, &signer, NetworkId(56), None, &trigger)
+            .await
+            .unwrap();
+        let loser = prove_epoch(
+            &store,
+            &signer,
+            NetworkId(56),
+            EpochId(0),
+            Scalar::from_int(8),
+            None,
+            &trigger,
+        )
+        .await
+        .unwrap();
+        assert_eq!(loser, winner);
+        assert_eq!(
+            store
+                .range(NetworkId(56), EpochId(0), None, None, 10)
+                .await
+                .unwrap(),
+            (vec![winner], None)
+        );
+    }
+
+    // Check a record the way the on-chain verifier does, with the witness address of U
+    fn verify_contract_record(
+        record: &ModelRandomness,
+        public_key: PublicKey,
+        alpha: &Scalar,
+    ) -> bool {
+        let scalar = |s: &str| {
+            let b: [u8; 32] = hex::decode(s).unwrap().try_into().unwrap();
+            scalar_from_b32(&b).unwrap()
+        };
+        let gamma_bytes = hex::decode(&record.gamma).unwrap();
+        let mut x = Field::default();
+        let mut y = Field::default();
+        assert!(x.set_b32(&gamma_bytes[..32].try_into().unwrap()));
+        assert!(y.set_b32(&gamma_bytes[32..].try_into().unwrap()));
+        let gamma = affine_composer(&x, &y);
+        let (c, s) = (scalar(&record.c), scalar(&record.s));
+        let mut pk: Affine = public_key.into();
+        pk.x.normalize();
+        pk.y.normalize();
+
+        let vrf = ECVRF::new_verifier(public_key);
+        let h = vrf.hash_to_curve_prefix(alpha, &pk);
+        // U = c * pk + s * G, V = c * gamma + s * H
+        let mut u = Jacobian::default();
+        ECMULT_CONTEXT.ecmult(&mut u, &Jacobian::from_ge(&pk), &c, &s);
+        let address = calculate_witness_address(&jacobian_to_affine(&u));
+        let v = Jacobian::from_ge(&ecmult(&ECMULT_CONTEXT, &gamma, &c)).add_ge(&ecmult(
+            &ECMULT_CONTEXT,
+            &h,
+            &s,
+        ));
+        vrf.hash_points_prefix(&h, &pk, &gamma, &address, &jacobian_to_affine(&v))
+            .unwrap()
+            == c
+            && keccak256_affine_scalar(&gamma).unwrap() == scalar(&record.y)
+    }
+
+    #[tokio::test]
+    async fn caller_alpha_is_proven_outside_of_the_chain() {
+        let db = memory_db().await;
+        let randomness = db.table_randomness().await;
+        let genesis =
+            create_genesis_epoch(&db, "chiro", NetworkId(56), [7u8; 32], &Trigger::default())
+                .await
+                .unwrap();
+        let alpha = Scalar::from_int(42);
+        let record = prove_caller_alpha(&db, "chiro", NetworkId(56), alpha)
+            .await
+            .unwrap();
+        assert!(record.caller_alpha);
+        assert_eq!((record.epoch, record.signature.clone()), (EpochId(0), None));
+        assert_eq!(
+            prove_caller_alpha(&db, "chiro", NetworkId(56), alpha)
+                .await
+                .unwrap(),
+            record
+        );
+
+        let keyring = db
+            .table_keyring()
+            .await
+            .find_by_name("chiro".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        let public_key = keyring.parse_public_key().unwrap();
+        assert!(verify_contract_record(&record, public_key, &alpha));
+
+        // Chain queries and new epochs ignore caller alphas
+        assert_eq!(
+            randomness.find_latest_epoch(NetworkId(56)).await.unwrap(),
+            Some(genesis.clone())
+        );
+        let next = new_epoch(&db, "chiro", NetworkId(56), None, &Trigger::default())
+            .await
+            .unwrap();
+        assert_eq!(next.epoch, 1);
+        assert_eq!(
+            prove_caller_alpha(&db, "chiro", NetworkId(56), Scalar::from_int(43))
+                .await
+                .unwrap()
+                .epoch,
+            1
+        );
+
+        // y of the tip is the alpha of the next epoch
+        let y: [u8; 32] = hex::decode(&next.y).unwrap().try_into().unwrap();
+        let taken = scalar_from_bytes_reduced(&y);
+        assert!(matches!(
+            prove_caller_alpha(&db, "chiro", NetworkId(56), taken).await,
+            Err(EpochError::AlphaTaken(_))
+        ));
+        assert!(matches!(
+            prove_caller_alpha(&db, "chiro", NetworkId(1), alpha).await,
+            Err(EpochError::NetworkNotFound(NetworkId(1)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn simulated_proof_is_not_recorded() {
+        let db = memory_db().await;
+        let genesis =
+            create_genesis_epoch(&db, "chiro", NetworkId(56), [7u8; 32], &Trigger::default())
+                .await
+                .unwrap();
+        let alpha = Scalar::from_int(42);
+        let proof = simulate_prove(&db, "chiro", NetworkId(56), alpha)
+            .await
+            .unwrap();
+        assert!(ECVRFProof::verify_standalone(&alpha, &proof).unwrap());
+        let randomness = db.table_randomness().await;
+        assert_eq!(
+            randomness
+                .find_caller_alpha(NetworkId(56), &hex::encode(alpha.b32()))
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            randomness
+                .find_latest_caller_alpha(NetworkId(56))
+                .await
+                .unwrap(),
+            None
+        );
+
+        let y: [u8; 32] = hex::decode(&genesis.y).unwrap().try_into().unwrap();
+        let next_alpha = scalar_from_bytes_reduced(&y);
+        assert!(matches!(
+            simulate_prove(&db, "chiro", NetworkId(56), next_alpha).await,
+            Err(EpochError::AlphaTaken(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn corrupted_record_fails_its_integrity_check() {
+        let db = memory_db().await;
+        create_genesis_epoch(&db, "chiro", NetworkId(56), [7u8; 32], &Trigger::default())
+            .await
+            .unwrap();
+        let record = new_epoch(&db, "chiro", NetworkId(56), None, &Trigger::default())
+            .await
+            .unwrap();
+        let public_key = db
+            .table_keyring()
+            .await
+            .find_by_name("chiro".to_string())
+            .await
+            .unwrap()
+            .unwrap()
+            .parse_public_key()
+            .unwrap();
+        assert_eq!(record.verify_integrity(&public_key), Ok(()));
+        assert_eq!(
+            record.verify_integrity(&generate_keypair().public_key),
+            Err(IntegrityError::InvalidProof)
+        );
+
+        let mut s = hex::decode(&record.s).unwrap();
+        s[31] ^= 1;
+        db.connection()
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                "UPDATE randomness SET s = ? WHERE id = ?",
+                vec![hex::encode(s).into(), record.id.into()],
+            ))
+            .await
+            .unwrap();
+        let corrupted = db
+            .table_randomness()
+            .await
+            .find_epoch(NetworkId(56), record.epoch)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            corrupted.verify_integrity(&public_key),
+            Err(IntegrityError::InvalidProof)
+        );
+
+        // Columns are intact but the blob that is served isn't their proof
+        let mut tampered = record.clone();
+        tampered.proof.as_mut().unwrap()[40] ^= 1;
+        assert_eq!(
+            tampered.verify_integrity(&public_key),
+            Err(IntegrityError::ProofMismatch)
+        );
+    }
+
+    async fn corrupt(db: &SqliteDB, column: &str, value: String, id: i32) {
+        db.connection()
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                &format!("UPDATE randomness SET {} = ? WHERE id = ?", column),
+                vec![value.into(), id.into()],
+            ))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn corrupted_s_is_healed() {
+        let db = memory_db().await;
+        create_genesis_epoch(&db, "chiro", NetworkId(56), [7u8; 32], &Trigger::default())
+            .await
+            .unwrap();
+        let record = new_epoch(&db, "chiro", NetworkId(56), None, &Trigger::default())
+            .await
+            .unwrap();
+        let trigger = Trigger {
+            actor: None,
+            request_hash: Some("ab".repeat(32)),
+        };
+        assert_eq!(
+            heal_epoch(&db, NetworkId(56), record.epoch, &trigger)
+                .await
+                .unwrap(),
+            (record.clone(), false)
+        );
+
+        let mut s = hex::decode(&record.s).unwrap();
+        s[31] ^= 1;
+        corrupt(&db, "s", hex::encode(s), record.id).await;
+        let (healed, changed) = heal_epoch(&db, NetworkId(56), record.epoch, &trigger)
+            .await
+            .unwrap();
+        assert!(changed);
+        // Output of the epoch is the one before it was corrupted
+        assert_eq!(
+            (&healed.gamma, &healed.y, &healed.signature),
+            (&record.gamma, &record.y, &record.signature)
+        );
+        assert_eq!(
+            (healed.id, healed.created_date),
+            (record.id, record.created_date)
+        );
+        let public_key = db
+            .table_keyring()
+            .await
+            .find_by_id(record.keyring_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .parse_public_key()
+            .unwrap();
+        assert_eq!(healed.verify_integrity(&public_key), Ok(()));
+        assert_eq!(
+            db.table_randomness()
+                .await
+                .find_epoch(NetworkId(56), record.epoch)
+                .await
+                .unwrap(),
+            Some(healed.clone())
+        );
+        let audit_log = db
+            .table_audit_log()
+            .await
+            .find_epoch_range(NetworkId(56), record.epoch, record.epoch)
+            .await
+            .unwrap();
+        assert_eq!(
+            audit_log
+                .iter()
+                .map(|r| r.request_hash.clone())
+                .collect::<Vec<_>>(),
+            vec![None, trigger.request_hash.clone()]
+        );
+
+        // Alpha that isn't the y of the previous epoch would be proven as if it were right
+        corrupt(&db, "s", "01".repeat(32), record.id).await;
+        corrupt(&db, "alpha", "02".repeat(32), record.id).await;
+        assert!(matches!(
+            heal_epoch(&db, NetworkId(56), record.epoch, &trigger).await,
+            Err(EpochError::InvalidRecord(_))
+        ));
+        assert!(matches!(
+            heal_epoch(&db, NetworkId(56), EpochId(9), &trigger).await,
+            Err(EpochError::EpochNotFound(NetworkId(56), EpochId(9)))
+        ));
+    }
+}
@@ -0,0 +1,121 @@
+//! Networks and epochs are both numbered by i64, they have their own types so one can't be
+//! passed for the other
+//!
+//! ```compile_fail
+//! use orochimaru::ids::{EpochId, NetworkId};
+//!
+//! fn epoch_of(network: NetworkId, epoch: EpochId) -> (NetworkId, EpochId) {
+//!     (network, epoch)
+//! }
+//!
+//! let (network, epoch) = (NetworkId(56), EpochId(7));
+//! epoch_of(epoch, network);
+//! ```
+use sea_orm::{
+    sea_query::{ArrayType, ColumnType, Nullable, Value, ValueType, ValueTypeErr},
+    QueryResult, TryGetError, TryGetable,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, num::ParseIntError, str::FromStr};
+
+// Newtype of an i64 id that is stored as a BIGINT column
+macro_rules! id_type {
+    ($name:ident) => {
+        #[derive(
+            Clone,
+            Copy,
+            Debug,
+            Default,
+            PartialEq,
+            Eq,
+            PartialOrd,
+            Ord,
+            Hash,
+            Serialize,
+            Deserialize,
+        )]
+        #[serde(transparent)]
+        pub struct $name(pub i64);
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl From<i64> for $name {
+            fn from(id: i64) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<$name> for i64 {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        // Literals of tests and bounds, e.g. epoch == 0
+        impl PartialEq<i64> for $name {
+            fn eq(&self, other: &i64) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseIntError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                s.parse().map(Self)
+            }
+        }
+
+        impl From<$name> for Value {
+            fn from(id: $name) -> Self {
+                Value::BigInt(Some(id.0))
+            }
+        }
+
+        impl Nullable for $name {
+            fn null() -> Value {
+                Value::BigInt(None)
+            }
+        }
+
+        impl ValueType for $name {
+            fn try_from(v: Value) -> Result<Self, ValueTypeErr> {
+                <i64 as ValueType>::try_from(v).map(Self)
+            }
+
+            fn type_name() -> String {
+                stringify!($name).to_string()
+            }
+
+            fn array_type() -> ArrayType {
+                ArrayType::BigInt
+            }
+
+            fn column_type() -> ColumnType {
+                ColumnType::BigInteger(None)
+            }
+        }
+
+        impl TryGetable for $name {
+            fn try_get(res: &QueryResult, pre: &str, col: &str) -> Result<Self, TryGetError> {
+                i64::try_get(res, pre, col).map(Self)
+            }
+        }
+    };
+}
+
+// Chain id of a network
+id_type!(NetworkId);
+// Position of an epoch in the hash chain of its network, or among its caller alphas
+id_type!(EpochId);
+
+impl EpochId {
+    // Epoch after this one, None at the end of i64
+    pub fn checked_next(self) -> Option<EpochId> {
+        self.0.checked_add(1).map(Self)
+    }
+}
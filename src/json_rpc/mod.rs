@@ -1,32 +1,1361 @@
-use core::panic;
-use serde::{Deserialize, Serialize};
+use crate::ids::{EpochId, NetworkId};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use core::fmt;
+use ecvrf::{ecproof::ECVRFProof, secp256k1::curve::Scalar};
+use sea_orm::prelude::{DateTime, DateTimeWithTimeZone};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+
+pub const JSONRPC_VERSION: &str = "2.0";
+
+// Error codes defined by JSON-RPC 2.0 specification
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+// Implementation defined server errors
+pub const UNAUTHORIZED: i64 = -32001;
+pub const NOT_FOUND: i64 = -32004;
+pub const CONFLICT: i64 = -32009;
+pub const REPLAY: i64 = -32025;
+pub const RATE_LIMITED: i64 = -32029;
+pub const SHUTTING_DOWN: i64 = -32053;
+
+// Maximum number of epochs returned by orand_getEpochRange and orand_getAuditLog
+pub const MAX_EPOCH_RANGE: i64 = 100;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct JSONRPCPayload {
+    // Missing version is kept as an empty string, it will be rejected later
+    #[serde(default)]
+    jsonrpc: String,
+    #[serde(default)]
+    id: Value,
     method: String,
-    params: Vec<String>,
+    // Either positional array or named object
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JSONRPCResponseError {
+    pub code: i64,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JSONRPCResponse {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<JSONRPCResponseError>,
+}
+
+impl JSONRPCResponse {
+    // Successful response, id is echoed from the request
+    pub fn success(id: Value, result: Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    // Error response, id is null if we weren't able to read it from the request
+    pub fn error(id: Value, code: i64, message: &str) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            result: None,
+            error: Some(JSONRPCResponseError {
+                code,
+                message: message.to_string(),
+            }),
+        }
+    }
+}
+
+// Params of a method, NAMES are the field names in positional order
+// OPTIONAL params may be omitted, they must come after the required ones
+pub trait JSONRPCParams: DeserializeOwned {
+    const NAMES: &'static [&'static str];
+    const OPTIONAL: &'static [&'static str] = &[];
+}
+
+// Integer params were sent as strings by older clients, accept both
+fn integer_or_string<'de, D: Deserializer<'de>, T: From<i64>>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum IntegerOrString {
+        Integer(i64),
+        String(String),
+    }
+    match IntegerOrString::deserialize(deserializer)? {
+        IntegerOrString::Integer(v) => Ok(v.into()),
+        IntegerOrString::String(s) => s
+            .parse::<i64>()
+            .map(T::from)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+fn optional_integer_or_string<'de, D: Deserializer<'de>, T: From<i64>>(
+    deserializer: D,
+) -> Result<Option<T>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(bound = "T: From<i64>")]
+    struct Wrapper<T>(#[serde(deserialize_with = "integer_or_string")] T);
+    Ok(Option::<Wrapper<T>>::deserialize(deserializer)?.map(|Wrapper(v)| v))
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct OrandGetPublicEpochParams {
+    #[serde(deserialize_with = "integer_or_string")]
+    pub network: NetworkId,
+    #[serde(deserialize_with = "integer_or_string")]
+    pub epoch: EpochId,
+}
+
+impl JSONRPCParams for OrandGetPublicEpochParams {
+    const NAMES: &'static [&'static str] = &["network", "epoch"];
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct OrandNewEpochParams {
+    #[serde(deserialize_with = "integer_or_string")]
+    pub network: NetworkId,
+    // Chain height the new epoch is anchored to
+    #[serde(default, deserialize_with = "optional_integer_or_string")]
+    pub block_number: Option<i64>,
+}
+
+impl JSONRPCParams for OrandNewEpochParams {
+    const NAMES: &'static [&'static str] = &["network", "block_number"];
+    const OPTIONAL: &'static [&'static str] = &["block_number"];
+}
+
+// Scalar encoded as 32 bytes hex string, 0x prefix is optional
+fn scalar_from_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Scalar, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    let bytes: [u8; 32] = hex::decode(s.strip_prefix("0x").unwrap_or(&s))
+        .map_err(serde::de::Error::custom)?
+        .try_into()
+        .map_err(|_| serde::de::Error::custom("scalar must be 32 bytes"))?;
+    let mut scalar = Scalar::default();
+    if bool::from(scalar.set_b32(&bytes)) {
+        return Err(serde::de::Error::custom("scalar overflows the group order"));
+    }
+    Ok(scalar)
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct OrandVerifyProofParams {
+    #[serde(deserialize_with = "integer_or_string")]
+    pub network: NetworkId,
+    #[serde(deserialize_with = "scalar_from_hex")]
+    pub alpha: Scalar,
+    pub proof: ECVRFProof,
+}
+
+impl JSONRPCParams for OrandVerifyProofParams {
+    const NAMES: &'static [&'static str] = &["network", "alpha", "proof"];
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct OrandGetEpochRangeParams {
+    #[serde(deserialize_with = "integer_or_string")]
+    pub network: NetworkId,
+    #[serde(deserialize_with = "integer_or_string")]
+    pub from_epoch: EpochId,
+    // Epochs after from_epoch are paged through if it's null
+    #[serde(default, deserialize_with = "optional_integer_or_string")]
+    pub to_epoch: Option<EpochId>,
+    // next_cursor of the previous page
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default, deserialize_with = "optional_integer_or_string")]
+    pub limit: Option<i64>,
+}
+
+impl JSONRPCParams for OrandGetEpochRangeParams {
+    const NAMES: &'static [&'static str] =
+        &["network", "from_epoch", "to_epoch", "cursor", "limit"];
+    const OPTIONAL: &'static [&'static str] = &["to_epoch", "cursor", "limit"];
+}
+
+// RFC 3339 time with any offset or unix seconds, normalized to UTC like created_date
+fn utc_date_time<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum SecondsOrString {
+        Seconds(i64),
+        String(String),
+    }
+    match SecondsOrString::deserialize(deserializer)? {
+        SecondsOrString::Seconds(secs) => DateTime::from_timestamp_opt(secs, 0)
+            .ok_or_else(|| serde::de::Error::custom("time is out of range")),
+        SecondsOrString::String(s) => DateTimeWithTimeZone::parse_from_rfc3339(&s)
+            .map(|time| time.naive_utc())
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct OrandGetEpochsByTimeParams {
+    #[serde(deserialize_with = "integer_or_string")]
+    pub network: NetworkId,
+    #[serde(deserialize_with = "utc_date_time")]
+    pub from: DateTime,
+    #[serde(deserialize_with = "utc_date_time")]
+    pub to: DateTime,
+}
+
+impl JSONRPCParams for OrandGetEpochsByTimeParams {
+    const NAMES: &'static [&'static str] = &["network", "from", "to"];
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct OrandGetAuditLogParams {
+    #[serde(deserialize_with = "integer_or_string")]
+    pub network: NetworkId,
+    #[serde(deserialize_with = "integer_or_string")]
+    pub from_epoch: EpochId,
+    #[serde(deserialize_with = "integer_or_string")]
+    pub to_epoch: EpochId,
+}
+
+impl JSONRPCParams for OrandGetAuditLogParams {
+    const NAMES: &'static [&'static str] = &["network", "from_epoch", "to_epoch"];
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct OrandGetLatestEpochParams {
+    #[serde(deserialize_with = "integer_or_string")]
+    pub network: NetworkId,
+}
+
+impl JSONRPCParams for OrandGetLatestEpochParams {
+    const NAMES: &'static [&'static str] = &["network"];
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct OrandPruneParams {
+    #[serde(deserialize_with = "integer_or_string")]
+    pub network: NetworkId,
+    #[serde(deserialize_with = "integer_or_string")]
+    pub keep_last_n: i64,
+}
+
+impl JSONRPCParams for OrandPruneParams {
+    const NAMES: &'static [&'static str] = &["network", "keep_last_n"];
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct OrandHealEpochParams {
+    #[serde(deserialize_with = "integer_or_string")]
+    pub network: NetworkId,
+    #[serde(deserialize_with = "integer_or_string")]
+    pub epoch: EpochId,
+}
+
+impl JSONRPCParams for OrandHealEpochParams {
+    const NAMES: &'static [&'static str] = &["network", "epoch"];
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct OrandGetPublicKeyParams {
+    #[serde(deserialize_with = "integer_or_string")]
+    pub network: NetworkId,
+}
+
+impl JSONRPCParams for OrandGetPublicKeyParams {
+    const NAMES: &'static [&'static str] = &["network"];
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct OrandListNetworksParams {}
+
+impl JSONRPCParams for OrandListNetworksParams {
+    const NAMES: &'static [&'static str] = &[];
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct OrandCreateGenesisParams {
+    #[serde(deserialize_with = "integer_or_string")]
+    pub network: NetworkId,
+    // Alpha of epoch 0
+    #[serde(deserialize_with = "scalar_from_hex")]
+    pub seed: Scalar,
+}
+
+impl JSONRPCParams for OrandCreateGenesisParams {
+    const NAMES: &'static [&'static str] = &["network", "seed"];
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct OrandProveAlphaParams {
+    #[serde(deserialize_with = "integer_or_string")]
+    pub network: NetworkId,
+    // 32 bytes hex string, 0x prefix is optional
+    pub alpha: String,
+}
+
+impl JSONRPCParams for OrandProveAlphaParams {
+    const NAMES: &'static [&'static str] = &["network", "alpha"];
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct OrandFindByValueParams {
+    #[serde(deserialize_with = "integer_or_string")]
+    pub network: NetworkId,
+    // Randomness of an epoch, 32 bytes hex string, 0x prefix is optional
+    pub y: String,
+}
+
+impl JSONRPCParams for OrandFindByValueParams {
+    const NAMES: &'static [&'static str] = &["network", "y"];
+}
+
+// orand_simulateProve takes the same params as orand_proveAlpha
+pub type OrandSimulateProveParams = OrandProveAlphaParams;
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct OrandSubscribeParams {
+    pub topic: String,
+    #[serde(deserialize_with = "integer_or_string")]
+    pub network: NetworkId,
+}
+
+impl JSONRPCParams for OrandSubscribeParams {
+    const NAMES: &'static [&'static str] = &["topic", "network"];
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct OrandUnsubscribeParams {
+    #[serde(deserialize_with = "integer_or_string")]
+    pub subscription: i64,
+}
+
+impl JSONRPCParams for OrandUnsubscribeParams {
+    const NAMES: &'static [&'static str] = &["subscription"];
 }
 
 pub enum JSONRPCMethod {
     // Network, epoch
-    OrandGetPublicEpoch(u32, u32),
-    // New epoch of given network
-    OrandNewEpoch(u32),
-    // Get public key
-    OrandGetPublicKey(String),
+    OrandGetPublicEpoch(NetworkId, EpochId),
+    // New epoch of given network, anchored to an optional block number
+    OrandNewEpoch(NetworkId, Option<i64>),
+    // Key of the keyring that signs the epochs of the network
+    OrandGetPublicKey(NetworkId),
+    // Network, alpha and the proof to verify against the keyring of the network
+    OrandVerifyProof(NetworkId, Scalar, Box<ECVRFProof>),
+    // Network, from epoch, to epoch, id of the last record of the previous page, page size
+    OrandGetEpochRange(NetworkId, EpochId, Option<EpochId>, Option<i32>, u64),
+    // Network, epochs created in [from, to), both are UTC
+    OrandGetEpochsByTime(NetworkId, DateTime, DateTime),
+    // Latest epoch of given network
+    OrandGetLatestEpoch(NetworkId),
+    // Network, number of latest epochs to keep, it's an admin method
+    OrandPrune(NetworkId, u64),
+    // Network, epoch that is proven again if it fails its integrity check, it's an admin method
+    OrandHealEpoch(NetworkId, EpochId),
+    // Network, seed of epoch 0, it's an admin method
+    OrandCreateGenesis(NetworkId, [u8; 32]),
+    // Network, from epoch, to epoch (inclusive), it's an admin method
+    OrandGetAuditLog(NetworkId, EpochId, EpochId),
+    // Every network that has an epoch
+    OrandListNetworks,
+    // Network, alpha supplied by the caller as 32 bytes hex without 0x
+    OrandProveAlpha(NetworkId, String),
+    // Network, alpha supplied by the caller, its proof isn't recorded
+    OrandSimulateProve(NetworkId, String),
+    // Network, y as 32 bytes hex without 0x, records that produced it
+    OrandFindByValue(NetworkId, String),
+}
+
+// Requests sent over WebSocket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JSONRPCSubscription {
+    // New epochs of given network
+    Subscribe(NetworkId),
+    // Subscription id
+    Unsubscribe(i64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JSONRPCError {
+    // Request is not a valid JSON-RPC payload
+    ParseError(String),
+    // Request is valid JSON but it isn't a JSON-RPC 2.0 request
+    InvalidRequest,
+    // Param with the given name is missing
+    MissingParam(&'static str),
+    // Params can not be deserialized
+    InvalidParam(String),
+    // Method is not supported
+    UnknownMethod(String),
+    // Requested record does not exist
+    NotFound(String),
+    // Record already exists
+    Conflict(String),
+    // Caller alpha that was already submitted within the replay window
+    Replay(String),
+    // Method needs credentials that weren't provided or are wrong
+    Unauthorized,
+    // Client sent too many requests
+    RateLimited,
+    // Node is shutting down and doesn't start new writes
+    ShuttingDown,
+    // Request is valid but the server failed to handle it
+    Internal(String),
+}
+
+impl JSONRPCError {
+    // JSON-RPC 2.0 error code
+    pub fn code(&self) -> i64 {
+        match self {
+            JSONRPCError::ParseError(_) => PARSE_ERROR,
+            JSONRPCError::InvalidRequest => INVALID_REQUEST,
+            JSONRPCError::MissingParam(_) | JSONRPCError::InvalidParam(_) => INVALID_PARAMS,
+            JSONRPCError::UnknownMethod(_) => METHOD_NOT_FOUND,
+            JSONRPCError::NotFound(_) => NOT_FOUND,
+            JSONRPCError::Conflict(_) => CONFLICT,
+            JSONRPCError::Replay(_) => REPLAY,
+            JSONRPCError::Unauthorized => UNAUTHORIZED,
+            JSONRPCError::RateLimited => RATE_LIMITED,
+            JSONRPCError::ShuttingDown => SHUTTING_DOWN,
+            JSONRPCError::Internal(_) => INTERNAL_ERROR,
+        }
+    }
+
+    // Error response that echoes the given request id
+    pub fn to_response(&self, id: Value) -> JSONRPCResponse {
+        JSONRPCResponse::error(id, self.code(), &self.to_string())
+    }
+}
+
+impl fmt::Display for JSONRPCError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JSONRPCError::ParseError(e) => write!(f, "Parse error: {}", e),
+            JSONRPCError::InvalidRequest => {
+                write!(
+                    f,
+                    "Invalid request, jsonrpc must be \"{}\"",
+                    JSONRPC_VERSION
+                )
+            }
+            JSONRPCError::MissingParam(name) => write!(f, "Missing param: {}", name),
+            JSONRPCError::InvalidParam(e) => write!(f, "Invalid param: {}", e),
+            JSONRPCError::UnknownMethod(m) => write!(f, "Unknown method: {}", m),
+            JSONRPCError::NotFound(what) => write!(f, "Not found: {}", what),
+            JSONRPCError::Conflict(what) => write!(f, "Conflict: {}", what),
+            JSONRPCError::Replay(alpha) => write!(f, "Replay of alpha {}", alpha),
+            JSONRPCError::Unauthorized => write!(f, "Unauthorized"),
+            JSONRPCError::RateLimited => write!(f, "Too many requests"),
+            JSONRPCError::ShuttingDown => write!(f, "Shutting down"),
+            JSONRPCError::Internal(e) => write!(f, "Internal error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for JSONRPCError {}
+
+// Read the id of a request, it's null if the request is malformed
+// Handlers use it to echo the id back even if the request can't be parsed
+pub fn request_id(json_string: &str) -> Value {
+    serde_json::from_str::<Value>(json_string)
+        .ok()
+        .and_then(|v| v.get("id").cloned())
+        .unwrap_or(Value::Null)
+}
+
+// 32 bytes hex string of the named param, 0x prefix is optional
+fn parse_b32(name: &str, value: &str) -> Result<[u8; 32], JSONRPCError> {
+    hex::decode(value.strip_prefix("0x").unwrap_or(value))
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| JSONRPCError::InvalidParam(format!("{} must be 32 bytes hex", name)))
+}
+
+// Alpha supplied by a caller as 32 bytes hex without 0x, it must be a scalar
+fn parse_alpha(alpha: &str) -> Result<String, JSONRPCError> {
+    let bytes = parse_b32("alpha", alpha)?;
+    if bool::from(Scalar::default().set_b32(&bytes)) {
+        return Err(JSONRPCError::InvalidParam(
+            "alpha overflows the group order".to_string(),
+        ));
+    }
+    Ok(hex::encode(bytes))
+}
+
+// Deserialize params of a method from either the positional or the named form
+fn parse_params<T: JSONRPCParams>(params: Value) -> Result<T, JSONRPCError> {
+    let named = match params {
+        Value::Object(map) => map,
+        Value::Array(values) => {
+            // zip() would drop the extra values silently
+            if values.len() > T::NAMES.len() {
+                return Err(JSONRPCError::InvalidParam(format!(
+                    "expected at most {} params, got {}",
+                    T::NAMES.len(),
+                    values.len()
+                )));
+            }
+            T::NAMES
+                .iter()
+                .map(|name| name.to_string())
+                .zip(values)
+                .collect::<Map<String, Value>>()
+        }
+        Value::Null => Map::new(),
+        _ => {
+            return Err(JSONRPCError::InvalidParam(
+                "params must be an array or an object".to_string(),
+            ))
+        }
+    };
+    if let Some(name) = T::NAMES
+        .iter()
+        .find(|name| !T::OPTIONAL.contains(name) && !named.contains_key(**name))
+    {
+        return Err(JSONRPCError::MissingParam(name));
+    }
+    serde_json::from_value(Value::Object(named))
+        .map_err(|e| JSONRPCError::InvalidParam(e.to_string()))
+}
+
+// Range must be ordered and at most MAX_EPOCH_RANGE epochs long
+fn check_epoch_range(from_epoch: EpochId, to_epoch: EpochId) -> Result<(), JSONRPCError> {
+    if to_epoch < from_epoch {
+        return Err(JSONRPCError::InvalidParam(
+            "to_epoch must not be less than from_epoch".to_string(),
+        ));
+    }
+    if to_epoch.0 - from_epoch.0 >= MAX_EPOCH_RANGE {
+        return Err(JSONRPCError::InvalidParam(format!(
+            "range must not exceed {} epochs",
+            MAX_EPOCH_RANGE
+        )));
+    }
+    Ok(())
+}
+
+// Cursors are opaque to clients, they're the id of the last record of a page
+pub fn encode_cursor(id: i32) -> String {
+    URL_SAFE_NO_PAD.encode(id.to_string())
+}
+
+fn decode_cursor(cursor: &str) -> Result<i32, JSONRPCError> {
+    URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .and_then(|id| id.parse().ok())
+        .ok_or_else(|| JSONRPCError::InvalidParam("invalid cursor".to_string()))
+}
+
+impl JSONRPCPayload {
+    // Batches aren't supported, an array is valid JSON but not a request
+    fn from_json_string(json_string: &str) -> Result<Self, JSONRPCError> {
+        let value: Value = serde_json::from_str(json_string)
+            .map_err(|e| JSONRPCError::ParseError(e.to_string()))?;
+        if value.is_array() {
+            return Err(JSONRPCError::InvalidRequest);
+        }
+        let json_rpc: Self =
+            serde_json::from_value(value).map_err(|e| JSONRPCError::ParseError(e.to_string()))?;
+        if json_rpc.jsonrpc != JSONRPC_VERSION {
+            return Err(JSONRPCError::InvalidRequest);
+        }
+        Ok(json_rpc)
+    }
 }
 
 impl JSONRPCMethod {
-    pub fn from_json_string(json_string: &str) -> Self {
-        let json_rpc: JSONRPCPayload = serde_json::from_str(json_string).unwrap();
+    // Methods that are only allowed for the operator
+    pub fn requires_admin(&self) -> bool {
+        matches!(
+            self,
+            Self::OrandPrune(_, _)
+                | Self::OrandHealEpoch(_, _)
+                | Self::OrandCreateGenesis(_, _)
+                | Self::OrandGetAuditLog(_, _, _)
+        )
+    }
+
+    // Methods that write to the database or prove with the secret key need an API key
+    pub fn requires_api_key(&self) -> bool {
+        matches!(
+            self,
+            Self::OrandNewEpoch(_, _)
+                | Self::OrandProveAlpha(_, _)
+                | Self::OrandSimulateProve(_, _)
+        )
+    }
+
+    // Methods that write epochs or the records of proofs, they're drained on shutdown
+    pub fn writes(&self) -> bool {
+        matches!(
+            self,
+            Self::OrandNewEpoch(_, _)
+                | Self::OrandCreateGenesis(_, _)
+                | Self::OrandHealEpoch(_, _)
+                | Self::OrandProveAlpha(_, _)
+                | Self::OrandPrune(_, _)
+        )
+    }
+
+    // Network and epoch the method is about, the first epoch of a range
+    pub fn target(&self) -> (Option<NetworkId>, Option<EpochId>) {
+        match self {
+            Self::OrandGetPublicEpoch(network, epoch)
+            | Self::OrandHealEpoch(network, epoch)
+            | Self::OrandGetEpochRange(network, epoch, _, _, _)
+            | Self::OrandGetAuditLog(network, epoch, _) => (Some(*network), Some(*epoch)),
+            Self::OrandNewEpoch(network, _)
+            | Self::OrandVerifyProof(network, _, _)
+            | Self::OrandGetLatestEpoch(network)
+            | Self::OrandPrune(network, _)
+            | Self::OrandCreateGenesis(network, _)
+            | Self::OrandProveAlpha(network, _)
+            | Self::OrandSimulateProve(network, _)
+            | Self::OrandGetEpochsByTime(network, _, _)
+            | Self::OrandFindByValue(network, _)
+            | Self::OrandGetPublicKey(network) => (Some(*network), None),
+            Self::OrandListNetworks => (None, None),
+        }
+    }
+
+    // Reject ids that can't exist, networks are chain ids and epochs start from 0
+    pub fn validate(&self) -> Result<(), JSONRPCError> {
+        let (network, epoch) = match self.target() {
+            (Some(network), epoch) => (network, epoch),
+            (None, _) => return Ok(()),
+        };
+        if network.0 <= 0 {
+            return Err(JSONRPCError::InvalidParam(
+                "network must be greater than 0".to_string(),
+            ));
+        }
+        if epoch.is_some_and(|epoch| epoch.0 < 0) {
+            return Err(JSONRPCError::InvalidParam(
+                "epoch must not be negative".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    // Name of the method as it's sent by clients
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::OrandGetPublicEpoch(_, _) => "orand_getPublicEpoch",
+            Self::OrandNewEpoch(_, _) => "orand_newEpoch",
+            Self::OrandGetPublicKey(_) => "orand_getPublicKey",
+            Self::OrandVerifyProof(_, _, _) => "orand_verifyProof",
+            Self::OrandGetEpochRange(_, _, _, _, _) => "orand_getEpochRange",
+            Self::OrandGetEpochsByTime(_, _, _) => "orand_getEpochsByTime",
+            Self::OrandGetLatestEpoch(_) => "orand_getLatestEpoch",
+            Self::OrandPrune(_, _) => "orand_prune",
+            Self::OrandHealEpoch(_, _) => "orand_healEpoch",
+            Self::OrandCreateGenesis(_, _) => "orand_createGenesis",
+            Self::OrandGetAuditLog(_, _, _) => "orand_getAuditLog",
+            Self::OrandListNetworks => "orand_listNetworks",
+            Self::OrandProveAlpha(_, _) => "orand_proveAlpha",
+            Self::OrandSimulateProve(_, _) => "orand_simulateProve",
+            Self::OrandFindByValue(_, _) => "orand_findByValue",
+        }
+    }
+
+    // Parse a JSON-RPC 2.0 request, use request_id() to get its id
+    pub fn from_json_string(json_string: &str) -> Result<Self, JSONRPCError> {
+        let json_rpc = JSONRPCPayload::from_json_string(json_string)?;
+        let params = json_rpc.params;
+        match json_rpc.method.as_str() {
+            "orand_getPublicEpoch" => {
+                let p: OrandGetPublicEpochParams = parse_params(params)?;
+                Ok(Self::OrandGetPublicEpoch(p.network, p.epoch))
+            }
+            "orand_newEpoch" => {
+                let p: OrandNewEpochParams = parse_params(params)?;
+                Ok(Self::OrandNewEpoch(p.network, p.block_number))
+            }
+            "orand_getPublicKey" => {
+                let p: OrandGetPublicKeyParams = parse_params(params)?;
+                Ok(Self::OrandGetPublicKey(p.network))
+            }
+            "orand_getEpochRange" => {
+                let p: OrandGetEpochRangeParams = parse_params(params)?;
+                if let Some(to_epoch) = p.to_epoch {
+                    check_epoch_range(p.from_epoch, to_epoch)?;
+                }
+                let limit = p.limit.unwrap_or(MAX_EPOCH_RANGE);
+                if !(1..=MAX_EPOCH_RANGE).contains(&limit) {
+                    return Err(JSONRPCError::InvalidParam(format!(
+                        "limit must be between 1 and {}",
+                        MAX_EPOCH_RANGE
+                    )));
+                }
+                let cursor = p.cursor.as_deref().map(decode_cursor).transpose()?;
+                Ok(Self::OrandGetEpochRange(
+                    p.network,
+                    p.from_epoch,
+                    p.to_epoch,
+                    cursor,
+                    limit as u64,
+                ))
+            }
+            "orand_getEpochsByTime" => {
+                let p: OrandGetEpochsByTimeParams = parse_params(params)?;
+                if p.to <= p.from {
+                    return Err(JSONRPCError::InvalidParam(
+                        "to must be after from".to_string(),
+                    ));
+                }
+                Ok(Self::OrandGetEpochsByTime(p.network, p.from, p.to))
+            }
+            "orand_getLatestEpoch" => {
+                let p: OrandGetLatestEpochParams = parse_params(params)?;
+                Ok(Self::OrandGetLatestEpoch(p.network))
+            }
+            "orand_listNetworks" => {
+                let _: OrandListNetworksParams = parse_params(params)?;
+                Ok(Self::OrandListNetworks)
+            }
+            "orand_getAuditLog" => {
+                let p: OrandGetAuditLogParams = parse_params(params)?;
+                check_epoch_range(p.from_epoch, p.to_epoch)?;
+                Ok(Self::OrandGetAuditLog(p.network, p.from_epoch, p.to_epoch))
+            }
+            "orand_createGenesis" => {
+                let p: OrandCreateGenesisParams = parse_params(params)?;
+                Ok(Self::OrandCreateGenesis(p.network, p.seed.b32()))
+            }
+            "orand_proveAlpha" => {
+                let p: OrandProveAlphaParams = parse_params(params)?;
+                Ok(Self::OrandProveAlpha(p.network, parse_alpha(&p.alpha)?))
+            }
+            "orand_simulateProve" => {
+                let p: OrandSimulateProveParams = parse_params(params)?;
+                Ok(Self::OrandSimulateProve(p.network, parse_alpha(&p.alpha)?))
+            }
+            "orand_findByValue" => {
+                let p: OrandFindByValueParams = parse_params(params)?;
+                Ok(Self::OrandFindByValue(
+                    p.network,
+                    hex::encode(parse_b32("y", &p.y)?),
+                ))
+            }
+            "orand_prune" => {
+                let p: OrandPruneParams = parse_params(params)?;
+                if p.keep_last_n < 1 {
+                    return Err(JSONRPCError::InvalidParam(
+                        "keep_last_n must be at least 1".to_string(),
+                    ));
+                }
+                Ok(Self::OrandPrune(p.network, p.keep_last_n as u64))
+            }
+            "orand_healEpoch" => {
+                let p: OrandHealEpochParams = parse_params(params)?;
+                Ok(Self::OrandHealEpoch(p.network, p.epoch))
+            }
+            "orand_verifyProof" => {
+                let p: OrandVerifyProofParams = parse_params(params)?;
+                Ok(Self::OrandVerifyProof(
+                    p.network,
+                    p.alpha,
+                    Box::new(p.proof),
+                ))
+            }
+            _ => Err(JSONRPCError::UnknownMethod(json_rpc.method)),
+        }
+    }
+}
+
+impl JSONRPCSubscription {
+    // Parse orand_subscribe and orand_unsubscribe, use request_id() to get its id
+    pub fn from_json_string(json_string: &str) -> Result<Self, JSONRPCError> {
+        let json_rpc = JSONRPCPayload::from_json_string(json_string)?;
         match json_rpc.method.as_str() {
-            "orand_getPublicEpoch" => Self::OrandGetPublicEpoch(
-                json_rpc.params[0].as_str().parse().unwrap(),
-                json_rpc.params[1].as_str().parse().unwrap(),
+            "orand_subscribe" => {
+                let p: OrandSubscribeParams = parse_params(json_rpc.params)?;
+                // Epochs are the only topic right now
+                if p.topic != "epochs" {
+                    return Err(JSONRPCError::InvalidParam(format!(
+                        "unknown topic {}",
+                        p.topic
+                    )));
+                }
+                Ok(Self::Subscribe(p.network))
+            }
+            "orand_unsubscribe" => {
+                let p: OrandUnsubscribeParams = parse_params(json_rpc.params)?;
+                Ok(Self::Unsubscribe(p.subscription))
+            }
+            method => Err(JSONRPCError::UnknownMethod(method.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ecvrf::{helper::randomize, random::thread_rng, secp256k1::SecretKey, ECVRF};
+    use serde_json::json;
+
+    #[test]
+    fn request_id_is_carried_through() {
+        let request =
+            r#"{"jsonrpc":"2.0","id":7,"method":"orand_getPublicEpoch","params":["56","10"]}"#;
+        let method = JSONRPCMethod::from_json_string(request).unwrap();
+        assert_eq!(request_id(request), json!(7));
+        assert!(matches!(
+            method,
+            JSONRPCMethod::OrandGetPublicEpoch(NetworkId(56), EpochId(10))
+        ));
+    }
+
+    #[test]
+    fn batch_is_an_invalid_request() {
+        let request =
+            r#"[{"jsonrpc":"2.0","id":1,"method":"orand_getPublicEpoch","params":[56, 0]}]"#;
+        assert_eq!(
+            JSONRPCMethod::from_json_string(request).err(),
+            Some(JSONRPCError::InvalidRequest)
+        );
+        assert_eq!(
+            JSONRPCSubscription::from_json_string(request).err(),
+            Some(JSONRPCError::InvalidRequest)
+        );
+    }
+
+    #[test]
+    fn request_without_version_is_rejected() {
+        let request = r#"{"id":"abc","method":"orand_newEpoch","params":["56"]}"#;
+        let error = JSONRPCMethod::from_json_string(request).err().unwrap();
+        assert_eq!(error, JSONRPCError::InvalidRequest);
+        let response = error.to_response(request_id(request));
+        assert_eq!(response.id, json!("abc"));
+        assert_eq!(response.error.unwrap().code, INVALID_REQUEST);
+    }
+
+    #[test]
+    fn malformed_request_is_parse_error() {
+        let request = r#"{"jsonrpc":"2.0","id":1,"method":"#;
+        let error = JSONRPCMethod::from_json_string(request).err().unwrap();
+        assert_eq!(error.code(), PARSE_ERROR);
+        assert_eq!(request_id(request), Value::Null);
+    }
+
+    #[test]
+    fn empty_params_are_missing() {
+        let error = JSONRPCMethod::from_json_string(
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_getPublicEpoch","params":[]}"#,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error, JSONRPCError::MissingParam("network"));
+        assert_eq!(error.code(), INVALID_PARAMS);
+    }
+
+    #[test]
+    fn non_numeric_network_is_invalid() {
+        let error = JSONRPCMethod::from_json_string(
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_newEpoch","params":["bnb"]}"#,
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(error, JSONRPCError::InvalidParam(_)));
+        assert_eq!(error.code(), INVALID_PARAMS);
+    }
+
+    #[test]
+    fn named_and_positional_params_are_equivalent() {
+        for request in [
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_getPublicEpoch","params":[56,10]}"#,
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_getPublicEpoch","params":["56","10"]}"#,
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_getPublicEpoch","params":{"epoch":10,"network":56}}"#,
+        ] {
+            assert!(matches!(
+                JSONRPCMethod::from_json_string(request),
+                Ok(JSONRPCMethod::OrandGetPublicEpoch(
+                    NetworkId(56),
+                    EpochId(10)
+                ))
+            ));
+        }
+    }
+
+    #[test]
+    fn named_params_require_every_field() {
+        let error = JSONRPCMethod::from_json_string(
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_getPublicEpoch","params":{"network":56}}"#,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error, JSONRPCError::MissingParam("epoch"));
+    }
+
+    #[test]
+    fn extra_params_are_rejected() {
+        for request in [
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_getPublicEpoch","params":[56, 0, 1]}"#,
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_getPublicEpoch","params":{"network":56,"epoch":0,"limit":1}}"#,
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_listNetworks","params":[56]}"#,
+        ] {
+            assert!(matches!(
+                JSONRPCMethod::from_json_string(request),
+                Err(JSONRPCError::InvalidParam(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn unknown_method_is_rejected() {
+        let error = JSONRPCMethod::from_json_string(
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_dropTables","params":[]}"#,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            error,
+            JSONRPCError::UnknownMethod("orand_dropTables".to_string())
+        );
+        assert_eq!(error.code(), METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn response_omits_unused_member() {
+        let success =
+            serde_json::to_value(JSONRPCResponse::success(json!(1), json!(true))).unwrap();
+        assert_eq!(success, json!({"jsonrpc":"2.0","id":1,"result":true}));
+        let error = serde_json::to_value(JSONRPCResponse::error(
+            Value::Null,
+            PARSE_ERROR,
+            "Parse error",
+        ))
+        .unwrap();
+        assert_eq!(
+            error,
+            json!({"jsonrpc":"2.0","id":null,"error":{"code":-32700,"message":"Parse error"}})
+        );
+    }
+
+    #[test]
+    fn verify_proof_params_are_decoded() {
+        let ecvrf = ECVRF::new(SecretKey::random(&mut thread_rng()));
+        let alpha = randomize();
+        let proof = ecvrf.prove(&alpha).unwrap();
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "orand_verifyProof",
+            "params": [56, hex::encode(alpha.b32()), proof],
+        })
+        .to_string();
+        match JSONRPCMethod::from_json_string(&request) {
+            Ok(JSONRPCMethod::OrandVerifyProof(network, decoded_alpha, decoded_proof)) => {
+                assert_eq!(network, 56);
+                assert_eq!(decoded_alpha, alpha);
+                assert!(ecvrf.verify(&decoded_alpha, &decoded_proof).unwrap());
+            }
+            _ => panic!("orand_verifyProof was not decoded"),
+        }
+    }
+
+    #[test]
+    fn verify_proof_rejects_bad_alpha() {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "orand_verifyProof",
+            "params": {"network": 56, "alpha": "0x1234", "proof": {}},
+        })
+        .to_string();
+        assert!(matches!(
+            JSONRPCMethod::from_json_string(&request),
+            Err(JSONRPCError::InvalidParam(_))
+        ));
+    }
+
+    #[test]
+    fn epoch_range_is_validated() {
+        let range = |from: i64, to: i64| {
+            JSONRPCMethod::from_json_string(
+                &json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "orand_getEpochRange",
+                    "params": [56, from, to],
+                })
+                .to_string(),
+            )
+        };
+        assert!(matches!(
+            range(10, 109),
+            Ok(JSONRPCMethod::OrandGetEpochRange(
+                NetworkId(56),
+                EpochId(10),
+                Some(EpochId(109)),
+                None,
+                100
+            ))
+        ));
+        assert!(matches!(
+            range(5, 5),
+            Ok(JSONRPCMethod::OrandGetEpochRange(
+                NetworkId(56),
+                EpochId(5),
+                Some(EpochId(5)),
+                None,
+                100
+            ))
+        ));
+        assert!(matches!(range(10, 110), Err(JSONRPCError::InvalidParam(_))));
+        assert!(matches!(range(10, 9), Err(JSONRPCError::InvalidParam(_))));
+    }
+
+    #[test]
+    fn epochs_by_time_are_normalized_to_utc() {
+        let by_time = |from: Value, to: Value| {
+            JSONRPCMethod::from_json_string(
+                &json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "orand_getEpochsByTime",
+                    "params": {"network": 56, "from": from, "to": to},
+                })
+                .to_string(),
+            )
+        };
+        let utc = |s: &str| DateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+        match by_time(json!("2023-01-01T07:00:00+07:00"), json!(1673136000)) {
+            Ok(JSONRPCMethod::OrandGetEpochsByTime(NetworkId(56), from, to)) => {
+                assert_eq!(from, utc("2023-01-01 00:00:00"));
+                assert_eq!(to, utc("2023-01-08 00:00:00"));
+            }
+            _ => panic!("orand_getEpochsByTime was not decoded"),
+        }
+        for (from, to) in [
+            (
+                json!("2023-01-08T00:00:00Z"),
+                json!("2023-01-08T07:00:00+07:00"),
             ),
-            "orand_newEpoch" => Self::OrandNewEpoch(json_rpc.params[0].as_str().parse().unwrap()),
-            "orand_getPublicKey" => Self::OrandGetPublicKey(json_rpc.params[0].to_string()),
-            _ => panic!("Unsupported method"),
+            (json!("2023-01-08T00:00:00Z"), json!("2023-01-01T00:00:00Z")),
+            (json!("2023-01-01 00:00:00"), json!("2023-01-08T00:00:00Z")),
+        ] {
+            assert!(matches!(
+                by_time(from, to),
+                Err(JSONRPCError::InvalidParam(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn epoch_pages_are_parsed() {
+        let page = |params: Value| {
+            JSONRPCMethod::from_json_string(
+                &json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "orand_getEpochRange",
+                    "params": params,
+                })
+                .to_string(),
+            )
+        };
+        // Pages aren't bounded by the range
+        assert!(matches!(
+            page(json!({"network": 56, "from_epoch": 0, "limit": 10})),
+            Ok(JSONRPCMethod::OrandGetEpochRange(
+                NetworkId(56),
+                EpochId(0),
+                None,
+                None,
+                10
+            ))
+        ));
+        let cursor = encode_cursor(250);
+        assert!(matches!(
+            page(json!([56, 0, null, cursor, "20"])),
+            Ok(JSONRPCMethod::OrandGetEpochRange(
+                NetworkId(56),
+                EpochId(0),
+                None,
+                Some(250),
+                20
+            ))
+        ));
+        for params in [
+            json!({"network": 56, "from_epoch": 0, "limit": 0}),
+            json!({"network": 56, "from_epoch": 0, "limit": 101}),
+            json!({"network": 56, "from_epoch": 0, "cursor": "not a cursor"}),
+            json!({"network": 56, "from_epoch": 0, "cursor": URL_SAFE_NO_PAD.encode("x")}),
+        ] {
+            assert!(matches!(page(params), Err(JSONRPCError::InvalidParam(_))));
         }
     }
+
+    #[test]
+    fn negative_network_and_epoch_are_invalid() {
+        let validate = |method: &str, params: Value| {
+            JSONRPCMethod::from_json_string(
+                &json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}).to_string(),
+            )
+            .unwrap()
+            .validate()
+        };
+        for (method, params) in [
+            ("orand_getPublicEpoch", json!([-56, 10])),
+            ("orand_getPublicEpoch", json!([0, 10])),
+            ("orand_newEpoch", json!([-1])),
+            ("orand_getLatestEpoch", json!([0])),
+            ("orand_getPublicEpoch", json!([56, -1])),
+            ("orand_getEpochRange", json!([56, -5, 5])),
+            ("orand_getPublicKey", json!([0])),
+        ] {
+            assert!(
+                matches!(
+                    validate(method, params.clone()),
+                    Err(JSONRPCError::InvalidParam(_))
+                ),
+                "{} {}",
+                method,
+                params
+            );
+        }
+        assert!(validate("orand_getPublicEpoch", json!([56, 0])).is_ok());
+        assert!(validate("orand_getPublicKey", json!(["56"])).is_ok());
+    }
+
+    #[test]
+    fn ids_above_i32_are_parsed() {
+        assert!(matches!(
+            JSONRPCMethod::from_json_string(
+                r#"{"jsonrpc":"2.0","id":1,"method":"orand_getPublicEpoch","params":[4294967352, 2147483648]}"#
+            ),
+            Ok(JSONRPCMethod::OrandGetPublicEpoch(
+                NetworkId(4_294_967_352),
+                EpochId(2_147_483_648)
+            ))
+        ));
+    }
+
+    #[test]
+    fn latest_epoch_is_parsed() {
+        assert!(matches!(
+            JSONRPCMethod::from_json_string(
+                r#"{"jsonrpc":"2.0","id":1,"method":"orand_getLatestEpoch","params":{"network":56}}"#
+            ),
+            Ok(JSONRPCMethod::OrandGetLatestEpoch(NetworkId(56)))
+        ));
+        let error = JSONRPCError::NotFound("epoch of network 56".to_string());
+        assert_eq!(error.to_response(json!(1)).error.unwrap().code, NOT_FOUND);
+    }
+
+    #[test]
+    fn list_networks_takes_no_params() {
+        for params in ["", r#","params":[]"#, r#","params":{}"#] {
+            let method = JSONRPCMethod::from_json_string(&format!(
+                r#"{{"jsonrpc":"2.0","id":1,"method":"orand_listNetworks"{}}}"#,
+                params
+            ))
+            .unwrap();
+            assert!(matches!(method, JSONRPCMethod::OrandListNetworks));
+            assert!(!method.requires_admin() && !method.requires_api_key());
+        }
+    }
+
+    #[test]
+    fn new_epoch_block_number_is_optional() {
+        for (params, block_number) in [
+            (json!(["56"]), None),
+            (json!([56, "17000000"]), Some(17_000_000)),
+            (
+                json!({"network": 56, "block_number": 17000000}),
+                Some(17_000_000),
+            ),
+            (json!({"network": 56, "block_number": null}), None),
+        ] {
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "orand_newEpoch",
+                "params": params,
+            })
+            .to_string();
+            match JSONRPCMethod::from_json_string(&request) {
+                Ok(JSONRPCMethod::OrandNewEpoch(NetworkId(56), decoded)) => {
+                    assert_eq!(decoded, block_number)
+                }
+                _ => panic!("orand_newEpoch was not decoded"),
+            }
+        }
+    }
+
+    #[test]
+    fn prune_is_an_admin_method() {
+        let method = JSONRPCMethod::from_json_string(
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_prune","params":[56, 10]}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            method,
+            JSONRPCMethod::OrandPrune(NetworkId(56), 10)
+        ));
+        assert_eq!(method.name(), "orand_prune");
+        assert!(method.requires_admin());
+        assert!(!method.requires_api_key());
+        assert!(!JSONRPCMethod::OrandGetLatestEpoch(NetworkId(56)).requires_admin());
+        assert!(JSONRPCMethod::from_json_string(
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_healEpoch","params":[56, 3]}"#
+        )
+        .unwrap()
+        .requires_admin());
+        assert!(matches!(
+            JSONRPCMethod::from_json_string(
+                r#"{"jsonrpc":"2.0","id":1,"method":"orand_prune","params":[56, 0]}"#
+            ),
+            Err(JSONRPCError::InvalidParam(_))
+        ));
+        assert_eq!(JSONRPCError::Unauthorized.code(), UNAUTHORIZED);
+    }
+
+    #[test]
+    fn subscription_requests_are_parsed() {
+        assert_eq!(
+            JSONRPCSubscription::from_json_string(
+                r#"{"jsonrpc":"2.0","id":1,"method":"orand_subscribe","params":["epochs", 56]}"#
+            ),
+            Ok(JSONRPCSubscription::Subscribe(NetworkId(56)))
+        );
+        assert_eq!(
+            JSONRPCSubscription::from_json_string(
+                r#"{"jsonrpc":"2.0","id":2,"method":"orand_unsubscribe","params":{"subscription":"3"}}"#
+            ),
+            Ok(JSONRPCSubscription::Unsubscribe(3))
+        );
+        assert!(matches!(
+            JSONRPCSubscription::from_json_string(
+                r#"{"jsonrpc":"2.0","id":1,"method":"orand_subscribe","params":["blocks", 56]}"#
+            ),
+            Err(JSONRPCError::InvalidParam(_))
+        ));
+        // Other methods aren't served over WebSocket
+        assert!(matches!(
+            JSONRPCSubscription::from_json_string(
+                r#"{"jsonrpc":"2.0","id":1,"method":"orand_getLatestEpoch","params":[56]}"#
+            ),
+            Err(JSONRPCError::UnknownMethod(_))
+        ));
+    }
+
+    #[test]
+    fn create_genesis_is_an_admin_method() {
+        let seed = "0x".to_string() + &"11".repeat(32);
+        let method = JSONRPCMethod::from_json_string(&format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"orand_createGenesis","params":[56, "{}"]}}"#,
+            seed
+        ))
+        .unwrap();
+        assert!(
+            matches!(method, JSONRPCMethod::OrandCreateGenesis(NetworkId(56), s) if s == [0x11; 32])
+        );
+        assert!(method.requires_admin());
+        // Seed is used as alpha, it must be a valid scalar
+        assert!(matches!(
+            JSONRPCMethod::from_json_string(&format!(
+                r#"{{"jsonrpc":"2.0","id":1,"method":"orand_createGenesis","params":[56, "{}"]}}"#,
+                "ff".repeat(32)
+            )),
+            Err(JSONRPCError::InvalidParam(_))
+        ));
+    }
+
+    #[test]
+    fn audit_log_is_an_admin_method() {
+        let method = JSONRPCMethod::from_json_string(
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_getAuditLog","params":[56, 0, 10]}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            method,
+            JSONRPCMethod::OrandGetAuditLog(NetworkId(56), EpochId(0), EpochId(10))
+        ));
+        assert!(method.requires_admin());
+        assert!(matches!(
+            JSONRPCMethod::from_json_string(
+                r#"{"jsonrpc":"2.0","id":1,"method":"orand_getAuditLog","params":[56, 10, 0]}"#,
+            ),
+            Err(JSONRPCError::InvalidParam(_))
+        ));
+    }
+
+    #[test]
+    fn prove_alpha_requires_32_bytes() {
+        let prove = |alpha: &str| {
+            JSONRPCMethod::from_json_string(
+                &json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "orand_proveAlpha",
+                    "params": [56, alpha],
+                })
+                .to_string(),
+            )
+        };
+        let alpha = "ab".repeat(32);
+        let method = prove(&format!("0x{}", alpha)).unwrap();
+        assert!(matches!(&method, JSONRPCMethod::OrandProveAlpha(NetworkId(56), a) if *a == alpha));
+        assert_eq!(method.name(), "orand_proveAlpha");
+        assert!(method.requires_api_key() && !method.requires_admin());
+        assert!(method.writes());
+        for bad in ["ab".repeat(31), "zz".repeat(32), "ff".repeat(32)] {
+            assert!(matches!(prove(&bad), Err(JSONRPCError::InvalidParam(_))));
+        }
+    }
+
+    #[test]
+    fn simulate_prove_is_parsed_like_prove_alpha() {
+        let alpha = "ab".repeat(32);
+        let method = JSONRPCMethod::from_json_string(
+            &json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "orand_simulateProve",
+                "params": {"network": "56", "alpha": format!("0x{}", alpha)},
+            })
+            .to_string(),
+        )
+        .unwrap();
+        assert!(
+            matches!(&method, JSONRPCMethod::OrandSimulateProve(NetworkId(56), a) if *a == alpha)
+        );
+        assert_eq!(method.name(), "orand_simulateProve");
+        assert!(method.requires_api_key() && !method.requires_admin());
+        // Simulations are read only, they're served while the node shuts down
+        assert!(!method.writes());
+    }
 }
@@ -1,4 +1,14 @@
 mod sqlite;
-pub use sqlite::{keyring, prelude, randomness, sqlitedb};
+pub use sqlite::{
+    api_key, audit_log, database, kek, keyring, network, prelude, randomness, sqlitedb,
+};
+pub mod config;
+pub mod epoch_scheduler;
 pub mod ethereum;
+pub mod ids;
 pub mod json_rpc;
+pub mod logging;
+pub mod metrics;
+pub mod server;
+pub mod shutdown;
+pub mod store;
@@ -0,0 +1,11 @@
+use tracing_subscriber::{fmt::format::FmtSpan, EnvFilter};
+
+// Log to stderr with the filter of RUST_LOG, only errors are logged if it isn't set
+// Closed spans are logged with their time so a slow request can be traced to its method,
+// network and epoch, e.g. RUST_LOG=orochimaru=debug also shows proving and queries
+pub fn init() {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_span_events(FmtSpan::CLOSE)
+        .init();
+}
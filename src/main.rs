@@ -1,191 +1,44 @@
 #![deny(warnings)]
 
-use bytes::Bytes;
 use dotenv::dotenv;
 use ecvrf::{
     helper::{generate_raw_keypair, get_address, random_bytes},
-    secp256k1::{curve::Scalar, PublicKey, SecretKey},
-    ECVRF,
+    secp256k1::{PublicKey, SecretKey},
 };
-use http_body_util::{combinators::BoxBody, BodyExt, Empty, Full};
-use hyper::{
-    body::Body,
-    server::conn::http1,
-    service::service_fn,
-    {Method, Request, Response, StatusCode},
+use orochimaru::{
+    config::Config,
+    epoch_scheduler::{active_networks, EpochScheduler},
+    kek::Kek,
+    logging,
+    prelude::Keyring,
+    server::{serve, AppState},
+    shutdown,
+    sqlitedb::SqliteDB,
 };
-use orochimaru::{json_rpc::JSONRPCMethod, sqlitedb::SqliteDB};
 use serde_json::json;
-use std::{borrow::Borrow, env, net::SocketAddr, str::from_utf8};
-use tokio::net::TcpListener;
-
-const CHAIN_ID_BNB: u32 = 56;
-
-/// This is our service handler. It receives a Request, routes on its
-/// path, and returns a Future of a Response.
-async fn orand(
-    req: Request<hyper::body::Incoming>,
-) -> Result<Response<BoxBody<Bytes, hyper::Error>>, hyper::Error> {
-    let database_url = env::var("DATABASE_URL").expect("Can not connect to the database");
-    // TODO Move these to another module, we should separate between KEYS and API
-    let sqlite: SqliteDB = SqliteDB::new(database_url).await;
-    let keyring = sqlite.table_keyring().await;
-    let randomness = sqlite.table_randomness().await;
-
-    // Reconstruct secret key from database
-    let keyring_record = keyring
-        .find_by_name("chiro".to_string())
-        .await
-        .expect("Can not query our database")
-        .expect("Chiro not found");
-
-    let secret_key = SecretKey::parse(
-        hex::decode(keyring_record.secret_key)
-            .unwrap()
-            .as_slice()
-            .try_into()
-            .unwrap(),
-    )
-    .expect("Can not reconstruct secret key");
-
-    let vrf = ECVRF::new(secret_key);
-
-    match (req.method(), req.uri().path()) {
-        // Serve some instructions at /
-        (&Method::GET, "/") => Ok(Response::new(full("Wrong method, you know!."))),
-
-        // Simply echo the body back to the client.
-        (&Method::POST, "/") => {
-            let max = req.body().size_hint().upper().unwrap_or(u64::MAX);
-            if max > 1024 * 64 {
-                let mut resp = Response::new(full("Your body too big, can not fit the body bag"));
-                *resp.status_mut() = hyper::StatusCode::PAYLOAD_TOO_LARGE;
-                return Ok(resp);
-            }
-            // Body to byte
-            let whole_body = req.collect().await?.to_bytes();
-            let json_string = from_utf8(whole_body.borrow()).unwrap();
-            let json_rpc_payload = JSONRPCMethod::from_json_string(json_string);
-
-            match json_rpc_payload {
-                // We ignore network param right now, support BNB chain first
-                JSONRPCMethod::OrandGetPublicEpoch(_, epoch) => {
-                    let recent_epochs = randomness
-                        .find_recent_epoch(epoch)
-                        .await
-                        .expect("Can not get recent epoch");
-
-                    let serialized_result = serde_json::to_string_pretty(&recent_epochs)
-                        .expect("Can not serialize data");
-                    Ok(Response::new(full(serialized_result)))
-                }
-                JSONRPCMethod::OrandNewEpoch(_) => {
-                    let latest_epoch_record =
-                        randomness.find_latest_epoch(CHAIN_ID_BNB).await.unwrap();
-
-                    let (current_alpha, next_epoch) = match latest_epoch_record {
-                        Some(latest_epoch) => {
-                            let mut alpha = Scalar::default();
-                            // Alpha of current epoch is previous randomness
-                            alpha
-                                .set_b32(
-                                    &hex::decode(latest_epoch.y)
-                                        .unwrap()
-                                        .as_slice()
-                                        .try_into()
-                                        .unwrap(),
-                                )
-                                .unwrap_u8();
-                            (alpha, latest_epoch.epoch + 1)
-                        }
-                        None => {
-                            // Get alpha from random entropy
-                            let mut buf = [0u8; 32];
-                            random_bytes(&mut buf);
-                            let mut alpha = Scalar::default();
-                            alpha.set_b32(&buf).unwrap_u8();
-                            (alpha, 0)
-                        }
-                    };
-
-                    let contract_proof = vrf.prove_contract(&current_alpha);
-
-                    let gamma =
-                        [contract_proof.gamma.x.b32(), contract_proof.gamma.y.b32()].concat();
-                    let witness_gamma = [
-                        contract_proof.witness_gamma.x.b32(),
-                        contract_proof.witness_gamma.y.b32(),
-                    ]
-                    .concat();
-
-                    let witness_hash = [
-                        contract_proof.witness_hash.x.b32(),
-                        contract_proof.witness_hash.y.b32(),
-                    ]
-                    .concat();
-
-                    let insert_result = randomness
-                        .insert_returning(json!({
-                            "network": CHAIN_ID_BNB,
-                            "keyring_id": keyring_record.id,
-                            "epoch": next_epoch,
-                            "alpha":hex::encode(current_alpha.b32()),
-                            "gamma":hex::encode(&gamma),
-                            "c":hex::encode(&contract_proof.c.b32()),
-                            "s":hex::encode(&contract_proof.s.b32()),
-                            "y":hex::encode(&contract_proof.y.b32()),
-                            "witness_address": hex::encode(contract_proof.witness_address.b32())[24..64],
-                            "witness_gamma": hex::encode(&witness_gamma),
-                            "witness_hash": hex::encode(&witness_hash),
-                            "inverse_z": hex::encode(contract_proof.inverse_z.b32()),
-                        }))
-                        .await
-                        .unwrap();
-                    let serialized_result = serde_json::to_string_pretty(&insert_result).unwrap();
-                    Ok(Response::new(full(serialized_result)))
-                }
-                JSONRPCMethod::OrandGetPublicKey(key_name) => {
-                    let key_record = keyring
-                        .find_by_name(key_name)
-                        .await
-                        .expect("Can find the given key name");
-
-                    let serialized_result =
-                        serde_json::to_string_pretty(&key_record).expect("Can not serialize data");
-                    Ok(Response::new(full(serialized_result)))
-                }
-            }
-        }
-
-        // Return the 403 Forbidden for other routes.
-        _ => {
-            let mut not_found = Response::new(empty());
-            *not_found.status_mut() = StatusCode::FORBIDDEN;
-            Ok(not_found)
-        }
-    }
-}
-
-fn empty() -> BoxBody<Bytes, hyper::Error> {
-    Empty::<Bytes>::new()
-        .map_err(|never| match never {})
-        .boxed()
-}
-
-fn full<T: Into<Bytes>>(chunk: T) -> BoxBody<Bytes, hyper::Error> {
-    Full::new(chunk.into())
-        .map_err(|never| match never {})
-        .boxed()
-}
+use std::{
+    env,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{net::TcpListener, time};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     dotenv().ok();
-    let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
-    let database_url = env::var("DATABASE_URL").expect("Can not connect to the database");
-    let sqlite: SqliteDB = SqliteDB::new(database_url).await;
+    logging::init();
+    // Settings are read from the file of ORAND_CONFIG if it's set, environment variables
+    // override them
+    let config = match env::var("ORAND_CONFIG") {
+        Ok(path) => Config::load(path)?,
+        Err(_) => Config::from_env()?,
+    };
+    let server_config = config.server.clone();
+    let sqlite: SqliteDB =
+        SqliteDB::with_config(config.database.url.clone(), &config.database.pool)
+            .await
+            .with_kek(Kek::from_env()?);
     let keyring = sqlite.table_keyring().await;
-    let result_keyring = keyring.find_by_name("chiro".to_string()).await.unwrap();
+    let result_keyring = keyring.load_keyring(&server_config.keyring_name).await?;
 
     // Create new key if not exist
     match result_keyring {
@@ -193,29 +46,43 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
             // Generate key if it didn't exist
             let mut hmac_secret = [0u8; 16];
             random_bytes(&mut hmac_secret);
-            let new_keypair = generate_raw_keypair();
+            // Keys derived from ORAND_KEYRING_SEED can be recovered from the seed
+            let seed = env::var("ORAND_KEYRING_SEED")
+                .ok()
+                .filter(|seed| !seed.is_empty());
+            let (secret_key, public_key) = match &seed {
+                Some(seed) => Keyring::from_seed(seed.as_bytes()),
+                None => {
+                    let new_keypair = generate_raw_keypair();
+                    let secret_key = SecretKey::parse(&new_keypair.secret_key)
+                        .expect("Can not parse secret key");
+                    (secret_key, PublicKey::from_secret_key(&secret_key))
+                }
+            };
+            let address = get_address(public_key);
             keyring
-                .insert(json!({
-                "username": "chiro",
-                "hmac_secret": hex::encode(hmac_secret),
-                "public_key": hex::encode(new_keypair.public_key), 
-                "secret_key": hex::encode(new_keypair.secret_key)}))
-                .await
-                .unwrap();
+                .store_keyring(
+                    json!({
+                    "username": server_config.keyring_name,
+                    "hmac_secret": hex::encode(hmac_secret),
+                    "public_key": hex::encode(public_key.serialize()),
+                    "address": hex::encode(address),
+                    "derived": seed.is_some()}),
+                    &secret_key,
+                )
+                .await?;
         }
-        Some(k) => {
-            println!("Found chiro key!");
-            println!("Secret key: {}", k.secret_key);
+        Some((k, secret_key)) => {
+            println!("Found {} key!", server_config.keyring_name);
             println!("Public Key: {}", k.public_key);
-            let secret_key = SecretKey::parse(
-                hex::decode(k.secret_key)
-                    .unwrap()
-                    .as_slice()
-                    .try_into()
-                    .unwrap(),
-            )
-            .unwrap();
             let public_key = PublicKey::from_secret_key(&secret_key);
+            if k.parse_public_key().ok() != Some(public_key) {
+                return Err(format!(
+                    "Public key of {} doesn't match its secret key",
+                    server_config.keyring_name
+                )
+                .into());
+            }
             println!(
                 "Address of public key: {}",
                 hex::encode(get_address(public_key))
@@ -223,20 +90,97 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         }
     };
 
-    let listener = TcpListener::bind(addr).await?;
+    // Register API key from environment so relayers can create epochs
+    if let Ok(api_key) = env::var("ORAND_API_KEY") {
+        let api_key_table = sqlite.table_api_key().await;
+        if api_key_table.find_by_key(&api_key).await?.is_none() {
+            api_key_table
+                .insert("default".to_string(), &api_key)
+                .await?;
+            println!("Registered API key from ORAND_API_KEY");
+        }
+    }
 
-    println!("Listening on http://{}", addr);
+    // Register configured networks, registered networks keep their configuration
+    let keyring_record = keyring
+        .find_by_name(server_config.keyring_name.clone())
+        .await?
+        .ok_or_else(|| format!("Keyring {} not found", server_config.keyring_name))?;
+    let network_table = sqlite.table_network().await;
+    for network in config.networks.iter() {
+        if network_table
+            .find_by_chain_id(network.chain_id)
+            .await?
+            .is_none()
+        {
+            network_table
+                .insert_returning(json!({
+                    "name": network.name(),
+                    "chain_id": network.chain_id,
+                    "epoch_interval_secs": config.epoch_interval_of(network),
+                    "keyring_id": keyring_record.id,
+                }))
+                .await?;
+            println!("Registered network {}", network.chain_id);
+        }
+    }
 
-    loop {
-        let (stream, _) = listener.accept().await?;
+    let state = AppState::new(sqlite, server_config.clone());
+    let shutdown = state.shutdown();
 
-        tokio::task::spawn(async move {
-            if let Err(err) = http1::Builder::new()
-                .serve_connection(stream, service_fn(orand))
-                .await
-            {
-                println!("Error serving connection: {:?}", err);
+    // Generate epochs of all active networks periodically, each at its own interval
+    let mut schedulers = Vec::new();
+    if config.epoch_interval_secs.is_some() {
+        for (interval, networks) in active_networks(&state.db()).await? {
+            println!(
+                "Generating epochs of networks {:?} every {} seconds",
+                networks
+                    .iter()
+                    .map(|(network, _)| network)
+                    .collect::<Vec<_>>(),
+                interval
+            );
+            let scheduler = EpochScheduler::new(state.db(), networks)
+                .with_notifier(state.epochs())
+                .with_shutdown(shutdown.clone());
+            // Fill the epochs that were due while the node was down before the first round
+            if let Some(max_backfill) = config.max_backfill_epochs {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+                scheduler.catch_up(interval, now, max_backfill).await;
             }
-        });
+            schedulers.push(scheduler.start(Duration::from_secs(interval)));
+        }
+    }
+
+    let listener = TcpListener::bind(server_config.bind_address).await?;
+
+    println!("Listening on http://{}", server_config.bind_address);
+
+    let mut server = tokio::spawn(serve(listener, state, shutdown.triggered()));
+    tokio::select! {
+        _ = shutdown::terminated() => println!("Shutting down"),
+        served = &mut server => return Ok(served??),
+    }
+
+    // New epochs aren't started from here on, the ones in flight are committed first
+    let timeout = Duration::from_secs(config.shutdown_timeout_secs);
+    let deadline = time::Instant::now() + timeout;
+    if !shutdown.drain(timeout).await {
+        println!(
+            "{} epochs are still in flight after {} seconds, exiting",
+            shutdown.in_flight(),
+            config.shutdown_timeout_secs
+        );
+        return Ok(());
+    }
+    for scheduler in schedulers {
+        scheduler.stop().await;
+    }
+    // Idle connections are closed by the server, e.g. WebSocket subscribers are cut at the
+    // deadline
+    match time::timeout_at(deadline, server).await {
+        Ok(served) => served??,
+        Err(_) => println!("Connections are still open, exiting"),
     }
+    Ok(())
 }
@@ -0,0 +1,44 @@
+use crate::ids::NetworkId;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::{sync::OnceLock, time::Duration};
+
+pub const REQUESTS_TOTAL: &str = "orand_requests_total";
+pub const PROOFS_GENERATED_TOTAL: &str = "orand_proofs_generated_total";
+pub const PROVE_DURATION_SECONDS: &str = "orand_prove_duration_seconds";
+pub const VERIFY_DURATION_SECONDS: &str = "orand_verify_duration_seconds";
+
+// Buckets of prove/verify duration, both take a few milliseconds
+const DURATION_BUCKETS: &[f64] = &[0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
+
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+// Install the Prometheus recorder, the recorder is global so it's only installed once
+pub fn install() -> PrometheusHandle {
+    HANDLE
+        .get_or_init(|| {
+            PrometheusBuilder::new()
+                .set_buckets_for_metric(
+                    Matcher::Suffix("duration_seconds".to_string()),
+                    DURATION_BUCKETS,
+                )
+                .expect("Can not set histogram buckets")
+                .install_recorder()
+                .expect("Can not install metrics recorder")
+        })
+        .clone()
+}
+
+// JSON-RPC request of the given method, requests that can't be parsed are "invalid"
+pub fn record_request(method: &'static str) {
+    ::metrics::counter!(REQUESTS_TOTAL, "method" => method).increment(1);
+}
+
+// New epoch of the given network was proved
+pub fn record_proof(network: NetworkId, duration: Duration) {
+    ::metrics::counter!(PROOFS_GENERATED_TOTAL, "network" => network.to_string()).increment(1);
+    ::metrics::histogram!(PROVE_DURATION_SECONDS).record(duration.as_secs_f64());
+}
+
+pub fn record_verify(duration: Duration) {
+    ::metrics::histogram!(VERIFY_DURATION_SECONDS).record(duration.as_secs_f64());
+}
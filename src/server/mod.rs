@@ -0,0 +1,1217 @@
+use crate::{
+    epoch_scheduler::{
+        chain_epoch, create_genesis_epoch, heal_epoch, prove_caller_alpha, simulate_prove,
+        EpochError, Signer, Trigger,
+    },
+    ids::{EpochId, NetworkId},
+    json_rpc::{encode_cursor, request_id, JSONRPCError, JSONRPCMethod, JSONRPCResponse},
+    keyring::Model as ModelKeyring,
+    metrics,
+    randomness::Model as ModelRandomness,
+    shutdown::Shutdown,
+    sqlitedb::{KeyringTable, SqliteDB},
+    store::RandomnessStore,
+};
+use axum::{
+    body::{Body, Bytes},
+    extract::{ConnectInfo, DefaultBodyLimit, Path, Query, State},
+    http::{
+        header::{ACCEPT, AUTHORIZATION, CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH, VARY},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use ecvrf::{
+    ecproof::ECVRFProof,
+    helper::{get_address, public_key_to_hex, scalar_from_b32},
+    secp256k1::{curve::Scalar, PublicKey},
+    signature::verify_response_signature,
+    VrfError, ECVRF,
+};
+use futures_util::{stream, StreamExt};
+use metrics_exporter_prometheus::PrometheusHandle;
+use rate_limit::{RateLimitConfig, RateLimiter};
+use sea_orm::DbErr;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    env,
+    future::Future,
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    str::from_utf8,
+    sync::Arc,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+use subtle::ConstantTimeEq;
+use tokio::{
+    net::TcpListener,
+    sync::{broadcast, mpsc},
+};
+use tracing::Instrument;
+
+pub mod rate_limit;
+mod ws;
+
+// Network of the default config
+const CHAIN_ID_BNB: NetworkId = NetworkId(56);
+// Admin methods need this header to match the configured admin token
+pub const ADMIN_TOKEN_HEADER: &str = "x-orand-admin-token";
+// Media type of proofs encoded by ECVRFProof::to_cbor()
+pub const CBOR: &str = "application/cbor";
+// Maximum size of a request body
+const MAX_BODY_SIZE: usize = 64 * 1024;
+// New epochs that are buffered for each WebSocket subscriber
+const EPOCH_CHANNEL_SIZE: usize = 64;
+// Encoded epochs of an export that are buffered ahead of the client
+const EXPORT_CHANNEL_SIZE: usize = 64;
+// Media types of GET /export/{network} and /proof
+const NDJSON: &str = "application/x-ndjson";
+const OCTET_STREAM: &str = "application/octet-stream";
+const APPLICATION_JSON: &str = "application/json";
+// Cache-Control of an epoch that is addressed by its number, it's never rewritten
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+// Cache-Control of the latest epoch, a new one is created every epoch interval
+const LATEST_MAX_AGE: &str = "public, max-age=5";
+
+// [server] section of the config file, networks are the chain ids of its [[network]] tables
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub bind_address: SocketAddr,
+    // Name of the keyring that signs epochs
+    #[serde(rename = "keyring")]
+    pub keyring_name: String,
+    // Admin methods are disabled if it's None
+    pub admin_token: Option<String>,
+    // Networks that must have an epoch for the node to be ready
+    #[serde(skip)]
+    pub networks: Vec<NetworkId>,
+    // Requests to the JSON-RPC endpoint per client IP, unlimited if it's None
+    pub rate_limit: Option<RateLimitConfig>,
+    // Seconds a caller alpha can't be submitted again, repeats are allowed if it's None
+    pub replay_window_secs: Option<u64>,
+    // Epochs are checked against the keyring that signed them before they're served
+    pub strict_integrity: bool,
+    // JSON responses are wrapped in a SignedResponse signed by the keyring
+    pub signed_responses: bool,
+}
+
+// Envelope of a signed response, payload is the response exactly as it was signed so
+// cached or relayed responses can be authenticated without trusting the transport
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SignedResponse {
+    pub payload: String,
+    // RecoverableSignature::to_bytes() of keccak256(payload) by the keyring as 0x hex
+    pub signature: String,
+}
+
+impl SignedResponse {
+    // Check the response was signed by the oracle address of orand_getPublicKey
+    pub fn verify(&self, address: &[u8; 20]) -> Result<bool, VrfError> {
+        let signature = hex::decode(self.signature.trim_start_matches("0x"))
+            .map_err(|_| VrfError::InvalidSignature)?;
+        verify_response_signature(self.payload.as_bytes(), &signature, address)
+    }
+}
+
+// Result of orand_getPublicEpoch, the proof with the context it was produced in
+#[derive(Serialize, Debug, Clone)]
+pub struct EpochResponse {
+    pub network: NetworkId,
+    pub epoch: EpochId,
+    pub alpha: String,
+    // Null for epochs created before proofs were stored
+    pub proof: Option<ECVRFProof>,
+    // ISO-8601 in UTC, e.g. 2022-11-11T07:22:36Z
+    pub created_date: String,
+    pub block_number: Option<i64>,
+}
+
+impl From<&ModelRandomness> for EpochResponse {
+    fn from(record: &ModelRandomness) -> Self {
+        Self {
+            network: record.network,
+            epoch: record.epoch,
+            alpha: record.alpha.clone(),
+            proof: record
+                .proof
+                .as_deref()
+                .and_then(|proof| ECVRFProof::from_bytes(proof).ok()),
+            created_date: record.created_date.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            block_number: record.block_number,
+        }
+    }
+}
+
+// Item of orand_listNetworks
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct NetworkResponse {
+    pub network: NetworkId,
+    pub latest_epoch: EpochId,
+    // Ethereum address of the keyring that signed the latest epoch without 0x
+    pub address: Option<String>,
+}
+
+// Result of orand_findByValue, y is unique so more than one record is an anomaly
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FindByValueResponse {
+    pub epochs: Vec<ModelRandomness>,
+    pub anomaly: bool,
+}
+
+// Result of orand_getEpochRange, next_cursor is null on the last page
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct EpochPageResponse {
+    pub epochs: Vec<ModelRandomness>,
+    pub next_cursor: Option<String>,
+}
+
+// Result of orand_getPublicKey, everything an on-chain verifier is set up with
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PublicKeyResponse {
+    pub public_key_compressed: String,
+    pub public_key_uncompressed: String,
+    // Address of the key as Ethereum derives it, keccak256 of the uncompressed x || y
+    pub address: String,
+}
+
+impl From<&PublicKey> for PublicKeyResponse {
+    fn from(public_key: &PublicKey) -> Self {
+        Self {
+            public_key_compressed: public_key_to_hex(public_key, true),
+            public_key_uncompressed: public_key_to_hex(public_key, false),
+            address: format!("0x{}", hex::encode(get_address(*public_key))),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: SocketAddr::from(([127, 0, 0, 1], 3000)),
+            keyring_name: "chiro".to_string(),
+            admin_token: None,
+            networks: vec![CHAIN_ID_BNB],
+            rate_limit: Some(RateLimitConfig::default()),
+            replay_window_secs: None,
+            strict_integrity: false,
+            signed_responses: false,
+        }
+    }
+}
+
+impl ServerConfig {
+    // Configuration overridden by ORAND_BIND_ADDRESS, ORAND_KEYRING, ORAND_ADMIN_TOKEN,
+    // ORAND_REPLAY_WINDOW, ORAND_STRICT_INTEGRITY, ORAND_SIGNED_RESPONSES and the rate limit
+    // variables, networks are overridden by Config
+    pub fn with_env(self) -> Result<Self, String> {
+        Ok(Self {
+            bind_address: match env::var("ORAND_BIND_ADDRESS") {
+                Ok(address) => address
+                    .parse()
+                    .map_err(|_| format!("Invalid value of ORAND_BIND_ADDRESS: {}", address))?,
+                Err(_) => self.bind_address,
+            },
+            keyring_name: env::var("ORAND_KEYRING").unwrap_or(self.keyring_name),
+            admin_token: match env::var("ORAND_ADMIN_TOKEN") {
+                Ok(token) => Some(token),
+                Err(_) => self.admin_token,
+            }
+            .filter(|token| !token.is_empty()),
+            networks: self.networks,
+            rate_limit: match self.rate_limit {
+                Some(rate_limit) => Some(rate_limit.with_env()?),
+                // Unlimited unless one of the variables turns the limit on
+                None if env::var("ORAND_RATE_LIMIT_RPS").is_ok()
+                    || env::var("ORAND_RATE_LIMIT_BURST").is_ok() =>
+                {
+                    Some(RateLimitConfig::default().with_env()?)
+                }
+                None => None,
+            },
+            replay_window_secs: match env::var("ORAND_REPLAY_WINDOW") {
+                Ok(window) => Some(
+                    window
+                        .parse()
+                        .map_err(|_| format!("Invalid value of ORAND_REPLAY_WINDOW: {}", window))?,
+                ),
+                Err(_) => self.replay_window_secs,
+            },
+            strict_integrity: match env::var("ORAND_STRICT_INTEGRITY") {
+                Ok(strict) => strict
+                    .parse()
+                    .map_err(|_| format!("Invalid value of ORAND_STRICT_INTEGRITY: {}", strict))?,
+                Err(_) => self.strict_integrity,
+            },
+            signed_responses: match env::var("ORAND_SIGNED_RESPONSES") {
+                Ok(signed) => signed
+                    .parse()
+                    .map_err(|_| format!("Invalid value of ORAND_SIGNED_RESPONSES: {}", signed))?,
+                Err(_) => self.signed_responses,
+            },
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct AppState {
+    db: Arc<SqliteDB>,
+    config: Arc<ServerConfig>,
+    metrics: PrometheusHandle,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    // New epochs are pushed to WebSocket subscribers
+    epochs: broadcast::Sender<ModelRandomness>,
+    shutdown: Shutdown,
+}
+
+impl AppState {
+    pub fn new(db: SqliteDB, config: ServerConfig) -> Self {
+        Self {
+            db: Arc::new(db),
+            rate_limiter: config.rate_limit.map(|c| Arc::new(RateLimiter::new(c))),
+            config: Arc::new(config),
+            metrics: metrics::install(),
+            epochs: broadcast::channel(EPOCH_CHANNEL_SIZE).0,
+            shutdown: Shutdown::new(),
+        }
+    }
+
+    pub fn db(&self) -> Arc<SqliteDB> {
+        self.db.clone()
+    }
+
+    // Channel of new epochs, the scheduler publishes its epochs here too
+    pub fn epochs(&self) -> broadcast::Sender<ModelRandomness> {
+        self.epochs.clone()
+    }
+
+    // Writes aren't accepted once it's triggered, the ones in flight are drained
+    pub fn shutdown(&self) -> Shutdown {
+        self.shutdown.clone()
+    }
+
+    // Epoch that is served, it must pass its integrity check in strict mode
+    async fn checked(&self, record: ModelRandomness) -> Result<ModelRandomness, String> {
+        if !self.config.strict_integrity {
+            return Ok(record);
+        }
+        let public_key = self
+            .db
+            .table_keyring()
+            .await
+            .find_by_id(record.keyring_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Keyring {} not found", record.keyring_id))?
+            .parse_public_key()
+            .map_err(|e| e.to_string())?;
+        record.verify_integrity(&public_key).map_err(|e| {
+            tracing::error!(
+                network = record.network.0,
+                epoch = record.epoch.0,
+                "integrity check failed: {}",
+                e
+            );
+            format!(
+                "epoch {} of network {} failed its integrity check: {}",
+                record.epoch, record.network, e
+            )
+        })?;
+        Ok(record)
+    }
+
+    // JSON body of a response, it's a SignedResponse of the JSON if responses are signed
+    async fn json_body<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, String> {
+        let payload = serde_json::to_string(value).map_err(|e| e.to_string())?;
+        if !self.config.signed_responses {
+            return Ok(payload.into_bytes());
+        }
+        let (_, secret_key) = self
+            .db
+            .table_keyring()
+            .await
+            .load_keyring(&self.config.keyring_name)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Keyring {} not found", self.config.keyring_name))?;
+        let signature = ECVRF::new(secret_key)
+            .sign_payload(payload.as_bytes())
+            .map_err(|e| e.to_string())?;
+        serde_json::to_vec(&SignedResponse {
+            payload,
+            signature: format!("0x{}", hex::encode(signature.to_bytes())),
+        })
+        .map_err(|e| e.to_string())
+    }
+
+    // Compare in constant time, admin methods are disabled if there is no admin token
+    fn is_admin(&self, token: Option<&str>) -> bool {
+        match (&self.config.admin_token, token) {
+            (Some(expected), Some(token)) => {
+                bool::from(expected.as_bytes().ct_eq(token.as_bytes()))
+            }
+            _ => false,
+        }
+    }
+}
+
+// JSON-RPC endpoint of the oracle
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/",
+            post(json_rpc).get(|| async { "Wrong method, you know!." }),
+        )
+        .route(
+            "/healthz",
+            get(|| async { Json(json!({ "status": "ok" })) }),
+        )
+        .route("/readyz", get(readyz))
+        .route("/proof/{network}/{epoch}", get(proof))
+        .route("/public/{round}", get(public_beacon))
+        .route("/export/{network}", get(export))
+        .route("/ws", get(ws::ws_handler))
+        .route(
+            "/metrics",
+            get(|State(state): State<AppState>| async move { state.metrics.render() }),
+        )
+        .fallback(|| async { StatusCode::FORBIDDEN })
+        .layer(DefaultBodyLimit::max(MAX_BODY_SIZE))
+        .with_state(state)
+}
+
+// Serve the router until shutdown resolves, in-flight requests are completed before returning
+pub async fn serve(
+    listener: TcpListener,
+    state: AppState,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> io::Result<()> {
+    // Rate limiter needs the address of the client
+    axum::serve(
+        listener,
+        router(state).into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown)
+    .await
+}
+
+fn error_response(status: StatusCode, error: String) -> Response {
+    (status, Json(json!({ "error": error }))).into_response()
+}
+
+// Proof of an epoch, it's CBOR if the client accepts application/cbor and JSON otherwise
+// {epoch}.json is the EpochResponse of the epoch, the epoch is a number or latest
+// Responses can be cached by CDNs, they're revalidated with their ETag
+async fn proof(
+    State(state): State<AppState>,
+    Path((network, epoch)): Path<(NetworkId, String)>,
+    headers: HeaderMap,
+) -> Response {
+    let (epoch, full) = match epoch.strip_suffix(".json") {
+        Some(epoch) => (epoch, true),
+        None => (epoch.as_str(), false),
+    };
+    let record = match find_served_epoch(&state, network, epoch).await {
+        Ok(record) => record,
+        Err(response) => return response,
+    };
+    let not_found = || {
+        error_response(
+            StatusCode::NOT_FOUND,
+            format!("proof of epoch {} of network {}", epoch, network),
+        )
+    };
+    let (content_type, body) = match record {
+        Some(record) if full => (
+            APPLICATION_JSON,
+            state.json_body(&EpochResponse::from(&record)).await,
+        ),
+        Some(record) => match EpochResponse::from(&record).proof {
+            Some(proof) if accepts_cbor(&headers) => (CBOR, Ok(proof.to_cbor())),
+            Some(proof) => (APPLICATION_JSON, state.json_body(&proof).await),
+            None => return not_found(),
+        },
+        None => return not_found(),
+    };
+    cached_response(&headers, content_type, body, epoch == "latest")
+}
+
+// Epoch of a network by number or latest, it's checked if strict_integrity is set
+async fn find_served_epoch(
+    state: &AppState,
+    network: NetworkId,
+    epoch: &str,
+) -> Result<Option<ModelRandomness>, Response> {
+    let randomness = state.db.table_randomness().await;
+    let record = if epoch == "latest" {
+        randomness.find_latest_epoch(network).await
+    } else {
+        match epoch.parse() {
+            Ok(epoch) => randomness.find_epoch(network, epoch).await,
+            Err(_) => {
+                return Err(error_response(
+                    StatusCode::BAD_REQUEST,
+                    format!("Invalid epoch: {}", epoch),
+                ))
+            }
+        }
+    }
+    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    match record {
+        Some(record) => state
+            .checked(record)
+            .await
+            .map(Some)
+            .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, e)),
+        None => Ok(None),
+    }
+}
+
+// Body of an epoch with the headers of CDNs, latest is only cached for a few seconds
+fn cached_response(
+    headers: &HeaderMap,
+    content_type: &str,
+    body: Result<Vec<u8>, String>,
+    latest: bool,
+) -> Response {
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    // Representations differ by their body so the ETag is a digest of it
+    let etag = format!("\"{}\"", hex::encode(&Sha256::digest(&body)[..16]));
+    let cache_control = if latest { LATEST_MAX_AGE } else { IMMUTABLE };
+    let cache_headers = [
+        (CACHE_CONTROL, cache_control),
+        (ETAG, etag.as_str()),
+        (VARY, ACCEPT.as_str()),
+    ];
+    let revalidated = headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|tag| tag.trim() == etag || tag.trim() == "*");
+    if revalidated {
+        (StatusCode::NOT_MODIFIED, cache_headers).into_response()
+    } else {
+        (cache_headers, [(CONTENT_TYPE, content_type)], body).into_response()
+    }
+}
+
+// Epoch of the first configured network as a drand beacon so drand clients can point at the
+// node, round is the epoch or latest
+async fn public_beacon(
+    State(state): State<AppState>,
+    Path(round): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let network = state
+        .config
+        .networks
+        .first()
+        .copied()
+        .unwrap_or(CHAIN_ID_BNB);
+    let not_found = || {
+        error_response(
+            StatusCode::NOT_FOUND,
+            format!("round {} of network {}", round, network),
+        )
+    };
+    let proof_of = |record: &ModelRandomness| {
+        record
+            .proof
+            .as_deref()
+            .and_then(|proof| ECVRFProof::from_bytes(proof).ok())
+    };
+    let (epoch, proof) = match find_served_epoch(&state, network, &round).await {
+        Ok(Some(record)) => match proof_of(&record) {
+            Some(proof) => (record.epoch, proof),
+            None => return not_found(),
+        },
+        Ok(None) => return not_found(),
+        Err(response) => return response,
+    };
+    // Genesis and the first epoch that is left after pruning have no previous signature
+    let previous = match epoch.0 {
+        0 => Ok(None),
+        n => state
+            .db
+            .table_randomness()
+            .await
+            .find_epoch(network, EpochId(n - 1))
+            .await
+            .map(|record| record.as_ref().and_then(proof_of)),
+    };
+    let previous = match previous {
+        Ok(previous) => previous,
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    let body = state
+        .json_body(&proof.to_drand_json(epoch.0 as u64, previous.as_ref()))
+        .await;
+    cached_response(&headers, APPLICATION_JSON, body, round == "latest")
+}
+
+fn accepts_cbor(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|media_type| media_type.split(';').next().unwrap_or("").trim() == CBOR)
+}
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    format: Option<String>,
+}
+
+// Every epoch of a network ordered by epoch, one EpochResponse per line or the wire format
+// of the proofs back to back with ?format=binary, those are PROOF_SIZE bytes each
+// Rows are streamed from the database so memory doesn't grow with the history
+async fn export(
+    State(state): State<AppState>,
+    Path(network): Path<NetworkId>,
+    Query(query): Query<ExportQuery>,
+) -> Response {
+    let (binary, content_type) = match query.format.as_deref() {
+        None | Some("json") => (false, NDJSON),
+        Some("binary") => (true, OCTET_STREAM),
+        Some(format) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": format!("unknown format {}", format) })),
+            )
+                .into_response()
+        }
+    };
+    match state
+        .db
+        .table_network()
+        .await
+        .find_by_chain_id(network)
+        .await
+    {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": format!("network {}", network) })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    }
+
+    // The stream borrows the database, it's read by a task that owns a handle of it
+    let (chunks, receiver) = mpsc::channel(EXPORT_CHANNEL_SIZE);
+    let db = state.db();
+    tokio::spawn(async move {
+        let randomness = db.table_randomness().await;
+        let mut epochs = match randomness.stream_epochs(network).await {
+            Ok(epochs) => Box::pin(epochs),
+            Err(e) => {
+                let _ = chunks.send(Err(e)).await;
+                return;
+            }
+        };
+        while let Some(record) = epochs.next().await {
+            let chunk = record.and_then(|record| {
+                let epoch = EpochResponse::from(&record);
+                if binary {
+                    // Epochs without a decodable proof can't be written in the wire format, the
+                    // export fails rather than skip them
+                    epoch
+                        .proof
+                        .map(|proof| proof.to_bytes().to_vec())
+                        .ok_or_else(|| {
+                            DbErr::Custom(format!(
+                                "epoch {} of network {} has no proof",
+                                record.epoch, record.network
+                            ))
+                        })
+                } else {
+                    let mut line =
+                        serde_json::to_vec(&epoch).map_err(|e| DbErr::Json(e.to_string()))?;
+                    line.push(b'\n');
+                    Ok(line)
+                }
+            });
+            let failed = chunk.is_err();
+            // The client is gone
+            if chunks.send(chunk).await.is_err() || failed {
+                break;
+            }
+        }
+    });
+    // An error ends the body early, the client sees a truncated export
+    let body = stream::unfold(receiver, |mut receiver| async move {
+        receiver
+            .recv()
+            .await
+            .map(|chunk| (chunk.map(Bytes::from), receiver))
+    });
+    ([(CONTENT_TYPE, content_type)], Body::from_stream(body)).into_response()
+}
+
+// Ready if the database is reachable and every configured network has an epoch
+async fn readyz(State(state): State<AppState>) -> impl IntoResponse {
+    if let Err(e) = state.db.ping().await {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "status": "unavailable",
+                "error": e.to_string(),
+                "unhealthy_networks": state.config.networks,
+            })),
+        );
+    }
+    let randomness = state.db.table_randomness().await;
+    let mut unhealthy_networks = Vec::new();
+    for network in state.config.networks.iter() {
+        if !matches!(randomness.find_latest_epoch(*network).await, Ok(Some(_))) {
+            unhealthy_networks.push(*network);
+        }
+    }
+    if unhealthy_networks.is_empty() {
+        (StatusCode::OK, Json(json!({ "status": "ok" })))
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "status": "unavailable",
+                "unhealthy_networks": unhealthy_networks,
+            })),
+        )
+    }
+}
+
+async fn json_rpc(
+    State(state): State<AppState>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if let Some(rate_limiter) = &state.rate_limiter {
+        let ip = connect_info
+            .map(|Extension(ConnectInfo(addr))| addr.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        if !rate_limiter.try_acquire(ip, Instant::now()) {
+            let id = from_utf8(&body).map(request_id).unwrap_or(Value::Null);
+            let response = JSONRPCError::RateLimited.to_response(id);
+            return (StatusCode::TOO_MANY_REQUESTS, Json(response)).into_response();
+        }
+    }
+    let response = handle_request(&state, &headers, &body).await;
+    match state.json_body(&response).await {
+        Ok(body) => ([(CONTENT_TYPE, APPLICATION_JSON)], body).into_response(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+// Parse, authorize and dispatch a single JSON-RPC request
+pub async fn handle_request(state: &AppState, headers: &HeaderMap, body: &[u8]) -> JSONRPCResponse {
+    let json_string = match from_utf8(body) {
+        Ok(json_string) => json_string,
+        Err(e) => return JSONRPCError::ParseError(e.to_string()).to_response(Value::Null),
+    };
+    let id = request_id(json_string);
+    let method = match JSONRPCMethod::from_json_string(json_string) {
+        Ok(method) => method,
+        Err(error) => {
+            metrics::record_request("invalid");
+            return error.to_response(id);
+        }
+    };
+    metrics::record_request(method.name());
+    // Params are left out of the span, they may carry API keys or seeds
+    let (network, epoch) = method.target();
+    let span = tracing::info_span!(
+        "json_rpc",
+        method = method.name(),
+        network = network.map(|network| network.0),
+        epoch = epoch.map(|epoch| epoch.0)
+    );
+    async move {
+        let actor = match authorize(state, headers, &method).await {
+            Ok(actor) => actor,
+            Err(error) => return error.to_response(id),
+        };
+        // Writes hold off shutdown until they're committed
+        let _work = if method.writes() {
+            match state.shutdown.start_work() {
+                Some(work) => Some(work),
+                None => return JSONRPCError::ShuttingDown.to_response(id),
+            }
+        } else {
+            None
+        };
+        let trigger = Trigger {
+            actor,
+            request_hash: Some(hex::encode(Sha256::digest(body))),
+        };
+        match dispatch(state, method, &trigger).await {
+            Ok(result) => JSONRPCResponse::success(id, result),
+            Err(error) => {
+                tracing::debug!(code = error.code(), "{}", error);
+                error.to_response(id)
+            }
+        }
+    }
+    .instrument(span)
+    .await
+}
+
+// Admin methods need the admin token, write methods need a bearer API key
+// Id of the API key is returned if the method needs one
+async fn authorize(
+    state: &AppState,
+    headers: &HeaderMap,
+    method: &JSONRPCMethod,
+) -> Result<Option<i32>, JSONRPCError> {
+    if method.requires_admin() {
+        let admin_token = headers
+            .get(ADMIN_TOKEN_HEADER)
+            .and_then(|v| v.to_str().ok());
+        if !state.is_admin(admin_token) {
+            return Err(JSONRPCError::Unauthorized);
+        }
+    }
+    if method.requires_api_key() {
+        let api_key = match headers
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+        {
+            Some(api_key) => api_key.trim(),
+            None => return Err(JSONRPCError::Unauthorized),
+        };
+        return match state
+            .db
+            .table_api_key()
+            .await
+            .find_by_key(api_key)
+            .await
+            .map_err(internal)?
+        {
+            Some(record) => Ok(Some(record.id)),
+            None => Err(JSONRPCError::Unauthorized),
+        };
+    }
+    Ok(None)
+}
+
+fn internal<E: ToString>(e: E) -> JSONRPCError {
+    JSONRPCError::Internal(e.to_string())
+}
+
+// Seconds since the unix epoch, seen alphas are timed by the clock of the node
+fn unix_time() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+fn to_value<T: serde::Serialize>(value: &T) -> Result<Value, JSONRPCError> {
+    serde_json::to_value(value).map_err(internal)
+}
+
+// Alphas are validated by the parser, they're 32 bytes hex without 0x
+fn parse_scalar(alpha: &str) -> Result<Scalar, JSONRPCError> {
+    let bytes: [u8; 32] = hex::decode(alpha)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| internal("alpha must be 32 bytes"))?;
+    scalar_from_b32(&bytes).map_err(internal)
+}
+
+fn caller_alpha_error(network: NetworkId, e: EpochError) -> JSONRPCError {
+    match e {
+        EpochError::AlphaTaken(_) | EpochError::Conflict(_, _) => {
+            JSONRPCError::Conflict(e.to_string())
+        }
+        EpochError::NetworkNotFound(_) | EpochError::KeyringNotFound(_) => {
+            JSONRPCError::NotFound(format!("keyring of network {}", network))
+        }
+        e => internal(e),
+    }
+}
+
+// Epochs are signed by the keyring of their network
+async fn network_keyring(
+    keyring: &KeyringTable<'_>,
+    network: NetworkId,
+) -> Result<ModelKeyring, JSONRPCError> {
+    keyring
+        .find_keyring_for_network(network)
+        .await
+        .map_err(internal)?
+        .ok_or_else(|| JSONRPCError::NotFound(format!("keyring of network {}", network)))
+}
+
+fn new_epoch_error(network: NetworkId, e: EpochError) -> JSONRPCError {
+    match e {
+        EpochError::Conflict(_, _) => JSONRPCError::Conflict(e.to_string()),
+        EpochError::NetworkNotFound(_) => JSONRPCError::NotFound(format!("network {}", network)),
+        EpochError::KeyringNotFound(_) => {
+            JSONRPCError::NotFound(format!("keyring of network {}", network))
+        }
+        e => internal(e),
+    }
+}
+
+// Handlers of methods that only need the epochs, they're tested against MemoryStore
+async fn get_public_epoch(
+    store: &dyn RandomnessStore,
+    network: NetworkId,
+    epoch: EpochId,
+) -> Result<ModelRandomness, JSONRPCError> {
+    store
+        .get(network, epoch)
+        .await
+        .map_err(internal)?
+        .ok_or_else(|| JSONRPCError::NotFound(format!("epoch {} of network {}", epoch, network)))
+}
+
+async fn get_latest_epoch(
+    store: &dyn RandomnessStore,
+    network: NetworkId,
+) -> Result<ModelRandomness, JSONRPCError> {
+    store
+        .latest(network)
+        .await
+        .map_err(internal)?
+        .ok_or_else(|| JSONRPCError::NotFound(format!("epoch of network {}", network)))
+}
+
+async fn get_epoch_range(
+    store: &dyn RandomnessStore,
+    network: NetworkId,
+    from_epoch: EpochId,
+    to_epoch: Option<EpochId>,
+    cursor: Option<i32>,
+    limit: u64,
+) -> Result<EpochPageResponse, JSONRPCError> {
+    let (epochs, next) = store
+        .range(network, from_epoch, to_epoch, cursor, limit)
+        .await
+        .map_err(internal)?;
+    Ok(EpochPageResponse {
+        epochs,
+        next_cursor: next.map(encode_cursor),
+    })
+}
+
+async fn create_new_epoch(
+    store: &dyn RandomnessStore,
+    signer: &Signer,
+    network: NetworkId,
+    block_number: Option<i64>,
+    trigger: &Trigger,
+) -> Result<ModelRandomness, JSONRPCError> {
+    chain_epoch(store, signer, network, block_number, trigger)
+        .await
+        .map_err(|e| new_epoch_error(network, e))
+}
+
+async fn dispatch(
+    state: &AppState,
+    method: JSONRPCMethod,
+    trigger: &Trigger,
+) -> Result<Value, JSONRPCError> {
+    method.validate()?;
+    let keyring = state.db.table_keyring().await;
+    let randomness = state.db.table_randomness().await;
+
+    match method {
+        JSONRPCMethod::OrandGetPublicEpoch(network, epoch) => {
+            let record = get_public_epoch(&randomness, network, epoch).await?;
+            let record = state.checked(record).await.map_err(internal)?;
+            to_value(&EpochResponse::from(&record))
+        }
+        JSONRPCMethod::OrandNewEpoch(network, block_number) => {
+            let keyring_name = network_keyring(&keyring, network).await?.username;
+            let signer = Signer::load(&state.db, &keyring_name, network)
+                .await
+                .map_err(|e| new_epoch_error(network, e))?;
+            let record =
+                create_new_epoch(&randomness, &signer, network, block_number, trigger).await?;
+            // It's fine if nobody is subscribed
+            let _ = state.epochs.send(record.clone());
+            to_value(&record)
+        }
+        JSONRPCMethod::OrandCreateGenesis(network, seed) => {
+            let signer = network_keyring(&keyring, network).await?;
+            match create_genesis_epoch(&state.db, &signer.username, network, seed, trigger).await {
+                Ok(record) => {
+                    let _ = state.epochs.send(record.clone());
+                    to_value(&record)
+                }
+                Err(e @ (EpochError::GenesisExists(_) | EpochError::Conflict(_, _))) => {
+                    Err(JSONRPCError::Conflict(e.to_string()))
+                }
+                Err(EpochError::NetworkNotFound(network)) => {
+                    Err(JSONRPCError::NotFound(format!("network {}", network)))
+                }
+                Err(EpochError::KeyringNotFound(_)) => Err(JSONRPCError::NotFound(format!(
+                    "keyring of network {}",
+                    network
+                ))),
+                Err(e) => Err(internal(e)),
+            }
+        }
+        JSONRPCMethod::OrandGetEpochRange(network, from_epoch, to_epoch, cursor, limit) => {
+            to_value(
+                &get_epoch_range(&randomness, network, from_epoch, to_epoch, cursor, limit).await?,
+            )
+        }
+        JSONRPCMethod::OrandGetEpochsByTime(network, from, to) => to_value(
+            &randomness
+                .find_by_date_range(network, from, to)
+                .await
+                .map_err(internal)?,
+        ),
+        JSONRPCMethod::OrandGetAuditLog(network, from_epoch, to_epoch) => to_value(
+            &state
+                .db
+                .table_audit_log()
+                .await
+                .find_epoch_range(network, from_epoch, to_epoch)
+                .await
+                .map_err(internal)?,
+        ),
+        JSONRPCMethod::OrandGetLatestEpoch(network) => {
+            to_value(&get_latest_epoch(&randomness, network).await?)
+        }
+        // Proofs are verified against the key of the keyring that signs the network
+        JSONRPCMethod::OrandVerifyProof(network, alpha, proof) => {
+            let public_key = network_keyring(&keyring, network)
+                .await?
+                .parse_public_key()
+                .map_err(|e| internal(format!("Can not reconstruct public key: {}", e)))?;
+            let started = Instant::now();
+            let verified = tracing::debug_span!("verify")
+                .in_scope(|| proof.verify_with_pubkey(&alpha, &public_key));
+            metrics::record_verify(started.elapsed());
+            let valid = verified.map_err(|e| JSONRPCError::InvalidParam(e.to_string()))?;
+            Ok(json!({ "valid": valid }))
+        }
+        JSONRPCMethod::OrandPrune(network, keep_last_n) => {
+            let deleted = randomness
+                .prune_randomness(network, keep_last_n)
+                .await
+                .map_err(internal)?;
+            Ok(json!({ "deleted": deleted }))
+        }
+        // Intact epochs are returned as they are with healed false
+        JSONRPCMethod::OrandHealEpoch(network, epoch) => {
+            match heal_epoch(&state.db, network, epoch, trigger).await {
+                Ok((record, healed)) => Ok(json!({ "healed": healed, "epoch": record })),
+                Err(e @ EpochError::EpochNotFound(_, _)) => {
+                    Err(JSONRPCError::NotFound(e.to_string()))
+                }
+                Err(e) => Err(internal(e)),
+            }
+        }
+        JSONRPCMethod::OrandListNetworks => {
+            let mut networks = Vec::new();
+            for record in randomness.find_latest_epochs().await.map_err(internal)? {
+                let address = keyring
+                    .find_by_id(record.keyring_id)
+                    .await
+                    .map_err(internal)?
+                    .and_then(|keyring| keyring.address);
+                networks.push(NetworkResponse {
+                    network: record.network,
+                    latest_epoch: record.epoch,
+                    address,
+                });
+            }
+            to_value(&networks)
+        }
+        JSONRPCMethod::OrandGetPublicKey(network) => {
+            let public_key = network_keyring(&keyring, network)
+                .await?
+                .parse_public_key()
+                .map_err(|e| internal(format!("Can not reconstruct public key: {}", e)))?;
+            to_value(&PublicKeyResponse::from(&public_key))
+        }
+        // Proofs of caller alphas aren't epochs, they aren't sent to subscribers
+        JSONRPCMethod::OrandProveAlpha(network, alpha) => {
+            let signer = network_keyring(&keyring, network).await?;
+            let scalar = parse_scalar(&alpha)?;
+            let record = prove_caller_alpha(&state.db, &signer.username, network, scalar)
+                .await
+                .map_err(|e| caller_alpha_error(network, e))?;
+            // Only proven alphas are seen, a request that failed can be retried
+            // Requests racing for the same alpha are told apart by the unique index of seen_alpha
+            if let Some(window) = state.config.replay_window_secs {
+                let fresh = state
+                    .db
+                    .table_seen_alpha()
+                    .await
+                    .record(network, &record.alpha, unix_time(), window as i64)
+                    .await
+                    .map_err(internal)?;
+                if !fresh {
+                    return Err(JSONRPCError::Replay(alpha));
+                }
+            }
+            to_value(&record)
+        }
+        // Dry run of orand_proveAlpha, nothing is written so it isn't checked for replays
+        JSONRPCMethod::OrandSimulateProve(network, alpha) => {
+            let signer = network_keyring(&keyring, network).await?;
+            let alpha = parse_scalar(&alpha)?;
+            simulate_prove(&state.db, &signer.username, network, alpha)
+                .await
+                .map_err(|e| caller_alpha_error(network, e))
+                .and_then(|proof| to_value(&proof))
+        }
+        JSONRPCMethod::OrandFindByValue(network, y) => {
+            let epochs = randomness.find_by_y(network, &y).await.map_err(internal)?;
+            if epochs.is_empty() {
+                return Err(JSONRPCError::NotFound(format!(
+                    "epoch of network {} with y {}",
+                    network, y
+                )));
+            }
+            let anomaly = epochs.len() > 1;
+            if anomaly {
+                tracing::warn!(
+                    "{} records of network {} have y {}",
+                    epochs.len(),
+                    network,
+                    y
+                );
+            }
+            to_value(&FindByValueResponse { epochs, anomaly })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{json_rpc::NOT_FOUND, store::MemoryStore};
+    use ecvrf::helper::generate_keypair;
+
+    #[tokio::test]
+    async fn epoch_handlers_run_without_a_database() {
+        let store = MemoryStore::new();
+        let keypair = generate_keypair();
+        let signer = Signer {
+            keyring_id: 1,
+            secret_key: keypair.secret_key,
+        };
+        let error = get_latest_epoch(&store, CHAIN_ID_BNB).await.unwrap_err();
+        assert_eq!(error.code(), NOT_FOUND);
+
+        let trigger = Trigger::default();
+        let genesis = create_new_epoch(&store, &signer, CHAIN_ID_BNB, None, &trigger)
+            .await
+            .unwrap();
+        let next = create_new_epoch(&store, &signer, CHAIN_ID_BNB, Some(100), &trigger)
+            .await
+            .unwrap();
+        assert_eq!(next.epoch, 1);
+        assert_eq!(next.alpha, genesis.y);
+        assert_eq!(next.block_number, Some(100));
+        assert!(next.verify_integrity(&keypair.public_key).is_ok());
+
+        assert_eq!(get_latest_epoch(&store, CHAIN_ID_BNB).await.unwrap(), next);
+        // Retries of a request return the epoch it created
+        let request = Trigger {
+            actor: Some(1),
+            request_hash: Some("ab".repeat(32)),
+        };
+        let created = create_new_epoch(&store, &signer, NetworkId(97), None, &request)
+            .await
+            .unwrap();
+        let retried = create_new_epoch(&store, &signer, NetworkId(97), None, &request)
+            .await
+            .unwrap();
+        assert_eq!(retried, created);
+        assert_eq!(
+            get_public_epoch(&store, CHAIN_ID_BNB, EpochId(0))
+                .await
+                .unwrap(),
+            genesis
+        );
+        let error = get_public_epoch(&store, CHAIN_ID_BNB, EpochId(2))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), NOT_FOUND);
+        assert!(get_latest_epoch(&store, NetworkId(1)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn epoch_ranges_are_paged_without_a_database() {
+        let store = MemoryStore::new();
+        let signer = Signer {
+            keyring_id: 1,
+            secret_key: generate_keypair().secret_key,
+        };
+        let trigger = Trigger::default();
+        let mut epochs = Vec::new();
+        for network in [
+            CHAIN_ID_BNB,
+            NetworkId(97),
+            CHAIN_ID_BNB,
+            CHAIN_ID_BNB,
+            CHAIN_ID_BNB,
+        ] {
+            let record = create_new_epoch(&store, &signer, network, None, &trigger)
+                .await
+                .unwrap();
+            if network == CHAIN_ID_BNB {
+                epochs.push(record);
+            }
+        }
+
+        // Epochs 1 to 3 in pages of 2, the cursor is the id of the last record of a page
+        let first = get_epoch_range(&store, CHAIN_ID_BNB, EpochId(1), Some(EpochId(3)), None, 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            first,
+            EpochPageResponse {
+                epochs: epochs[1..3].to_vec(),
+                next_cursor: Some(encode_cursor(epochs[2].id)),
+            }
+        );
+        let second = get_epoch_range(
+            &store,
+            CHAIN_ID_BNB,
+            EpochId(1),
+            Some(EpochId(3)),
+            Some(epochs[2].id),
+            2,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            second,
+            EpochPageResponse {
+                epochs: epochs[3..].to_vec(),
+                next_cursor: None,
+            }
+        );
+        let empty = get_epoch_range(&store, NetworkId(1), EpochId(0), None, None, 2)
+            .await
+            .unwrap();
+        assert!(empty.epochs.is_empty() && empty.next_cursor.is_none());
+    }
+}
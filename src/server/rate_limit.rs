@@ -0,0 +1,132 @@
+use serde::Deserialize;
+use std::{collections::HashMap, env, net::IpAddr, sync::Mutex, time::Instant};
+
+// Buckets are pruned once there are this many clients
+const MAX_BUCKETS: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    // Tokens refilled per second
+    pub requests_per_second: f64,
+    // Size of the bucket, the number of requests a client can send at once
+    pub burst: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 10.0,
+            burst: 20,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    // Configuration overridden by ORAND_RATE_LIMIT_RPS and ORAND_RATE_LIMIT_BURST
+    pub fn with_env(self) -> Result<Self, String> {
+        let default = self;
+        let config = Self {
+            requests_per_second: match env::var("ORAND_RATE_LIMIT_RPS") {
+                Ok(value) => value
+                    .parse()
+                    .map_err(|_| format!("Invalid value of ORAND_RATE_LIMIT_RPS: {}", value))?,
+                Err(_) => default.requests_per_second,
+            },
+            burst: match env::var("ORAND_RATE_LIMIT_BURST") {
+                Ok(value) => value
+                    .parse()
+                    .map_err(|_| format!("Invalid value of ORAND_RATE_LIMIT_BURST: {}", value))?,
+                Err(_) => default.burst,
+            },
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.requests_per_second.is_nan() || self.requests_per_second <= 0.0 || self.burst == 0 {
+            return Err("Rate limit must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+// Token bucket per client IP
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Take a token from the bucket of the given IP, false if it's empty
+    pub fn try_acquire(&self, ip: IpAddr, now: Instant) -> bool {
+        let burst = self.config.burst as f64;
+        let rate = self.config.requests_per_second;
+        let refill = |bucket: &Bucket| {
+            let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+            (bucket.tokens + elapsed * rate).min(burst)
+        };
+        let mut buckets = self.buckets.lock().expect("Rate limiter lock is poisoned");
+        if buckets.len() >= MAX_BUCKETS && !buckets.contains_key(&ip) {
+            // Full buckets are the same as new ones
+            buckets.retain(|_, bucket| refill(bucket) < burst);
+        }
+        let bucket = buckets.entry(ip).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        bucket.tokens = refill(bucket);
+        bucket.updated = now;
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn bucket_is_refilled_over_time() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_second: 2.0,
+            burst: 3,
+        });
+        let client = IpAddr::from([10, 0, 0, 1]);
+        let other = IpAddr::from([10, 0, 0, 2]);
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.try_acquire(client, now));
+        }
+        assert!(!limiter.try_acquire(client, now));
+        // Other clients have their own bucket
+        assert!(limiter.try_acquire(other, now));
+        // A token is refilled every 500ms
+        let later = now + Duration::from_millis(500);
+        assert!(limiter.try_acquire(client, later));
+        assert!(!limiter.try_acquire(client, later));
+        // Bucket never holds more than burst
+        let much_later = now + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert!(limiter.try_acquire(client, much_later));
+        }
+        assert!(!limiter.try_acquire(client, much_later));
+    }
+}
@@ -0,0 +1,80 @@
+use super::AppState;
+use crate::{
+    ids::NetworkId,
+    json_rpc::{request_id, JSONRPCResponse, JSONRPCSubscription, JSONRPC_VERSION},
+};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use tokio::sync::broadcast::error::RecvError;
+
+pub(crate) async fn ws_handler(ws: WebSocketUpgrade, State(state): State<AppState>) -> Response {
+    ws.on_upgrade(move |socket| subscription(socket, state))
+}
+
+// Serve subscriptions of a client until it disconnects
+async fn subscription(mut socket: WebSocket, state: AppState) {
+    let mut epochs = state.epochs.subscribe();
+    // Subscription id to network
+    let mut subscriptions: HashMap<i64, NetworkId> = HashMap::new();
+    let mut next_id = 0;
+    loop {
+        tokio::select! {
+            message = socket.recv() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    // Ping is answered by the WebSocket implementation
+                    Some(Ok(_)) => continue,
+                };
+                let id = request_id(text.as_str());
+                let response = match JSONRPCSubscription::from_json_string(text.as_str()) {
+                    Ok(JSONRPCSubscription::Subscribe(network)) => {
+                        next_id += 1;
+                        subscriptions.insert(next_id, network);
+                        JSONRPCResponse::success(id, json!(next_id))
+                    }
+                    Ok(JSONRPCSubscription::Unsubscribe(subscription)) => JSONRPCResponse::success(
+                        id,
+                        json!(subscriptions.remove(&subscription).is_some()),
+                    ),
+                    Err(error) => error.to_response(id),
+                };
+                if send(&mut socket, json!(response)).await.is_err() {
+                    break;
+                }
+            }
+            epoch = epochs.recv() => {
+                let record = match epoch {
+                    Ok(record) => record,
+                    // Slow client missed some epochs, it can catch up with orand_getEpochRange
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                };
+                for (subscription, _) in subscriptions
+                    .iter()
+                    .filter(|(_, network)| **network == record.network)
+                {
+                    let notification = json!({
+                        "jsonrpc": JSONRPC_VERSION,
+                        "method": "orand_subscription",
+                        "params": { "subscription": subscription, "result": record },
+                    });
+                    if send(&mut socket, notification).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn send(socket: &mut WebSocket, message: serde_json::Value) -> Result<(), axum::Error> {
+    socket.send(Message::Text(message.to_string().into())).await
+}
@@ -0,0 +1,138 @@
+use std::{future::Future, sync::Arc, time::Duration};
+use tokio::{sync::watch, time};
+
+#[derive(Clone, Copy, Debug, Default)]
+struct State {
+    triggered: bool,
+    in_flight: usize,
+}
+
+// Coordinates shutdown of the node: once it's triggered no new work is started and the work
+// in flight, e.g. an epoch that is being proven and inserted, is drained
+// Both are kept behind one lock so work can't start after a drain saw nothing in flight
+#[derive(Clone, Debug)]
+pub struct Shutdown {
+    state: Arc<watch::Sender<State>>,
+}
+
+// Work in flight, it's done when it's dropped
+#[derive(Debug)]
+pub struct Work {
+    state: Arc<watch::Sender<State>>,
+}
+
+impl Drop for Work {
+    fn drop(&mut self) {
+        self.state.send_modify(|state| state.in_flight -= 1);
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(watch::Sender::new(State::default())),
+        }
+    }
+
+    // Stop accepting new work, it's fine to trigger it more than once
+    pub fn trigger(&self) {
+        self.state.send_if_modified(|state| {
+            let triggered = !state.triggered;
+            state.triggered = true;
+            triggered
+        });
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.state.borrow().triggered
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.state.borrow().in_flight
+    }
+
+    // Resolves once shutdown is triggered, e.g. for the graceful shutdown of the server
+    pub fn triggered(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut state = self.state.subscribe();
+        async move {
+            let _ = state.wait_for(|state| state.triggered).await;
+        }
+    }
+
+    // Work that must be completed before the node exits, None once shutdown is triggered
+    pub fn start_work(&self) -> Option<Work> {
+        let started = self.state.send_if_modified(|state| {
+            if state.triggered {
+                return false;
+            }
+            state.in_flight += 1;
+            true
+        });
+        started.then(|| Work {
+            state: self.state.clone(),
+        })
+    }
+
+    // Trigger shutdown and wait for the work in flight, false if it's still running after the
+    // timeout
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        self.trigger();
+        let mut state = self.state.subscribe();
+        let drained = time::timeout(timeout, state.wait_for(|state| state.in_flight == 0))
+            .await
+            .is_ok();
+        drained
+    }
+}
+
+// Resolves on Ctrl+C or SIGTERM, which is what container runtimes send
+pub async fn terminated() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Can not install Ctrl+C handler");
+    };
+    #[cfg(unix)]
+    let sigterm = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Can not install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let sigterm = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = sigterm => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn work_is_drained_before_shutdown() {
+        let shutdown = Shutdown::new();
+        let work = shutdown.start_work().unwrap();
+        assert_eq!(shutdown.in_flight(), 1);
+        assert!(!shutdown.drain(Duration::from_millis(10)).await);
+        assert!(shutdown.is_triggered());
+        // New work isn't accepted, the work in flight is completed
+        assert!(shutdown.start_work().is_none());
+        let drain = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.drain(Duration::from_secs(10)).await }
+        });
+        drop(work);
+        assert!(drain.await.unwrap());
+        assert_eq!(shutdown.in_flight(), 0);
+        shutdown.triggered().await;
+    }
+}
@@ -0,0 +1,62 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use ecvrf::secp256k1::SecretKey;
+use std::env;
+
+// Hex encoded 32 bytes key that encrypts keyring.secret_key_enc
+pub const KEK_ENV: &str = "ORAND_KEYRING_KEK";
+
+const NONCE_SIZE: usize = 12;
+
+// Key encryption key, secret keys are stored as hex(nonce || AES-256-GCM ciphertext)
+// The migration that encrypts existing keys uses the same format
+pub struct Kek {
+    cipher: Aes256Gcm,
+}
+
+impl Kek {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+        }
+    }
+
+    // 0x prefix is optional
+    pub fn from_hex(s: &str) -> Result<Self, String> {
+        let key: [u8; 32] = hex::decode(s.strip_prefix("0x").unwrap_or(s))
+            .ok()
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| "KEK must be 32 bytes hex encoded".to_string())?;
+        Ok(Self::new(&key))
+    }
+
+    pub fn from_env() -> Result<Self, String> {
+        let value = env::var(KEK_ENV).map_err(|_| format!("{} is not set", KEK_ENV))?;
+        Self::from_hex(&value).map_err(|e| format!("Invalid {}: {}", KEK_ENV, e))
+    }
+
+    pub fn encrypt(&self, secret_key: &SecretKey) -> String {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, secret_key.serialize().as_ref())
+            .expect("AES-GCM can encrypt 32 bytes");
+        hex::encode([nonce.as_slice(), &ciphertext].concat())
+    }
+
+    // Fails if the value was encrypted under another KEK or was tampered
+    pub fn decrypt(&self, secret_key_enc: &str) -> Result<SecretKey, String> {
+        let bytes = hex::decode(secret_key_enc).map_err(|e| e.to_string())?;
+        if bytes.len() <= NONCE_SIZE {
+            return Err("encrypted secret key is too short".to_string());
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_SIZE);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "can not decrypt secret key, wrong KEK?".to_string())?;
+        SecretKey::parse_slice(&plaintext).map_err(|e| e.to_string())
+    }
+}
@@ -1,22 +1,40 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.10.1
 
+use ecvrf::{
+    helper::parse_public_key,
+    secp256k1::{PublicKey, SecretKey},
+    VrfError,
+};
+use hkdf::Hkdf;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+// Changing the salt or info changes every derived key
+const SEED_SALT: &[u8] = b"orand-keyring-v1";
+const SEED_INFO: &[u8] = b"secp256k1 secret key";
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "keyring")]
 pub struct Model {
     #[sea_orm(primary_key)]
     #[serde(skip_deserializing, skip_serializing)]
-    pub id: u32,
+    pub id: i32,
     pub username: String,
     #[serde(skip_serializing)]
     pub hmac_secret: String,
     pub public_key: String,
+    // Encrypted by sqlite::kek::Kek, use KeyringTable::load_keyring() to read the secret key
     #[serde(skip_serializing)]
-    pub secret_key: String,
+    pub secret_key_enc: String,
     #[serde(skip_deserializing)]
-    pub created_date: String,
+    pub created_date: DateTime,
+    // Ethereum address of public_key, hex encoded without 0x
+    #[serde(default)]
+    pub address: Option<String>,
+    // Key was derived from a seed by Entity::from_seed() and can be recovered from it
+    #[serde(default)]
+    pub derived: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -32,3 +50,27 @@ impl Related<super::randomness::Entity> for Entity {
 }
 
 impl ActiveModelBehavior for ActiveModel {}
+
+impl Entity {
+    // Derive a key pair from a seed with HKDF-SHA256, the same seed always yields the same keys
+    pub fn from_seed(seed: &[u8]) -> (SecretKey, PublicKey) {
+        let hkdf = Hkdf::<Sha256>::new(Some(SEED_SALT), seed);
+        // Output is out of the group order with probability ~2^-128, try the next counter
+        for counter in 0u32.. {
+            let mut okm = [0u8; 32];
+            hkdf.expand_multi_info(&[SEED_INFO, &counter.to_be_bytes()], &mut okm)
+                .expect("32 bytes is a valid HKDF-SHA256 output length");
+            if let Ok(secret_key) = SecretKey::parse(&okm) {
+                return (secret_key, PublicKey::from_secret_key(&secret_key));
+            }
+        }
+        unreachable!("HKDF never yields a valid secret key")
+    }
+}
+
+impl Model {
+    // Decode public_key, it's stored in either compressed or uncompressed form
+    pub fn parse_public_key(&self) -> Result<PublicKey, VrfError> {
+        parse_public_key(&self.public_key)
+    }
+}
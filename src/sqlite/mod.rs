@@ -2,6 +2,14 @@
 
 pub mod prelude;
 
+pub mod database;
+
+pub mod kek;
+
+pub mod api_key;
+pub mod audit_log;
 pub mod keyring;
+pub mod network;
 pub mod randomness;
+pub mod seen_alpha;
 pub mod sqlitedb;
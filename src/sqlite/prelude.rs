@@ -1,4 +1,8 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.10.1
 
+pub use super::api_key::Entity as ApiKey;
+pub use super::audit_log::Entity as AuditLog;
 pub use super::keyring::Entity as Keyring;
+pub use super::network::Entity as Network;
 pub use super::randomness::Entity as Randomness;
+pub use super::seen_alpha::Entity as SeenAlpha;
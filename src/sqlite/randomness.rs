@@ -1,7 +1,18 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.10.1
 
+use crate::ids::{EpochId, NetworkId};
+use ecvrf::{
+    ecproof::ECVRFProof,
+    helper::{affine_composer, scalar_from_b32},
+    secp256k1::{
+        curve::{Field, Scalar},
+        PublicKey,
+    },
+    VrfError, ECVRF,
+};
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "randomness")]
@@ -9,11 +20,14 @@ pub struct Model {
     #[sea_orm(primary_key)]
     #[serde(skip_deserializing, skip_serializing)]
     pub id: i32,
-    pub network: i32,
+    // (network, epoch) is unique
+    pub network: NetworkId,
     #[serde(skip_serializing)]
     pub keyring_id: i32,
-    pub epoch: i32,
+    pub epoch: EpochId,
     pub alpha: String,
+    // Stored as x || y, it's a compressed SEC1 point in JSON, either form is accepted
+    #[serde(with = "compressed_point")]
     pub gamma: String,
     pub c: String,
     pub s: String,
@@ -23,7 +37,141 @@ pub struct Model {
     pub witness_hash: String,
     pub inverse_z: String,
     #[serde(skip_deserializing)]
-    pub created_date: String,
+    pub created_date: DateTime,
+    // ECVRFProof::to_bytes(), it's encoded as hex in JSON
+    #[serde(default, with = "hex_bytes")]
+    pub proof: Option<Vec<u8>>,
+    // Chain height this epoch is anchored to, it's null for older epochs
+    #[serde(default)]
+    pub block_number: Option<i64>,
+    // RecoverableSignature::to_bytes() of the epoch as hex, it's null for older epochs
+    #[serde(default)]
+    pub signature: Option<String>,
+    // Proof of an alpha supplied by a caller, it isn't part of the hash chain and its
+    // epoch is numbered among the caller alphas of the network
+    #[serde(default)]
+    pub caller_alpha: bool,
+}
+
+// Why a stored record isn't a proof of its alpha
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntegrityError {
+    // A column can't be decoded
+    Malformed(VrfError),
+    // gamma, c, s and y don't verify for alpha under the keyring
+    InvalidProof,
+    // Proof blob isn't the proof of the columns
+    ProofMismatch,
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityError::Malformed(e) => write!(f, "malformed record: {}", e),
+            IntegrityError::InvalidProof => write!(f, "proof does not verify"),
+            IntegrityError::ProofMismatch => write!(f, "proof blob does not match the record"),
+        }
+    }
+}
+
+impl std::error::Error for IntegrityError {}
+
+fn decode_b32(hex_str: &str) -> Result<[u8; 32], VrfError> {
+    hex::decode(hex_str)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or(VrfError::InvalidLength)
+}
+
+fn decode_scalar(hex_str: &str) -> Result<Scalar, VrfError> {
+    scalar_from_b32(&decode_b32(hex_str)?)
+}
+
+impl Model {
+    // Check the columns of the record are a proof of its alpha under public_key, the way the
+    // on-chain verifier checks them, so corruption or tampering isn't served to clients
+    pub fn verify_integrity(&self, public_key: &PublicKey) -> Result<(), IntegrityError> {
+        let gamma = hex::decode(&self.gamma)
+            .ok()
+            .and_then(|b| <[u8; 64]>::try_from(b).ok())
+            .ok_or(IntegrityError::Malformed(VrfError::InvalidLength))?;
+        let (mut x, mut y) = (Field::default(), Field::default());
+        if !x.set_b32(gamma[0..32].try_into().expect("32 bytes"))
+            || !y.set_b32(gamma[32..64].try_into().expect("32 bytes"))
+        {
+            return Err(IntegrityError::Malformed(VrfError::InvalidGamma));
+        }
+        let proof = ECVRFProof::new(
+            affine_composer(&x, &y),
+            decode_scalar(&self.c).map_err(IntegrityError::Malformed)?,
+            decode_scalar(&self.s).map_err(IntegrityError::Malformed)?,
+            decode_scalar(&self.y).map_err(IntegrityError::Malformed)?,
+            *public_key,
+        );
+        let alpha = decode_scalar(&self.alpha).map_err(IntegrityError::Malformed)?;
+        match ECVRF::new_verifier(*public_key).verify_contract(&alpha, &proof) {
+            Ok(true) => {}
+            Ok(false) => return Err(IntegrityError::InvalidProof),
+            Err(e) => return Err(IntegrityError::Malformed(e)),
+        }
+        match &self.proof {
+            Some(blob) if *blob != proof.to_bytes() => Err(IntegrityError::ProofMismatch),
+            _ => Ok(()),
+        }
+    }
+}
+
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &Option<Vec<u8>>, s: S) -> Result<S::Ok, S::Error> {
+        match bytes {
+            Some(b) => s.serialize_some(&hex::encode(b)),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Vec<u8>>, D::Error> {
+        Option::<String>::deserialize(d)?
+            .map(|h| hex::decode(h).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+mod compressed_point {
+    use ecvrf::{
+        helper::{affine_composer, affine_from_compressed, affine_to_compressed},
+        secp256k1::curve::Field,
+    };
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    // Points that can't be decoded are kept as they are
+    pub fn serialize<S: Serializer>(point: &str, s: S) -> Result<S::Ok, S::Error> {
+        let xy = hex::decode(point)
+            .ok()
+            .and_then(|b| <[u8; 64]>::try_from(b).ok());
+        let affine = xy.and_then(|xy| {
+            let (mut x, mut y) = (Field::default(), Field::default());
+            let x_valid = x.set_b32(xy[0..32].try_into().expect("32 bytes"));
+            let y_valid = y.set_b32(xy[32..64].try_into().expect("32 bytes"));
+            (x_valid && y_valid).then(|| affine_composer(&x, &y))
+        });
+        match affine {
+            Some(affine) => s.serialize_str(&hex::encode(affine_to_compressed(&affine))),
+            None => s.serialize_str(point),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<String, D::Error> {
+        let point = String::deserialize(d)?;
+        match hex::decode(&point) {
+            Ok(b) if b.len() == 33 => {
+                let point = affine_from_compressed(&b).map_err(serde::de::Error::custom)?;
+                Ok(hex::encode([point.x.b32(), point.y.b32()].concat()))
+            }
+            _ => Ok(point),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -36,6 +184,14 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     Keyring,
+    #[sea_orm(
+        belongs_to = "super::network::Entity",
+        from = "Column::Network",
+        to = "super::network::Column::ChainId",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    Network,
 }
 
 impl Related<super::keyring::Entity> for Entity {
@@ -44,4 +200,10 @@ impl Related<super::keyring::Entity> for Entity {
     }
 }
 
+impl Related<super::network::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Network.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -1,29 +1,94 @@
 use super::{
+    api_key::{ActiveModel as AModelApiKey, Entity as ApiKey, Model as ModelApiKey},
+    audit_log::{
+        ActiveModel as AModelAuditLog, Column as ColumnAuditLog, Entity as AuditLog,
+        Model as ModelAuditLog,
+    },
     keyring::{
         ActiveModel as AModelKeyring, Column as ColumnKeyring, Entity as Keyring,
         Model as ModelKeyring,
     },
+    network::{
+        ActiveModel as AModelNetwork, Column as ColumnNetwork, Entity as Network,
+        Model as ModelNetwork,
+    },
     randomness::{
         ActiveModel as AModelRandomness, Column as ColumnRandomness, Entity as Randomness,
         Model as ModelRandomness,
     },
+    seen_alpha::{ActiveModel as AModelSeenAlpha, Column as ColumnSeenAlpha, Entity as SeenAlpha},
 };
 
+use super::database::{Database, DbConfig};
+use super::kek::{Kek, KEK_ENV};
+use crate::ids::{EpochId, NetworkId};
+use ecvrf::secp256k1::SecretKey;
+use futures_util::Stream;
 use sea_orm::{
-    sea_query::Query, ActiveModelTrait, ColumnTrait, Condition, Database, DatabaseConnection,
-    DbErr, EntityTrait, InsertResult, QueryFilter,
+    prelude::DateTime, sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait,
+    DatabaseConnection, DbErr, EntityTrait, FromQueryResult, InsertResult, QueryFilter, QueryOrder,
+    QuerySelect, Select, Set, Statement, TransactionTrait, Unchanged,
 };
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use tracing::instrument;
+
+// Whether the error was caused by a unique index, e.g. a second record of the same (network, epoch)
+pub fn is_unique_violation(err: &DbErr) -> bool {
+    let message = err.to_string();
+    // SQLite and PostgreSQL
+    message.contains("UNIQUE constraint failed")
+        || message.contains("duplicate key value violates unique constraint")
+}
 
 pub struct KeyringTable<'a> {
     connection: &'a DatabaseConnection,
+    kek: Option<&'a Kek>,
 }
 
 impl<'a> KeyringTable<'a> {
-    pub async fn new(conn: &'a DatabaseConnection) -> KeyringTable {
-        Self { connection: conn }
+    pub async fn new(conn: &'a DatabaseConnection, kek: Option<&'a Kek>) -> KeyringTable<'a> {
+        Self {
+            connection: conn,
+            kek,
+        }
+    }
+
+    fn kek(&self) -> Result<&'a Kek, DbErr> {
+        self.kek
+            .ok_or_else(|| DbErr::Custom(format!("{} is required to access secret keys", KEK_ENV)))
     }
 
-    pub async fn find_by_id(&self, id: u32) -> Result<Option<ModelKeyring>, DbErr> {
+    // Insert a keyring, its secret key is encrypted under the KEK
+    #[instrument(level = "debug", skip_all, err)]
+    pub async fn store_keyring(
+        &self,
+        mut json_record: serde_json::Value,
+        secret_key: &SecretKey,
+    ) -> Result<ModelKeyring, DbErr> {
+        json_record["secret_key_enc"] = self.kek()?.encrypt(secret_key).into();
+        self.insert_returning(json_record).await
+    }
+
+    // Find a keyring by name and decrypt its secret key
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn load_keyring(
+        &self,
+        name: &str,
+    ) -> Result<Option<(ModelKeyring, SecretKey)>, DbErr> {
+        let kek = self.kek()?;
+        match self.find_by_name(name.to_string()).await? {
+            Some(record) => {
+                let secret_key = kek
+                    .decrypt(&record.secret_key_enc)
+                    .map_err(|e| DbErr::Custom(format!("Keyring {}: {}", record.username, e)))?;
+                Ok(Some((record, secret_key)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn find_by_id(&self, id: i32) -> Result<Option<ModelKeyring>, DbErr> {
         Keyring::find_by_id(id).one(self.connection).await
     }
 
@@ -34,6 +99,29 @@ impl<'a> KeyringTable<'a> {
             .await
     }
 
+    // Find key by its Ethereum address, 0x prefix is optional
+    pub async fn find_by_address(&self, address: &str) -> Result<Option<ModelKeyring>, DbErr> {
+        let address = address.strip_prefix("0x").unwrap_or(address).to_lowercase();
+        Keyring::find()
+            .filter(ColumnKeyring::Address.eq(address))
+            .one(self.connection)
+            .await
+    }
+
+    // Keyring that signs the epochs of a network, None if either of them doesn't exist
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn find_keyring_for_network(
+        &self,
+        network: NetworkId,
+    ) -> Result<Option<ModelKeyring>, DbErr> {
+        Ok(Network::find()
+            .filter(ColumnNetwork::ChainId.eq(network))
+            .find_also_related(Keyring)
+            .one(self.connection)
+            .await?
+            .and_then(|(_, keyring)| keyring))
+    }
+
     // Get all keys in keyring table
     pub async fn find_all(&self) -> Result<Vec<ModelKeyring>, DbErr> {
         Keyring::find().all(self.connection).await
@@ -60,36 +148,313 @@ impl<'a> KeyringTable<'a> {
     }
 }
 
+// API keys are stored as their SHA-256, hex encoded
+pub fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+pub struct ApiKeyTable<'a> {
+    connection: &'a DatabaseConnection,
+}
+
+impl<'a> ApiKeyTable<'a> {
+    pub async fn new(conn: &'a DatabaseConnection) -> ApiKeyTable<'a> {
+        Self { connection: conn }
+    }
+
+    // Find the record of a raw key, every hash is compared in constant time
+    // The raw key is a secret, it's kept out of the span
+    #[instrument(level = "debug", skip_all, err)]
+    pub async fn find_by_key(&self, key: &str) -> Result<Option<ModelApiKey>, DbErr> {
+        let key_hash = hash_api_key(key);
+        let mut found = None;
+        for record in ApiKey::find().all(self.connection).await? {
+            if bool::from(record.key_hash.as_bytes().ct_eq(key_hash.as_bytes())) {
+                found = Some(record);
+            }
+        }
+        Ok(found)
+    }
+
+    // Store the hash of a new raw key under the given name
+    pub async fn insert(&self, name: String, key: &str) -> Result<ModelApiKey, DbErr> {
+        let new_record = AModelApiKey {
+            name: Set(name),
+            key_hash: Set(hash_api_key(key)),
+            ..Default::default()
+        };
+        ApiKey::insert(new_record)
+            .exec_with_returning(self.connection)
+            .await
+    }
+}
+
+// Records of the hash chain, proofs of caller alphas are left out
+fn epochs() -> Select<Randomness> {
+    Randomness::find().filter(ColumnRandomness::CallerAlpha.eq(false))
+}
+
 pub struct RandomnessTable<'a> {
     connection: &'a DatabaseConnection,
 }
 
 impl<'a> RandomnessTable<'a> {
-    pub async fn new(conn: &'a DatabaseConnection) -> RandomnessTable {
+    pub async fn new(conn: &'a DatabaseConnection) -> RandomnessTable<'a> {
         Self { connection: conn }
     }
 
-    pub async fn find_recent_epoch(&self, epoch: u32) -> Result<Vec<ModelRandomness>, DbErr> {
-        Randomness::find()
+    pub async fn find_recent_epoch(&self, epoch: EpochId) -> Result<Vec<ModelRandomness>, DbErr> {
+        epochs()
             .filter(ColumnRandomness::Epoch.gte(epoch))
             .all(self.connection)
             .await
     }
 
-    pub async fn find_latest_epoch(&self, network: u32) -> Result<Option<ModelRandomness>, DbErr> {
+    // Epoch of a network, None if it has not been created or was pruned
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn find_epoch(
+        &self,
+        network: NetworkId,
+        epoch: EpochId,
+    ) -> Result<Option<ModelRandomness>, DbErr> {
+        epochs()
+            .filter(ColumnRandomness::Network.eq(network))
+            .filter(ColumnRandomness::Epoch.eq(epoch))
+            .one(self.connection)
+            .await
+    }
+
+    // Epoch of a network that was proven for the given hex encoded alpha
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn find_by_network_alpha(
+        &self,
+        network: NetworkId,
+        alpha: &str,
+    ) -> Result<Option<ModelRandomness>, DbErr> {
+        epochs()
+            .filter(ColumnRandomness::Network.eq(network))
+            .filter(ColumnRandomness::Alpha.eq(alpha))
+            .one(self.connection)
+            .await
+    }
+
+    // Epochs of a network in [from_epoch, to_epoch], ordered by epoch
+    pub async fn find_epoch_range(
+        &self,
+        network: NetworkId,
+        from_epoch: EpochId,
+        to_epoch: EpochId,
+    ) -> Result<Vec<ModelRandomness>, DbErr> {
+        epochs()
+            .filter(ColumnRandomness::Network.eq(network))
+            .filter(ColumnRandomness::Epoch.between(from_epoch, to_epoch))
+            .order_by_asc(ColumnRandomness::Epoch)
+            .all(self.connection)
+            .await
+    }
+
+    // Every epoch of a network ordered by epoch, rows are fetched while the stream is read
+    pub async fn stream_epochs(
+        &self,
+        network: NetworkId,
+    ) -> Result<impl Stream<Item = Result<ModelRandomness, DbErr>> + Send + 'a, DbErr> {
+        epochs()
+            .filter(ColumnRandomness::Network.eq(network))
+            .order_by_asc(ColumnRandomness::Epoch)
+            .stream(self.connection)
+            .await
+    }
+
+    // Records of a network with the given hex encoded y, proofs of caller alphas included
+    // y is unique, the result has more than one record only if the index is missing
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn find_by_y(
+        &self,
+        network: NetworkId,
+        y: &str,
+    ) -> Result<Vec<ModelRandomness>, DbErr> {
         Randomness::find()
             .filter(ColumnRandomness::Network.eq(network))
+            .filter(ColumnRandomness::Y.eq(y))
+            .order_by_asc(ColumnRandomness::Id)
+            .all(self.connection)
+            .await
+    }
+
+    // Epochs of a network created in [from, to), both are UTC, ordered by created_date
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn find_by_date_range(
+        &self,
+        network: NetworkId,
+        from: DateTime,
+        to: DateTime,
+    ) -> Result<Vec<ModelRandomness>, DbErr> {
+        epochs()
+            .filter(ColumnRandomness::Network.eq(network))
+            .filter(ColumnRandomness::CreatedDate.gte(from))
+            .filter(ColumnRandomness::CreatedDate.lt(to))
+            .order_by_asc(ColumnRandomness::CreatedDate)
+            .order_by_asc(ColumnRandomness::Epoch)
+            .all(self.connection)
+            .await
+    }
+
+    // Page of at most limit epochs of a network from from_epoch, after the record with id
+    // after_id, with the id to continue from if there are more
+    // Pages are ordered by id so iteration is stable while new epochs are created
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn find_epoch_page(
+        &self,
+        network: NetworkId,
+        from_epoch: EpochId,
+        to_epoch: Option<EpochId>,
+        after_id: Option<i32>,
+        limit: u64,
+    ) -> Result<(Vec<ModelRandomness>, Option<i32>), DbErr> {
+        let mut query = epochs()
+            .filter(ColumnRandomness::Network.eq(network))
+            .filter(ColumnRandomness::Epoch.gte(from_epoch));
+        if let Some(to_epoch) = to_epoch {
+            query = query.filter(ColumnRandomness::Epoch.lte(to_epoch));
+        }
+        if let Some(after_id) = after_id {
+            query = query.filter(ColumnRandomness::Id.gt(after_id));
+        }
+        // One more record tells whether there is a next page
+        let mut page = query
+            .order_by_asc(ColumnRandomness::Id)
+            .limit(limit + 1)
+            .all(self.connection)
+            .await?;
+        if page.len() as u64 > limit {
+            page.truncate(limit as usize);
+            let last = page.last().map(|record| record.id);
+            return Ok((page, last));
+        }
+        Ok((page, None))
+    }
+
+    // Delete all but the latest keep_last_n epochs of a network, return number of deleted rows
+    // The chain tip and the genesis (first epoch of the network) are always kept
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn prune_randomness(
+        &self,
+        network: NetworkId,
+        keep_last_n: u64,
+    ) -> Result<u64, DbErr> {
+        if keep_last_n == 0 {
+            return Err(DbErr::Custom(
+                "keep_last_n must keep at least the chain tip".to_string(),
+            ));
+        }
+        // Tip and genesis are read in the transaction of the delete, an epoch that is created
+        // meanwhile can't move the cutoff
+        let txn = self.connection.begin().await?;
+        let network_epochs = || epochs().filter(ColumnRandomness::Network.eq(network));
+        let latest = network_epochs()
+            .order_by_desc(ColumnRandomness::Epoch)
+            .one(&txn)
+            .await?;
+        let genesis = network_epochs()
+            .order_by_asc(ColumnRandomness::Epoch)
+            .one(&txn)
+            .await?;
+        let (latest, genesis) = match (latest, genesis) {
+            (Some(latest), Some(genesis)) => (latest, genesis),
+            _ => return Ok(0),
+        };
+        let cutoff = latest.epoch.0.saturating_sub(keep_last_n as i64 - 1);
+        let result = Randomness::delete_many()
+            .filter(ColumnRandomness::Network.eq(network))
+            .filter(ColumnRandomness::Epoch.lt(cutoff))
+            .filter(ColumnRandomness::Epoch.ne(genesis.epoch))
+            .filter(ColumnRandomness::CallerAlpha.eq(false))
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+        Ok(result.rows_affected)
+    }
+
+    // Latest epoch of every network that has one, ordered by network
+    pub async fn find_latest_epochs(&self) -> Result<Vec<ModelRandomness>, DbErr> {
+        #[derive(FromQueryResult)]
+        struct NetworkTip {
+            network: NetworkId,
+            epoch: EpochId,
+        }
+        let tips = epochs()
+            .select_only()
+            .column(ColumnRandomness::Network)
+            .column_as(ColumnRandomness::Epoch.max(), "epoch")
+            .group_by(ColumnRandomness::Network)
+            .order_by_asc(ColumnRandomness::Network)
+            .into_model::<NetworkTip>()
+            .all(self.connection)
+            .await?;
+        let mut records = Vec::with_capacity(tips.len());
+        for tip in tips {
+            if let Some(record) = self.find_epoch(tip.network, tip.epoch).await? {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    // Proof of an alpha that was supplied by a caller
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn find_caller_alpha(
+        &self,
+        network: NetworkId,
+        alpha: &str,
+    ) -> Result<Option<ModelRandomness>, DbErr> {
+        Randomness::find()
+            .filter(ColumnRandomness::CallerAlpha.eq(true))
+            .filter(ColumnRandomness::Network.eq(network))
+            .filter(ColumnRandomness::Alpha.eq(alpha))
+            .one(self.connection)
+            .await
+    }
+
+    // Caller alpha of the network with the highest index
+    pub async fn find_latest_caller_alpha(
+        &self,
+        network: NetworkId,
+    ) -> Result<Option<ModelRandomness>, DbErr> {
+        Randomness::find()
+            .filter(ColumnRandomness::CallerAlpha.eq(true))
+            .filter(ColumnRandomness::Network.eq(network))
+            .order_by_desc(ColumnRandomness::Epoch)
+            .one(self.connection)
+            .await
+    }
+
+    // Whether a caller can't prove the given alpha: it was proven by any network, or it's
+    // y of an epoch, i.e. the alpha of the next epoch
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn is_alpha_taken(&self, alpha: &str) -> Result<bool, DbErr> {
+        Ok(Randomness::find()
             .filter(
-                Condition::any().add(
-                    ColumnRandomness::Epoch.in_subquery(
-                        Query::select()
-                            .expr(ColumnRandomness::Epoch.max())
-                            .from(Randomness)
-                            .to_owned(),
-                    ),
+                Condition::any().add(ColumnRandomness::Alpha.eq(alpha)).add(
+                    Condition::all()
+                        .add(ColumnRandomness::CallerAlpha.eq(false))
+                        .add(ColumnRandomness::Y.eq(alpha)),
                 ),
             )
             .one(self.connection)
+            .await?
+            .is_some())
+    }
+
+    // Epoch with the highest index of the given network
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn find_latest_epoch(
+        &self,
+        network: NetworkId,
+    ) -> Result<Option<ModelRandomness>, DbErr> {
+        epochs()
+            .filter(ColumnRandomness::Network.eq(network))
+            .order_by_desc(ColumnRandomness::Epoch)
+            .one(self.connection)
             .await
     }
 
@@ -110,27 +475,992 @@ impl<'a> RandomnessTable<'a> {
             .exec_with_returning(self.connection)
             .await
     }
+
+    // Latest epoch of a network that was created by the given request of the given API key,
+    // it's found by its audit record
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn find_by_request(
+        &self,
+        network: NetworkId,
+        actor: Option<i32>,
+        request_hash: &str,
+    ) -> Result<Option<ModelRandomness>, DbErr> {
+        let actor = match actor {
+            Some(actor) => ColumnAuditLog::Actor.eq(actor),
+            None => ColumnAuditLog::Actor.is_null(),
+        };
+        let audit_record = AuditLog::find()
+            .filter(ColumnAuditLog::Network.eq(network))
+            .filter(ColumnAuditLog::RequestHash.eq(request_hash))
+            .filter(actor)
+            .order_by_desc(ColumnAuditLog::Epoch)
+            .one(self.connection)
+            .await?;
+        match audit_record {
+            Some(audit_record) => self.find_epoch(network, audit_record.epoch).await,
+            None => Ok(None),
+        }
+    }
+
+    // Insert an epoch and its audit record in one transaction, neither is stored on error
+    #[instrument(level = "debug", skip(self, json_record), err)]
+    pub async fn insert_audited(
+        &self,
+        json_record: serde_json::Value,
+        actor: Option<i32>,
+        request_hash: Option<String>,
+    ) -> Result<ModelRandomness, DbErr> {
+        let new_record = AModelRandomness::from_json(json_record)?;
+        let txn = self.connection.begin().await?;
+        let record = Randomness::insert(new_record)
+            .exec_with_returning(&txn)
+            .await?;
+        let audit_record = AModelAuditLog {
+            network: Set(record.network),
+            epoch: Set(record.epoch),
+            actor: Set(actor),
+            request_hash: Set(request_hash),
+            ..Default::default()
+        };
+        AuditLog::insert(audit_record).exec(&txn).await?;
+        txn.commit().await?;
+        Ok(record)
+    }
+
+    // Overwrite the columns of a record with the given ones and audit the change in one
+    // transaction, columns that aren't in json_record are kept
+    #[instrument(level = "debug", skip(self, json_record), err)]
+    pub async fn update_audited(
+        &self,
+        id: i32,
+        json_record: serde_json::Value,
+        actor: Option<i32>,
+        request_hash: Option<String>,
+    ) -> Result<ModelRandomness, DbErr> {
+        let mut record = AModelRandomness::from_json(json_record)?;
+        record.id = Unchanged(id);
+        let txn = self.connection.begin().await?;
+        let record = record.update(&txn).await?;
+        let audit_record = AModelAuditLog {
+            network: Set(record.network),
+            epoch: Set(record.epoch),
+            actor: Set(actor),
+            request_hash: Set(request_hash),
+            ..Default::default()
+        };
+        AuditLog::insert(audit_record).exec(&txn).await?;
+        txn.commit().await?;
+        Ok(record)
+    }
+}
+
+pub struct AuditLogTable<'a> {
+    connection: &'a DatabaseConnection,
+}
+
+impl<'a> AuditLogTable<'a> {
+    pub async fn new(conn: &'a DatabaseConnection) -> AuditLogTable<'a> {
+        Self { connection: conn }
+    }
+
+    // Audit records of the epochs of a network in [from_epoch, to_epoch], ordered by epoch
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn find_epoch_range(
+        &self,
+        network: NetworkId,
+        from_epoch: EpochId,
+        to_epoch: EpochId,
+    ) -> Result<Vec<ModelAuditLog>, DbErr> {
+        AuditLog::find()
+            .filter(ColumnAuditLog::Network.eq(network))
+            .filter(ColumnAuditLog::Epoch.between(from_epoch, to_epoch))
+            .order_by_asc(ColumnAuditLog::Epoch)
+            .all(self.connection)
+            .await
+    }
+}
+
+pub struct SeenAlphaTable<'a> {
+    connection: &'a DatabaseConnection,
+}
+
+impl<'a> SeenAlphaTable<'a> {
+    pub async fn new(conn: &'a DatabaseConnection) -> SeenAlphaTable<'a> {
+        Self { connection: conn }
+    }
+
+    // Record an alpha of a network seen at now, unix time in seconds, alphas seen more than
+    // window_secs ago are pruned first
+    // False if the alpha was already seen within the window
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn record(
+        &self,
+        network: NetworkId,
+        alpha: &str,
+        now: i64,
+        window_secs: i64,
+    ) -> Result<bool, DbErr> {
+        self.prune(now - window_secs).await?;
+        let seen = AModelSeenAlpha {
+            network: Set(network),
+            alpha: Set(alpha.to_string()),
+            seen_at: Set(now),
+            ..Default::default()
+        };
+        match SeenAlpha::insert(seen).exec(self.connection).await {
+            Ok(_) => Ok(true),
+            Err(e) if is_unique_violation(&e) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Delete alphas seen at or before the given unix time, returns how many were deleted
+    pub async fn prune(&self, until: i64) -> Result<u64, DbErr> {
+        Ok(SeenAlpha::delete_many()
+            .filter(ColumnSeenAlpha::SeenAt.lte(until))
+            .exec(self.connection)
+            .await?
+            .rows_affected)
+    }
+}
+
+pub struct NetworkTable<'a> {
+    connection: &'a DatabaseConnection,
+}
+
+impl<'a> NetworkTable<'a> {
+    pub async fn new(conn: &'a DatabaseConnection) -> NetworkTable<'a> {
+        Self { connection: conn }
+    }
+
+    pub async fn find_by_chain_id(
+        &self,
+        chain_id: NetworkId,
+    ) -> Result<Option<ModelNetwork>, DbErr> {
+        Network::find()
+            .filter(ColumnNetwork::ChainId.eq(chain_id))
+            .one(self.connection)
+            .await
+    }
+
+    // Networks the scheduler creates epochs for, ordered by chain id
+    pub async fn find_active(&self) -> Result<Vec<ModelNetwork>, DbErr> {
+        Network::find()
+            .filter(ColumnNetwork::Active.eq(true))
+            .order_by_asc(ColumnNetwork::ChainId)
+            .all(self.connection)
+            .await
+    }
+
+    // Get all networks in network table
+    pub async fn find_all(&self) -> Result<Vec<ModelNetwork>, DbErr> {
+        Network::find()
+            .order_by_asc(ColumnNetwork::ChainId)
+            .all(self.connection)
+            .await
+    }
+
+    pub async fn insert_returning(
+        &self,
+        json_record: serde_json::Value,
+    ) -> Result<ModelNetwork, DbErr> {
+        let new_record = AModelNetwork::from_json(json_record)?;
+        Network::insert(new_record)
+            .exec_with_returning(self.connection)
+            .await
+    }
+
+    // Skip or resume a network in the scheduler, return whether it exists
+    pub async fn set_active(&self, chain_id: NetworkId, active: bool) -> Result<bool, DbErr> {
+        let result = Network::update_many()
+            .col_expr(ColumnNetwork::Active, Expr::value(active))
+            .filter(ColumnNetwork::ChainId.eq(chain_id))
+            .exec(self.connection)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
 }
 
 pub struct SqliteDB {
     connection: DatabaseConnection,
+    kek: Option<Kek>,
 }
 
 impl SqliteDB {
-    // Create a new instance of SQLite database
+    // Create a new instance of the database, backend is chosen by the URL scheme
+    // the name is kept from the time SQLite was the only backend
     pub async fn new(database_url: String) -> Self {
         Self {
-            connection: Database::connect(database_url)
+            connection: Database::connect(&database_url)
                 .await
                 .expect("Can not connect to database"),
+            kek: None,
         }
     }
 
-    pub async fn table_randomness(&self) -> RandomnessTable {
+    // Create a new instance of the database with the given pool configuration
+    pub async fn with_config(database_url: String, config: &DbConfig) -> Self {
+        Self {
+            connection: Database::connect_with(&database_url, config)
+                .await
+                .expect("Can not connect to database"),
+            kek: None,
+        }
+    }
+
+    // Key encryption key of the keyring table, secret keys can't be read or stored without it
+    pub fn with_kek(mut self, kek: Kek) -> Self {
+        self.kek = Some(kek);
+        self
+    }
+
+    // Underlying connection, e.g. to run migrations
+    pub fn connection(&self) -> &DatabaseConnection {
+        &self.connection
+    }
+
+    // Cheap query to check that the database is reachable
+    pub async fn ping(&self) -> Result<(), DbErr> {
+        let backend = self.connection.get_database_backend();
+        self.connection
+            .execute(Statement::from_string(backend, "SELECT 1".to_string()))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn table_randomness(&self) -> RandomnessTable<'_> {
         RandomnessTable::new(&self.connection).await
     }
 
-    pub async fn table_keyring(&self) -> KeyringTable {
-        KeyringTable::new(&self.connection).await
+    pub async fn table_keyring(&self) -> KeyringTable<'_> {
+        KeyringTable::new(&self.connection, self.kek.as_ref()).await
+    }
+
+    pub async fn table_api_key(&self) -> ApiKeyTable<'_> {
+        ApiKeyTable::new(&self.connection).await
+    }
+
+    pub async fn table_audit_log(&self) -> AuditLogTable<'_> {
+        AuditLogTable::new(&self.connection).await
+    }
+
+    pub async fn table_network(&self) -> NetworkTable<'_> {
+        NetworkTable::new(&self.connection).await
+    }
+
+    pub async fn table_seen_alpha(&self) -> SeenAlphaTable<'_> {
+        SeenAlphaTable::new(&self.connection).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ecvrf::{
+        ecproof::ECVRFProof,
+        helper::{
+            affine_from_compressed, affine_to_compressed, get_address, public_key_to_hex, randomize,
+        },
+        random::thread_rng,
+        secp256k1::{PublicKey, SecretKey},
+        ECVRF,
+    };
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{ConnectionTrait, DbBackend, Statement};
+    use serde_json::json;
+
+    async fn memory_db() -> SqliteDB {
+        let sqlite = SqliteDB::new("sqlite::memory:".to_string()).await;
+        Migrator::up(&sqlite.connection, None).await.unwrap();
+        sqlite
+            .table_keyring()
+            .await
+            .insert(json!({
+                "username": "chiro",
+                "hmac_secret": "00",
+                "public_key": "00",
+                "secret_key_enc": "00",
+            }))
+            .await
+            .unwrap();
+        for chain_id in [1, 56, 57] {
+            insert_network(&sqlite, chain_id).await;
+        }
+        sqlite
+    }
+
+    // Every epoch belongs to a registered network
+    async fn insert_network(sqlite: &SqliteDB, chain_id: i64) -> ModelNetwork {
+        sqlite
+            .table_network()
+            .await
+            .insert_returning(json!({
+                "name": format!("chain-{}", chain_id),
+                "chain_id": chain_id,
+                "epoch_interval_secs": 60,
+                "keyring_id": 1,
+            }))
+            .await
+            .unwrap()
+    }
+
+    // Keyring of a schema older than the entity, its secret key is never read
+    async fn insert_legacy_keyring(sqlite: &SqliteDB, public_key: &PublicKey) {
+        sqlite
+            .connection
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                "INSERT INTO keyring (username, hmac_secret, public_key, secret_key) \
+                 VALUES (?, ?, ?, ?)",
+                vec![
+                    "chiro".into(),
+                    "00".into(),
+                    hex::encode(public_key.serialize()).into(),
+                    "00".into(),
+                ],
+            ))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn secret_key_is_encrypted_at_rest() {
+        let sqlite = memory_db().await.with_kek(Kek::new(&[1u8; 32]));
+        let secret_key = SecretKey::random(&mut thread_rng());
+        let public_key = PublicKey::from_secret_key(&secret_key);
+        let keyring = sqlite.table_keyring().await;
+        let stored = keyring
+            .store_keyring(
+                json!({
+                    "username": "oracle",
+                    "hmac_secret": "00",
+                    "public_key": hex::encode(public_key.serialize()),
+                }),
+                &secret_key,
+            )
+            .await
+            .unwrap();
+        assert!(!stored
+            .secret_key_enc
+            .contains(&hex::encode(secret_key.serialize())));
+
+        let (record, loaded) = keyring.load_keyring("oracle").await.unwrap().unwrap();
+        assert_eq!(record.id, stored.id);
+        assert_eq!(loaded.serialize(), secret_key.serialize());
+        assert!(keyring.load_keyring("missing").await.unwrap().is_none());
+
+        // Same rows under another KEK can't be decrypted
+        let wrong_kek = Kek::new(&[2u8; 32]);
+        let wrong = KeyringTable::new(&sqlite.connection, Some(&wrong_kek)).await;
+        assert!(wrong.load_keyring("oracle").await.is_err());
+        let without_kek = KeyringTable::new(&sqlite.connection, None).await;
+        assert!(without_kek.load_keyring("oracle").await.is_err());
+    }
+
+    fn epoch_record(network: i64, epoch: i64) -> serde_json::Value {
+        // alpha and y have their own unique index
+        let tag = format!("{:02x}{:08x}", network, epoch);
+        json!({
+            "network": network,
+            "keyring_id": 1,
+            "epoch": epoch,
+            "alpha": format!("a{}", tag),
+            "gamma": "00",
+            "c": "00",
+            "s": "00",
+            "y": format!("f{}", tag),
+            "witness_address": "00",
+            "witness_gamma": "00",
+            "witness_hash": "00",
+            "inverse_z": "00",
+        })
+    }
+
+    #[tokio::test]
+    async fn epoch_pages_are_stable_while_epochs_are_created() {
+        let sqlite = memory_db().await;
+        let randomness = sqlite.table_randomness().await;
+        for epoch in 0..250 {
+            randomness.insert(epoch_record(56, epoch)).await.unwrap();
+            randomness.insert(epoch_record(57, epoch)).await.unwrap();
+        }
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let (page, next) = randomness
+                .find_epoch_page(NetworkId(56), EpochId(0), Some(EpochId(249)), cursor, 100)
+                .await
+                .unwrap();
+            seen.push(page.len());
+            assert!(page.iter().all(|record| record.network == 56));
+            assert_eq!(
+                page.first().map(|record| record.epoch),
+                Some(EpochId(seen.len() as i64 * 100 - 100))
+            );
+            // Epochs created while paging come after the range
+            randomness
+                .insert(epoch_record(56, 250 + seen.len() as i64))
+                .await
+                .unwrap();
+            match next {
+                Some(id) => cursor = Some(id),
+                None => break,
+            }
+        }
+        assert_eq!(seen, vec![100, 100, 50]);
+
+        // Without the upper bound new epochs are paged through too
+        let (page, next) = randomness
+            .find_epoch_page(NetworkId(56), EpochId(200), None, None, 100)
+            .await
+            .unwrap();
+        assert_eq!((page.len(), next), (53, None));
+    }
+
+    #[tokio::test]
+    async fn epochs_are_found_by_date_range() {
+        let sqlite = memory_db().await;
+        let randomness = sqlite.table_randomness().await;
+        let date = |s: &str| DateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+        // Epoch 3 is created before epoch 2, e.g. by a clock that was set back
+        for (network, epoch, created_date) in [
+            (56, 0, "2022-12-31 23:59:59"),
+            (56, 1, "2023-01-01 00:00:00"),
+            (56, 2, "2023-01-03 12:00:00"),
+            (56, 3, "2023-01-02 00:00:00"),
+            (56, 4, "2023-01-08 00:00:00"),
+            (57, 0, "2023-01-02 00:00:00"),
+        ] {
+            randomness
+                .insert(epoch_record(network, epoch))
+                .await
+                .unwrap();
+            sqlite
+                .connection
+                .execute(Statement::from_sql_and_values(
+                    DbBackend::Sqlite,
+                    "UPDATE randomness SET created_date = ? WHERE network = ? AND epoch = ?",
+                    vec![date(created_date).into(), network.into(), epoch.into()],
+                ))
+                .await
+                .unwrap();
+        }
+
+        // from is inclusive, to is exclusive
+        let week = randomness
+            .find_by_date_range(
+                NetworkId(56),
+                date("2023-01-01 00:00:00"),
+                date("2023-01-08 00:00:00"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            week.iter().map(|record| record.epoch).collect::<Vec<_>>(),
+            vec![1, 3, 2]
+        );
+        assert!(randomness
+            .find_by_date_range(
+                NetworkId(56),
+                date("2023-01-09 00:00:00"),
+                date("2023-01-10 00:00:00")
+            )
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn network_epoch_is_unique() {
+        let sqlite = memory_db().await;
+        let randomness = sqlite.table_randomness().await;
+        randomness.insert(epoch_record(56, 0)).await.unwrap();
+        // Same epoch on another network is fine
+        randomness.insert(epoch_record(1, 0)).await.unwrap();
+
+        let mut duplicate = epoch_record(56, 0);
+        duplicate["alpha"] = json!("other alpha");
+        duplicate["y"] = json!("other y");
+        let err = randomness.insert(duplicate).await.unwrap_err();
+        assert!(is_unique_violation(&err));
+    }
+
+    #[tokio::test]
+    async fn failed_audit_rolls_back_the_epoch() {
+        let sqlite = memory_db().await;
+        // Fail the second insert of the transaction
+        sqlite
+            .connection
+            .execute(Statement::from_string(
+                DbBackend::Sqlite,
+                "CREATE TRIGGER fail_audit BEFORE INSERT ON audit_log \
+                 BEGIN SELECT RAISE(ABORT, 'injected failure'); END"
+                    .to_string(),
+            ))
+            .await
+            .unwrap();
+        let randomness = sqlite.table_randomness().await;
+        let err = randomness
+            .insert_audited(epoch_record(56, 0), Some(1), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("injected failure"));
+        assert!(randomness
+            .find_latest_epoch(NetworkId(56))
+            .await
+            .unwrap()
+            .is_none());
+
+        // The same epoch can be created once the failure is gone
+        sqlite
+            .connection
+            .execute(Statement::from_string(
+                DbBackend::Sqlite,
+                "DROP TRIGGER fail_audit".to_string(),
+            ))
+            .await
+            .unwrap();
+        let record = randomness
+            .insert_audited(epoch_record(56, 0), Some(1), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            sqlite
+                .table_audit_log()
+                .await
+                .find_epoch_range(NetworkId(56), EpochId(0), EpochId(0))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(record.epoch, 0);
+    }
+
+    #[tokio::test]
+    async fn epoch_lookups_use_index() {
+        let sqlite = memory_db().await;
+        for query in [
+            "SELECT * FROM randomness WHERE network = 56 AND epoch = 1",
+            "SELECT * FROM randomness WHERE network = 56 ORDER BY epoch DESC LIMIT 1",
+        ] {
+            let plan = sqlite
+                .connection
+                .query_all(Statement::from_string(
+                    DbBackend::Sqlite,
+                    format!("EXPLAIN QUERY PLAN {}", query),
+                ))
+                .await
+                .unwrap()
+                .iter()
+                .map(|row| row.try_get::<String>("", "detail").unwrap())
+                .collect::<Vec<String>>();
+            assert!(
+                plan.iter().any(|detail| detail.contains("USING INDEX")),
+                "{} scans the table: {:?}",
+                query,
+                plan
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn proof_blob_is_round_tripped() {
+        let sqlite = memory_db().await;
+        let randomness = sqlite.table_randomness().await;
+        let mut record = epoch_record(56, 0);
+        record["proof"] = json!("00ff");
+        record["block_number"] = json!(17_000_000);
+        randomness.insert(record).await.unwrap();
+        randomness.insert(epoch_record(56, 1)).await.unwrap();
+
+        let epochs = randomness
+            .find_epoch_range(NetworkId(56), EpochId(0), EpochId(1))
+            .await
+            .unwrap();
+        assert_eq!(epochs[0].proof, Some(vec![0x00, 0xff]));
+        assert_eq!(epochs[1].proof, None);
+        assert_eq!(epochs[0].block_number, Some(17_000_000));
+        assert_eq!(epochs[1].block_number, None);
+        assert_eq!(serde_json::to_value(&epochs[0]).unwrap()["proof"], "00ff");
+    }
+
+    #[tokio::test]
+    async fn gamma_is_compressed_in_json() {
+        let sqlite = memory_db().await;
+        let randomness = sqlite.table_randomness().await;
+        let secret_key = SecretKey::random(&mut thread_rng());
+        let public_key = PublicKey::from_secret_key(&secret_key);
+        let alpha = randomize();
+        let proof = ECVRF::new(secret_key).prove(&alpha).unwrap();
+        let xy = hex::encode([proof.gamma.x.b32(), proof.gamma.y.b32()].concat());
+        let mut record = epoch_record(56, 0);
+        record["gamma"] = json!(xy);
+        let stored = randomness.insert_returning(record).await.unwrap();
+        assert_eq!(stored.gamma, xy);
+
+        let json = serde_json::to_value(&stored).unwrap();
+        let compressed = json["gamma"].as_str().unwrap().to_string();
+        assert_eq!(compressed.len(), 33 * 2);
+        assert_eq!(compressed, hex::encode(affine_to_compressed(&proof.gamma)));
+        // Compressed gamma is stored as x || y
+        let mut record = epoch_record(56, 1);
+        record["gamma"] = json!(compressed);
+        assert_eq!(randomness.insert_returning(record).await.unwrap().gamma, xy);
+        let mut record = epoch_record(56, 2);
+        record["gamma"] = json!(format!("02{}05", "00".repeat(31)));
+        assert!(randomness.insert_returning(record).await.is_err());
+
+        // Proof with the reconstructed gamma verifies like the original
+        let mut reconstructed = proof;
+        reconstructed.gamma = affine_from_compressed(&hex::decode(&compressed).unwrap()).unwrap();
+        assert!(reconstructed
+            .verify_with_pubkey(&alpha, &public_key)
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn proof_blob_is_backfilled() {
+        let sqlite = SqliteDB::new("sqlite::memory:".to_string()).await;
+        // Everything before the proof column
+        Migrator::up(&sqlite.connection, Some(4)).await.unwrap();
+
+        let secret_key = SecretKey::random(&mut thread_rng());
+        let public_key = PublicKey::from_secret_key(&secret_key);
+        let alpha = randomize();
+        let contract_proof = ECVRF::new(secret_key).prove_contract(&alpha).unwrap();
+        insert_legacy_keyring(&sqlite, &public_key).await;
+        let mut record = epoch_record(56, 0);
+        record["gamma"] = json!(hex::encode(
+            [contract_proof.gamma.x.b32(), contract_proof.gamma.y.b32()].concat()
+        ));
+        record["c"] = json!(hex::encode(contract_proof.c.b32()));
+        record["s"] = json!(hex::encode(contract_proof.s.b32()));
+        record["y"] = json!(hex::encode(contract_proof.y.b32()));
+        sqlite
+            .table_randomness()
+            .await
+            .insert(record)
+            .await
+            .unwrap();
+
+        Migrator::up(&sqlite.connection, None).await.unwrap();
+        let backfilled = sqlite
+            .table_randomness()
+            .await
+            .find_latest_epoch(NetworkId(56))
+            .await
+            .unwrap()
+            .unwrap();
+        let proof = ECVRFProof::from_bytes(&backfilled.proof.unwrap()).unwrap();
+        assert_eq!(proof.gamma, contract_proof.gamma);
+        assert_eq!(proof.c, contract_proof.c);
+        assert_eq!(proof.s, contract_proof.s);
+        assert_eq!(proof.y, contract_proof.y);
+        assert_eq!(proof.public_key(), public_key);
+    }
+
+    #[tokio::test]
+    async fn keyring_is_found_by_address() {
+        let sqlite = SqliteDB::new("sqlite::memory:".to_string()).await;
+        // Keyring created before the address column
+        Migrator::up(&sqlite.connection, Some(6)).await.unwrap();
+        let public_key = PublicKey::from_secret_key(&SecretKey::random(&mut thread_rng()));
+        insert_legacy_keyring(&sqlite, &public_key).await;
+        Migrator::up(&sqlite.connection, None).await.unwrap();
+        let keyring = sqlite.table_keyring().await;
+
+        let address = hex::encode(get_address(public_key));
+        let found = keyring
+            .find_by_address(&format!("0x{}", address.to_uppercase()))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.username, "chiro");
+        assert_eq!(found.address, Some(address));
+        assert!(keyring
+            .find_by_address("0x0000000000000000000000000000000000000000")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn keyrings_are_ordered_by_created_date() {
+        let sqlite = memory_db().await;
+        let keyring = sqlite.table_keyring().await;
+        for username in ["old", "new"] {
+            keyring
+                .insert(json!({
+                    "username": username,
+                    "hmac_secret": "00",
+                    "public_key": username,
+                    "secret_key_enc": username,
+                }))
+                .await
+                .unwrap();
+        }
+        // created_date is a timestamp, it's compared as a date rather than a string
+        for (username, created_date) in [
+            ("chiro", "2022-12-01 08:00:00"),
+            ("old", "2022-11-30 23:59:59"),
+            ("new", "2022-12-24 00:00:00"),
+        ] {
+            sqlite
+                .connection
+                .execute(Statement::from_sql_and_values(
+                    DbBackend::Sqlite,
+                    "UPDATE keyring SET created_date = ? WHERE username = ?",
+                    vec![
+                        DateTime::parse_from_str(created_date, "%Y-%m-%d %H:%M:%S")
+                            .unwrap()
+                            .into(),
+                        username.into(),
+                    ],
+                ))
+                .await
+                .unwrap();
+        }
+
+        let ordered = Keyring::find()
+            .order_by_asc(ColumnKeyring::CreatedDate)
+            .all(&sqlite.connection)
+            .await
+            .unwrap();
+        assert_eq!(
+            ordered
+                .iter()
+                .map(|k| k.username.as_str())
+                .collect::<Vec<_>>(),
+            vec!["old", "chiro", "new"]
+        );
+        let since = DateTime::parse_from_str("2022-12-01 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let recent = Keyring::find()
+            .filter(ColumnKeyring::CreatedDate.gte(since))
+            .order_by_desc(ColumnKeyring::CreatedDate)
+            .all(&sqlite.connection)
+            .await
+            .unwrap();
+        assert_eq!(
+            recent
+                .iter()
+                .map(|k| k.username.as_str())
+                .collect::<Vec<_>>(),
+            vec!["new", "chiro"]
+        );
+    }
+
+    #[tokio::test]
+    async fn keyring_public_key_is_parsed_in_both_forms() {
+        let sqlite = SqliteDB::new("sqlite::memory:".to_string()).await;
+        Migrator::up(&sqlite.connection, None).await.unwrap();
+        let keyring = sqlite.table_keyring().await;
+        let public_key = PublicKey::from_secret_key(&SecretKey::random(&mut thread_rng()));
+        for (username, compressed) in [("compressed", true), ("uncompressed", false)] {
+            keyring
+                .insert(json!({
+                    "username": username,
+                    "hmac_secret": "00",
+                    "public_key": public_key_to_hex(&public_key, compressed),
+                    "secret_key_enc": username,
+                }))
+                .await
+                .unwrap();
+            let found = keyring
+                .find_by_name(username.to_string())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(found.parse_public_key().unwrap(), public_key);
+        }
+    }
+
+    #[tokio::test]
+    async fn same_seed_yields_the_same_keyring() {
+        let seed = b"correct horse battery staple";
+        let (secret_key, public_key) = Keyring::from_seed(seed);
+        // Keys must be recoverable from seeds of older releases
+        assert_eq!(
+            hex::encode(secret_key.serialize()),
+            "42e9b1dc3ef86aaeaf0192afe8befaf64ff1d410dc7c611e3b406bfc7dcf0102"
+        );
+        for _ in 0..3 {
+            let (again, again_public) = Keyring::from_seed(seed);
+            assert_eq!(again.serialize(), secret_key.serialize());
+            assert_eq!(again_public, public_key);
+        }
+        assert_eq!(public_key, PublicKey::from_secret_key(&secret_key));
+        let (other, _) = Keyring::from_seed(b"correct horse battery staplf");
+        assert_ne!(other.serialize(), secret_key.serialize());
+
+        let sqlite = memory_db().await.with_kek(Kek::new(&[7u8; 32]));
+        let keyring = sqlite.table_keyring().await;
+        let stored = keyring
+            .store_keyring(
+                json!({
+                    "username": "recovered",
+                    "hmac_secret": "00",
+                    "public_key": hex::encode(public_key.serialize()),
+                    "derived": true,
+                }),
+                &secret_key,
+            )
+            .await
+            .unwrap();
+        assert!(stored.derived);
+        assert_eq!(stored.parse_public_key().unwrap(), public_key);
+    }
+
+    #[tokio::test]
+    async fn network_and_epoch_are_64_bit() {
+        let sqlite = memory_db().await;
+        let randomness = sqlite.table_randomness().await;
+        // Chain ids and epochs of long running networks don't fit in i32
+        let network = i64::from(u32::MAX) + 56;
+        let epoch = i64::from(i32::MAX) + 1;
+        insert_network(&sqlite, network).await;
+        randomness
+            .insert(epoch_record(network, epoch))
+            .await
+            .unwrap();
+        randomness
+            .insert(epoch_record(network, epoch + 1))
+            .await
+            .unwrap();
+
+        let latest = randomness
+            .find_latest_epoch(NetworkId(network))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.network, network);
+        assert_eq!(latest.epoch, epoch + 1);
+        let range = randomness
+            .find_epoch_range(NetworkId(network), EpochId(epoch), EpochId(epoch + 1))
+            .await
+            .unwrap();
+        assert_eq!(
+            range.iter().map(|r| r.epoch).collect::<Vec<_>>(),
+            vec![epoch, epoch + 1]
+        );
+    }
+
+    #[tokio::test]
+    async fn epochs_belong_to_registered_networks() {
+        let sqlite = memory_db().await;
+        let networks = sqlite.table_network().await;
+        let active =
+            |networks: Vec<ModelNetwork>| networks.iter().map(|n| n.chain_id).collect::<Vec<_>>();
+        // Networks are active unless they are disabled
+        assert_eq!(
+            active(networks.find_active().await.unwrap()),
+            vec![1, 56, 57]
+        );
+        assert!(networks.set_active(NetworkId(56), false).await.unwrap());
+        assert!(!networks.set_active(NetworkId(97), false).await.unwrap());
+        assert_eq!(active(networks.find_active().await.unwrap()), vec![1, 57]);
+        assert_eq!(active(networks.find_all().await.unwrap()), vec![1, 56, 57]);
+        assert!(
+            !networks
+                .find_by_chain_id(NetworkId(56))
+                .await
+                .unwrap()
+                .unwrap()
+                .active
+        );
+        assert!(networks
+            .find_by_chain_id(NetworkId(97))
+            .await
+            .unwrap()
+            .is_none());
+
+        let randomness = sqlite.table_randomness().await;
+        assert!(randomness.insert(epoch_record(97, 0)).await.is_err());
+        insert_network(&sqlite, 97).await;
+        randomness.insert(epoch_record(97, 0)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn prune_keeps_tip_and_genesis() {
+        let sqlite = memory_db().await;
+        let randomness = sqlite.table_randomness().await;
+        for epoch in 0..50 {
+            randomness.insert(epoch_record(56, epoch)).await.unwrap();
+            randomness.insert(epoch_record(1, epoch)).await.unwrap();
+        }
+
+        assert_eq!(
+            randomness
+                .prune_randomness(NetworkId(56), 10)
+                .await
+                .unwrap(),
+            39
+        );
+        let remaining: Vec<i64> = randomness
+            .find_epoch_range(NetworkId(56), EpochId(0), EpochId(99))
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.epoch.0)
+            .collect();
+        assert_eq!(
+            remaining,
+            [0].into_iter().chain(40..50).collect::<Vec<i64>>()
+        );
+        // Other networks are untouched
+        assert_eq!(
+            randomness
+                .find_epoch_range(NetworkId(1), EpochId(0), EpochId(99))
+                .await
+                .unwrap()
+                .len(),
+            50
+        );
+        // Nothing left to prune
+        assert_eq!(
+            randomness
+                .prune_randomness(NetworkId(56), 10)
+                .await
+                .unwrap(),
+            0
+        );
+        assert!(randomness.prune_randomness(NetworkId(56), 0).await.is_err());
+        assert_eq!(
+            randomness.prune_randomness(NetworkId(2), 10).await.unwrap(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn seen_alphas_expire_after_the_window() {
+        let sqlite = memory_db().await;
+        let seen = sqlite.table_seen_alpha().await;
+        assert!(seen.record(NetworkId(56), "2a", 1000, 60).await.unwrap());
+        assert!(!seen.record(NetworkId(56), "2a", 1059, 60).await.unwrap());
+        // Alphas are seen per network
+        assert!(seen.record(NetworkId(97), "2a", 1059, 60).await.unwrap());
+        assert!(seen.record(NetworkId(56), "2a", 1060, 60).await.unwrap());
+        assert_eq!(seen.prune(1060).await.unwrap(), 2);
+        assert_eq!(
+            SeenAlpha::find().all(&sqlite.connection).await.unwrap(),
+            vec![]
+        );
+    }
+
+    #[tokio::test]
+    async fn api_key_is_found_by_raw_key() {
+        let sqlite = memory_db().await;
+        let api_key = sqlite.table_api_key().await;
+        let record = api_key
+            .insert("relayer".to_string(), "secret-key")
+            .await
+            .unwrap();
+        // Only the hash is stored
+        assert_eq!(record.key_hash, hash_api_key("secret-key"));
+        assert_ne!(record.key_hash, "secret-key");
+        let found = api_key.find_by_key("secret-key").await.unwrap().unwrap();
+        assert_eq!(found.id, record.id);
+        assert!(api_key.find_by_key("secret").await.unwrap().is_none());
+        assert!(api_key.find_by_key("").await.unwrap().is_none());
     }
 }
@@ -0,0 +1,349 @@
+use crate::{
+    ids::{EpochId, NetworkId},
+    randomness::Model as ModelRandomness,
+    sqlitedb::{is_unique_violation, RandomnessTable},
+};
+use async_trait::async_trait;
+use sea_orm::{prelude::DateTime, DbErr};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Mutex, MutexGuard},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// Epochs with the API key and the request that created them
+type Epochs = HashMap<(NetworkId, EpochId), (ModelRandomness, Option<i32>, Option<String>)>;
+
+#[derive(Debug)]
+pub enum InsertError {
+    // Network already has the epoch
+    Conflict,
+    Database(DbErr),
+}
+
+impl fmt::Display for InsertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InsertError::Conflict => write!(f, "epoch already exists"),
+            InsertError::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for InsertError {}
+
+impl From<DbErr> for InsertError {
+    fn from(e: DbErr) -> Self {
+        InsertError::Database(e)
+    }
+}
+
+// Epochs of the hash chain, caller alphas aren't part of it
+// It only has what chain_epoch() and the epoch handlers use, they're tested with MemoryStore
+// Lookups by value, exports and admin methods (listNetworks, prune) query the tables of SqliteDB
+#[async_trait]
+pub trait RandomnessStore: Send + Sync {
+    // Insert an epoch and its audit record, it's a conflict if the network already has the epoch
+    async fn insert(
+        &self,
+        json_record: serde_json::Value,
+        actor: Option<i32>,
+        request_hash: Option<String>,
+    ) -> Result<ModelRandomness, InsertError>;
+
+    // Epoch of a network, None if it has not been created or was pruned
+    async fn get(
+        &self,
+        network: NetworkId,
+        epoch: EpochId,
+    ) -> Result<Option<ModelRandomness>, DbErr>;
+
+    // Epoch with the highest index of the given network
+    async fn latest(&self, network: NetworkId) -> Result<Option<ModelRandomness>, DbErr>;
+
+    // Page of the epochs of a network in [from_epoch, to_epoch] ordered by id, it starts after
+    // the record after_id, the id of its last record is returned if there is a next page
+    async fn range(
+        &self,
+        network: NetworkId,
+        from_epoch: EpochId,
+        to_epoch: Option<EpochId>,
+        after_id: Option<i32>,
+        limit: u64,
+    ) -> Result<(Vec<ModelRandomness>, Option<i32>), DbErr>;
+
+    // Epoch of a network that was proven for the given hex encoded alpha
+    async fn find_by_network_alpha(
+        &self,
+        network: NetworkId,
+        alpha: &str,
+    ) -> Result<Option<ModelRandomness>, DbErr>;
+
+    // Latest epoch of a network that the given request of the given API key created
+    async fn find_by_request(
+        &self,
+        network: NetworkId,
+        actor: Option<i32>,
+        request_hash: &str,
+    ) -> Result<Option<ModelRandomness>, DbErr>;
+}
+
+#[async_trait]
+impl RandomnessStore for RandomnessTable<'_> {
+    async fn insert(
+        &self,
+        json_record: serde_json::Value,
+        actor: Option<i32>,
+        request_hash: Option<String>,
+    ) -> Result<ModelRandomness, InsertError> {
+        self.insert_audited(json_record, actor, request_hash)
+            .await
+            .map_err(|e| {
+                if is_unique_violation(&e) {
+                    InsertError::Conflict
+                } else {
+                    InsertError::Database(e)
+                }
+            })
+    }
+
+    async fn get(
+        &self,
+        network: NetworkId,
+        epoch: EpochId,
+    ) -> Result<Option<ModelRandomness>, DbErr> {
+        self.find_epoch(network, epoch).await
+    }
+
+    async fn latest(&self, network: NetworkId) -> Result<Option<ModelRandomness>, DbErr> {
+        self.find_latest_epoch(network).await
+    }
+
+    async fn range(
+        &self,
+        network: NetworkId,
+        from_epoch: EpochId,
+        to_epoch: Option<EpochId>,
+        after_id: Option<i32>,
+        limit: u64,
+    ) -> Result<(Vec<ModelRandomness>, Option<i32>), DbErr> {
+        self.find_epoch_page(network, from_epoch, to_epoch, after_id, limit)
+            .await
+    }
+
+    async fn find_by_network_alpha(
+        &self,
+        network: NetworkId,
+        alpha: &str,
+    ) -> Result<Option<ModelRandomness>, DbErr> {
+        RandomnessTable::find_by_network_alpha(self, network, alpha).await
+    }
+
+    async fn find_by_request(
+        &self,
+        network: NetworkId,
+        actor: Option<i32>,
+        request_hash: &str,
+    ) -> Result<Option<ModelRandomness>, DbErr> {
+        RandomnessTable::find_by_request(self, network, actor, request_hash).await
+    }
+}
+
+// Epochs in a HashMap by (network, epoch)
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    epochs: Mutex<Epochs>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn epochs(&self) -> Result<MutexGuard<'_, Epochs>, DbErr> {
+        self.epochs.lock().map_err(|e| DbErr::Custom(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl RandomnessStore for MemoryStore {
+    async fn insert(
+        &self,
+        json_record: serde_json::Value,
+        actor: Option<i32>,
+        request_hash: Option<String>,
+    ) -> Result<ModelRandomness, InsertError> {
+        let mut record: ModelRandomness =
+            serde_json::from_value(json_record).map_err(|e| DbErr::Json(e.to_string()))?;
+        let mut epochs = self.epochs()?;
+        let key = (record.network, record.epoch);
+        if epochs.contains_key(&key) {
+            return Err(InsertError::Conflict);
+        }
+        record.id = epochs.len() as i32 + 1;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+        record.created_date = DateTime::from_timestamp_opt(now, 0).unwrap_or_default();
+        epochs.insert(key, (record.clone(), actor, request_hash));
+        Ok(record)
+    }
+
+    async fn get(
+        &self,
+        network: NetworkId,
+        epoch: EpochId,
+    ) -> Result<Option<ModelRandomness>, DbErr> {
+        Ok(self
+            .epochs()?
+            .get(&(network, epoch))
+            .map(|(record, _, _)| record.clone()))
+    }
+
+    async fn latest(&self, network: NetworkId) -> Result<Option<ModelRandomness>, DbErr> {
+        Ok(self
+            .epochs()?
+            .values()
+            .map(|(record, _, _)| record)
+            .filter(|record| record.network == network)
+            .max_by_key(|record| record.epoch)
+            .cloned())
+    }
+
+    async fn range(
+        &self,
+        network: NetworkId,
+        from_epoch: EpochId,
+        to_epoch: Option<EpochId>,
+        after_id: Option<i32>,
+        limit: u64,
+    ) -> Result<(Vec<ModelRandomness>, Option<i32>), DbErr> {
+        let mut page: Vec<_> = self
+            .epochs()?
+            .values()
+            .map(|(record, _, _)| record)
+            .filter(|record| {
+                record.network == network
+                    && record.epoch >= from_epoch
+                    && to_epoch.is_none_or(|to_epoch| record.epoch <= to_epoch)
+                    && after_id.is_none_or(|after_id| record.id > after_id)
+            })
+            .cloned()
+            .collect();
+        page.sort_by_key(|record| record.id);
+        if page.len() as u64 > limit {
+            page.truncate(limit as usize);
+            let last = page.last().map(|record| record.id);
+            return Ok((page, last));
+        }
+        Ok((page, None))
+    }
+
+    async fn find_by_network_alpha(
+        &self,
+        network: NetworkId,
+        alpha: &str,
+    ) -> Result<Option<ModelRandomness>, DbErr> {
+        Ok(self
+            .epochs()?
+            .values()
+            .map(|(record, _, _)| record)
+            .find(|record| record.network == network && record.alpha == alpha)
+            .cloned())
+    }
+
+    async fn find_by_request(
+        &self,
+        network: NetworkId,
+        actor: Option<i32>,
+        request_hash: &str,
+    ) -> Result<Option<ModelRandomness>, DbErr> {
+        Ok(self
+            .epochs()?
+            .values()
+            .filter(|(record, by, request)| {
+                record.network == network
+                    && *by == actor
+                    && request.as_deref() == Some(request_hash)
+            })
+            .map(|(record, _, _)| record)
+            .max_by_key(|record| record.epoch)
+            .cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epoch_scheduler::{chain_epoch, Signer, Trigger};
+    use ecvrf::helper::generate_keypair;
+
+    #[tokio::test]
+    async fn memory_store_finds_epochs_by_network() {
+        let store = MemoryStore::new();
+        let signer = Signer {
+            keyring_id: 1,
+            secret_key: generate_keypair().secret_key,
+        };
+        let mut epochs = Vec::new();
+        for network in [NetworkId(56), NetworkId(56), NetworkId(97)] {
+            epochs.push(
+                chain_epoch(&store, &signer, network, None, &Trigger::default())
+                    .await
+                    .unwrap(),
+            );
+        }
+        assert_eq!(
+            store.latest(NetworkId(56)).await.unwrap().as_ref(),
+            Some(&epochs[1])
+        );
+        assert_eq!(
+            store
+                .range(NetworkId(56), EpochId(0), Some(EpochId(10)), None, 100)
+                .await
+                .unwrap(),
+            (epochs[..2].to_vec(), None)
+        );
+        // Pages continue after the cursor
+        assert_eq!(
+            store
+                .range(NetworkId(56), EpochId(0), None, None, 1)
+                .await
+                .unwrap(),
+            (epochs[..1].to_vec(), Some(epochs[0].id))
+        );
+        assert_eq!(
+            store
+                .range(NetworkId(56), EpochId(0), None, Some(epochs[0].id), 1)
+                .await
+                .unwrap(),
+            (epochs[1..2].to_vec(), None)
+        );
+        assert_eq!(
+            store
+                .find_by_network_alpha(NetworkId(56), &epochs[1].alpha)
+                .await
+                .unwrap()
+                .as_ref(),
+            Some(&epochs[1])
+        );
+        assert!(store
+            .find_by_network_alpha(NetworkId(97), &epochs[1].alpha)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(store
+            .get(NetworkId(97), EpochId(1))
+            .await
+            .unwrap()
+            .is_none());
+
+        // (network, epoch) is unique
+        let mut record = serde_json::to_value(&epochs[2]).unwrap();
+        record["keyring_id"] = 1.into();
+        let error = store.insert(record, None, None).await.unwrap_err();
+        assert!(matches!(error, InsertError::Conflict));
+    }
+}
@@ -0,0 +1,101 @@
+use assert_cmd::Command;
+
+fn cli() -> Command {
+    Command::cargo_bin("orand-cli").unwrap()
+}
+
+// Value of `key: value` line of keygen output
+fn field(output: &str, key: &str) -> String {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix(&format!("{}: ", key)))
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn prove_and_verify_round_trip() {
+    let keygen = cli().arg("keygen").assert().success();
+    let keys = String::from_utf8(keygen.get_output().stdout.clone()).unwrap();
+    let (secret, public_key) = (field(&keys, "secret_key"), field(&keys, "public_key"));
+    let alpha = "0x".to_string() + &"11".repeat(32);
+
+    let prove = cli()
+        .args(["prove", "--secret", &secret, "--alpha", &alpha])
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("--secret is deprecated"));
+    let proof = String::from_utf8(prove.get_output().stdout.clone()).unwrap();
+    let proof = proof.trim();
+
+    cli()
+        .args(["verify", "--pubkey", &public_key, "--alpha", &alpha])
+        .args(["--proof", proof])
+        .assert()
+        .success()
+        .stdout("valid\n");
+
+    // Proof of another alpha
+    let other_alpha = "22".repeat(32);
+    cli()
+        .args(["verify", "--pubkey", &public_key, "--alpha", &other_alpha])
+        .args(["--proof", proof])
+        .assert()
+        .code(1)
+        .stdout("invalid\n");
+
+    // Malformed proof
+    cli()
+        .args(["verify", "--pubkey", &public_key, "--alpha", &alpha])
+        .args(["--proof", "00"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn secret_key_is_read_from_env_or_file() {
+    let keygen = cli().arg("keygen").assert().success();
+    let keys = String::from_utf8(keygen.get_output().stdout.clone()).unwrap();
+    let secret = field(&keys, "secret_key");
+    let alpha = "33".repeat(32);
+
+    let from_env = cli()
+        .env("ORAND_SECRET_KEY", &secret)
+        .args(["prove", "--alpha", &alpha])
+        .assert()
+        .success()
+        .stderr("");
+    let proof = from_env.get_output().stdout.clone();
+
+    let path = std::env::temp_dir().join(format!("orand-cli-secret-{}", std::process::id()));
+    std::fs::write(&path, format!("{}\n", secret)).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        cli()
+            .env_remove("ORAND_SECRET_KEY")
+            .args(["prove", "--alpha", &alpha])
+            .arg("--secret-file")
+            .arg(&path)
+            .assert()
+            .success()
+            .stdout(predicates::ord::eq(proof));
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        cli()
+            .args(["prove", "--alpha", &alpha])
+            .arg("--secret-file")
+            .arg(&path)
+            .assert()
+            .code(2)
+            .stderr(predicates::str::contains("world readable"));
+    }
+    std::fs::remove_file(&path).unwrap();
+
+    cli()
+        .env_remove("ORAND_SECRET_KEY")
+        .args(["prove", "--alpha", &alpha])
+        .assert()
+        .code(2);
+}
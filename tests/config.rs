@@ -0,0 +1,42 @@
+use orochimaru::{config::Config, server::ServerConfig};
+use std::env;
+
+// Environment variables are process wide, every override is checked by this single test
+#[test]
+fn environment_overrides_the_config_file() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/orand.example.toml");
+    env::set_var("DATABASE_URL", "sqlite::memory:");
+    env::set_var("ORAND_KEYRING", "relayer");
+    env::set_var("ORAND_EPOCH_INTERVAL", "15");
+    // Network 97 keeps its settings of the file, 56 is dropped
+    env::set_var("ORAND_NETWORKS", "97, 204");
+    let config = Config::load(path).unwrap();
+    assert_eq!(config.database.url, "sqlite::memory:");
+    assert_eq!(config.server.keyring_name, "relayer");
+    assert_eq!(config.server.networks, vec![97, 204]);
+    let settings: Vec<_> = config
+        .networks
+        .iter()
+        .map(|network| (network.name(), config.epoch_interval_of(network)))
+        .collect();
+    assert_eq!(
+        settings,
+        vec![("bnb-testnet".to_string(), 30), ("204".to_string(), 15)]
+    );
+
+    env::set_var("ORAND_EPOCH_INTERVAL", "0");
+    assert!(Config::load(path).is_err());
+    env::remove_var("ORAND_EPOCH_INTERVAL");
+    assert!(Config::load("does-not-exist.toml").is_err());
+
+    // Rate limiting stays off unless a variable sets it
+    let unlimited = ServerConfig {
+        rate_limit: None,
+        ..ServerConfig::default()
+    };
+    assert_eq!(unlimited.clone().with_env().unwrap().rate_limit, None);
+    env::set_var("ORAND_RATE_LIMIT_BURST", "5");
+    let limited = unlimited.with_env().unwrap().rate_limit.unwrap();
+    assert_eq!(limited.burst, 5);
+    env::remove_var("ORAND_RATE_LIMIT_BURST");
+}
@@ -0,0 +1,115 @@
+use ecvrf::secp256k1::{PublicKey, SecretKey};
+use migration::{Migrator, MigratorTrait};
+use orochimaru::{
+    kek::{Kek, KEK_ENV},
+    sqlitedb::SqliteDB,
+};
+use sea_orm::{prelude::DateTime, ConnectionTrait, DbBackend, Statement};
+
+const KEK: &str = "0x000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+// The migration reads the KEK from the environment, this test has its own process
+#[tokio::test]
+async fn plaintext_secret_keys_are_encrypted_by_the_migration() {
+    std::env::set_var(KEK_ENV, KEK);
+    let sqlite = SqliteDB::new("sqlite::memory:".to_string())
+        .await
+        .with_kek(Kek::from_hex(KEK).unwrap());
+    // Schema before secret keys were encrypted
+    Migrator::up(sqlite.connection(), Some(9)).await.unwrap();
+    let secret_key = SecretKey::parse(&[0x42u8; 32]).unwrap();
+    let public_key = PublicKey::from_secret_key(&secret_key);
+    sqlite
+        .connection()
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "INSERT INTO keyring (username, hmac_secret, public_key, secret_key) \
+             VALUES (?, ?, ?, ?)",
+            vec![
+                "chiro".into(),
+                "00".into(),
+                hex::encode(public_key.serialize()).into(),
+                hex::encode(secret_key.serialize()).into(),
+            ],
+        ))
+        .await
+        .unwrap();
+    Migrator::up(sqlite.connection(), None).await.unwrap();
+
+    let keyring = sqlite.table_keyring().await;
+    let (record, loaded) = keyring.load_keyring("chiro").await.unwrap().unwrap();
+    assert_ne!(record.secret_key_enc, hex::encode(secret_key.serialize()));
+    assert_eq!(loaded.serialize(), secret_key.serialize());
+
+    // Down migrations back to the same schema restore the plaintext column
+    let steps = Migrator::migrations().len() as u32 - 9;
+    Migrator::down(sqlite.connection(), Some(steps))
+        .await
+        .unwrap();
+    let row = sqlite
+        .connection()
+        .query_one(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT secret_key FROM keyring".to_string(),
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    let plaintext: String = row.try_get("", "secret_key").unwrap();
+    assert_eq!(plaintext, hex::encode(secret_key.serialize()));
+}
+
+// Dates of the baseline schema are parsed as they are, there is nothing to backfill
+#[tokio::test]
+async fn created_date_of_baseline_keyrings_is_loaded_as_a_timestamp() {
+    std::env::set_var(KEK_ENV, KEK);
+    let sqlite = SqliteDB::new("sqlite::memory:".to_string())
+        .await
+        .with_kek(Kek::from_hex(KEK).unwrap());
+    // Keyring table of the baseline
+    Migrator::up(sqlite.connection(), Some(1)).await.unwrap();
+    for (username, seed, created_date) in [
+        ("chiro", 0x42u8, None),
+        ("legacy", 0x43, Some("2022-11-05 17:26:37")),
+    ] {
+        let secret_key = SecretKey::parse(&[seed; 32]).unwrap();
+        let public_key = PublicKey::from_secret_key(&secret_key);
+        let mut values = vec![
+            username.into(),
+            "00".into(),
+            hex::encode(public_key.serialize()).into(),
+            hex::encode(secret_key.serialize()).into(),
+        ];
+        // Without a value the column default fills it in
+        let sql = match created_date {
+            Some(created_date) => {
+                values.push(created_date.into());
+                "INSERT INTO keyring (username, hmac_secret, public_key, secret_key, created_date) \
+                 VALUES (?, ?, ?, ?, ?)"
+            }
+            None => {
+                "INSERT INTO keyring (username, hmac_secret, public_key, secret_key) \
+                 VALUES (?, ?, ?, ?)"
+            }
+        };
+        sqlite
+            .connection()
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                sql,
+                values,
+            ))
+            .await
+            .unwrap();
+    }
+    Migrator::up(sqlite.connection(), None).await.unwrap();
+
+    let keyring = sqlite.table_keyring().await;
+    let (legacy, _) = keyring.load_keyring("legacy").await.unwrap().unwrap();
+    assert_eq!(
+        legacy.created_date,
+        DateTime::parse_from_str("2022-11-05 17:26:37", "%Y-%m-%d %H:%M:%S").unwrap()
+    );
+    let (chiro, _) = keyring.load_keyring("chiro").await.unwrap().unwrap();
+    assert!(chiro.created_date > legacy.created_date);
+}
@@ -0,0 +1,130 @@
+use migration::{Migrator, MigratorTrait};
+use orochimaru::{
+    ids::{EpochId, NetworkId},
+    sqlitedb::SqliteDB,
+};
+use sea_orm::{ConnectionTrait, DbBackend, Statement};
+use serde_json::json;
+
+fn epoch_record(network: i64, keyring_id: i32, epoch: i64) -> serde_json::Value {
+    let tag = format!("{:02x}{:08x}", network, epoch);
+    json!({
+        "network": network,
+        "keyring_id": keyring_id,
+        "epoch": epoch,
+        "alpha": format!("a{}", tag),
+        "gamma": "00",
+        "c": "00",
+        "s": "00",
+        "y": format!("f{}", tag),
+        "witness_address": "00",
+        "witness_gamma": "00",
+        "witness_hash": "00",
+        "inverse_z": "00",
+    })
+}
+
+async fn index_names(sqlite: &SqliteDB) -> Vec<String> {
+    let rows = sqlite
+        .connection()
+        .query_all(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT name FROM sqlite_master WHERE type = 'index' AND tbl_name = 'randomness' \
+             AND sql IS NOT NULL ORDER BY name"
+                .to_string(),
+        ))
+        .await
+        .unwrap();
+    rows.iter()
+        .map(|row| row.try_get("", "name").unwrap())
+        .collect()
+}
+
+// Entity queries need the columns of later migrations, rows are counted by hand
+async fn epoch_count(sqlite: &SqliteDB) -> i64 {
+    sqlite
+        .connection()
+        .query_one(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT COUNT(*) AS count FROM randomness".to_string(),
+        ))
+        .await
+        .unwrap()
+        .unwrap()
+        .try_get("", "count")
+        .unwrap()
+}
+
+// Networks that have epochs are registered by the migration, the down migration keeps epochs
+#[tokio::test]
+async fn networks_with_epochs_are_seeded_by_the_migration() {
+    let sqlite = SqliteDB::new("sqlite::memory:".to_string()).await;
+    let migrations = Migrator::migrations();
+    let before = migrations
+        .iter()
+        .position(|m| m.name() == "m20221231_000001_create_table_network")
+        .unwrap() as u32;
+    Migrator::up(sqlite.connection(), Some(before))
+        .await
+        .unwrap();
+    let keyring = sqlite.table_keyring().await;
+    for (username, key) in [("chiro", "01"), ("rotated", "02")] {
+        keyring
+            .insert(json!({
+                "username": username,
+                "hmac_secret": "00",
+                "public_key": key,
+                "secret_key_enc": key,
+            }))
+            .await
+            .unwrap();
+    }
+    let randomness = sqlite.table_randomness().await;
+    // Key of network 56 was rotated after its genesis
+    for (network, keyring_id, epoch) in [(56, 1, 0), (56, 2, 1), (97, 1, 0)] {
+        randomness
+            .insert(epoch_record(network, keyring_id, epoch))
+            .await
+            .unwrap();
+    }
+    let indexes = index_names(&sqlite).await;
+    Migrator::up(sqlite.connection(), None).await.unwrap();
+
+    let networks = sqlite.table_network().await.find_all().await.unwrap();
+    assert_eq!(
+        networks
+            .iter()
+            .map(|n| (
+                n.name.as_str(),
+                n.chain_id.0,
+                n.keyring_id,
+                n.epoch_interval_secs,
+                n.active
+            ))
+            .collect::<Vec<_>>(),
+        vec![("56", 56, 2, 60, true), ("97", 97, 1, 60, true)]
+    );
+    // Rebuilt table keeps its rows and indexes
+    assert_eq!(
+        randomness
+            .find_epoch_range(NetworkId(56), EpochId(0), EpochId(1))
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+    // Indexes of later migrations are added, the rebuilt table keeps the others
+    let mut upgraded = indexes.clone();
+    upgraded.push("index_network_created_date".to_string());
+    upgraded.sort();
+    assert_eq!(index_names(&sqlite).await, upgraded);
+    assert!(randomness.insert(epoch_record(56, 1, 1)).await.is_err());
+    assert!(randomness.insert(epoch_record(1, 1, 0)).await.is_err());
+
+    Migrator::down(sqlite.connection(), Some(migrations.len() as u32 - before))
+        .await
+        .unwrap();
+    assert_eq!(epoch_count(&sqlite).await, 3);
+    assert_eq!(index_names(&sqlite).await, indexes);
+    randomness.insert(epoch_record(1, 1, 0)).await.unwrap();
+}
@@ -0,0 +1,1443 @@
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::ConnectInfo,
+    http::{
+        header::{ACCEPT, AUTHORIZATION, CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH},
+        HeaderValue, Request, StatusCode,
+    },
+    Router,
+};
+use ecvrf::{
+    ecproof::{ECVRFProof, PROOF_SIZE},
+    helper::{generate_keypair, get_address},
+    secp256k1::curve::Scalar,
+    ECVRF,
+};
+use futures_util::{SinkExt, StreamExt};
+use migration::{Migrator, MigratorTrait};
+use orochimaru::{
+    epoch_scheduler::{new_epoch, Trigger},
+    ids::NetworkId,
+    json_rpc::{
+        INTERNAL_ERROR, INVALID_PARAMS, INVALID_REQUEST, NOT_FOUND, PARSE_ERROR, RATE_LIMITED,
+        REPLAY, SHUTTING_DOWN, UNAUTHORIZED,
+    },
+    kek::Kek,
+    server::{
+        rate_limit::RateLimitConfig, router, serve, AppState, ServerConfig, SignedResponse,
+        ADMIN_TOKEN_HEADER, CBOR,
+    },
+    sqlitedb::SqliteDB,
+};
+use sea_orm::{ConnectionTrait, DbBackend, Statement};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    fmt::Debug,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tiny_keccak::{Hasher, Keccak};
+use tokio::net::TcpListener;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tower::ServiceExt;
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id},
+    Subscriber,
+};
+use tracing_subscriber::{layer::Context, prelude::*, Layer, Registry};
+
+const API_KEY: &str = "orand-test-api-key";
+
+async fn test_router() -> Router {
+    test_router_with(ServerConfig {
+        admin_token: Some("admin".to_string()),
+        ..ServerConfig::default()
+    })
+    .await
+}
+
+async fn test_router_with(config: ServerConfig) -> Router {
+    router(test_state(config).await)
+}
+
+async fn test_state(config: ServerConfig) -> AppState {
+    let sqlite = SqliteDB::new("sqlite::memory:".to_string())
+        .await
+        .with_kek(Kek::new(&[0u8; 32]));
+    Migrator::up(sqlite.connection(), None).await.unwrap();
+    let keypair = generate_keypair();
+    sqlite
+        .table_keyring()
+        .await
+        .store_keyring(
+            json!({
+                "username": "chiro",
+                "hmac_secret": "00",
+                "public_key": hex::encode(keypair.public_key.serialize()),
+                "address": hex::encode(get_address(keypair.public_key)),
+            }),
+            &keypair.secret_key,
+        )
+        .await
+        .unwrap();
+    sqlite
+        .table_api_key()
+        .await
+        .insert("relayer".to_string(), API_KEY)
+        .await
+        .unwrap();
+    for chain_id in [56, 97] {
+        sqlite
+            .table_network()
+            .await
+            .insert_returning(json!({
+                "name": format!("chain-{}", chain_id),
+                "chain_id": chain_id,
+                "epoch_interval_secs": 60,
+                "keyring_id": 1,
+            }))
+            .await
+            .unwrap();
+    }
+    AppState::new(sqlite, config)
+}
+
+async fn call(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn rpc(body: &str) -> Request<Body> {
+    Request::post("/")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+// orand_newEpoch of network 56, requests with other ids aren't retries of each other
+fn new_epoch_request(id: u64) -> String {
+    format!(
+        r#"{{"jsonrpc":"2.0","id":{},"method":"orand_newEpoch","params":[56]}}"#,
+        id
+    )
+}
+
+fn rpc_with_api_key(body: &str, api_key: &str) -> Request<Body> {
+    Request::post("/")
+        .header(AUTHORIZATION, format!("Bearer {}", api_key))
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn new_epoch_is_the_latest_epoch() {
+    let app = test_router().await;
+    let (status, response) = call(
+        &app,
+        rpc(r#"{"jsonrpc":"2.0","id":1,"method":"orand_getLatestEpoch","params":[56]}"#),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["error"]["code"], NOT_FOUND);
+
+    let (_, created) = call(
+        &app,
+        rpc_with_api_key(
+            r#"{"jsonrpc":"2.0","id":2,"method":"orand_newEpoch","params":[56]}"#,
+            API_KEY,
+        ),
+    )
+    .await;
+    assert_eq!(created["id"], 2);
+    assert_eq!(created["result"]["epoch"], 0);
+
+    let (_, latest) = call(
+        &app,
+        rpc(r#"{"jsonrpc":"2.0","id":"3","method":"orand_getLatestEpoch","params":[56]}"#),
+    )
+    .await;
+    assert_eq!(latest["id"], "3");
+    assert_eq!(latest["result"], created["result"]);
+}
+
+#[tokio::test]
+async fn retried_new_epoch_returns_the_created_epoch() {
+    let app = test_router().await;
+    let (_, created) = call(&app, rpc_with_api_key(&new_epoch_request(1), API_KEY)).await;
+    let (_, retried) = call(&app, rpc_with_api_key(&new_epoch_request(1), API_KEY)).await;
+    assert_eq!(created["result"]["epoch"], 0);
+    assert_eq!(retried, created);
+    let (_, next) = call(&app, rpc_with_api_key(&new_epoch_request(2), API_KEY)).await;
+    assert_eq!(next["result"]["epoch"], 1);
+    assert_eq!(next["result"]["alpha"], created["result"]["y"]);
+}
+
+#[tokio::test]
+async fn new_epoch_is_created_for_the_requested_network() {
+    let app = test_router().await;
+    let (_, epoch) = call(
+        &app,
+        rpc_with_api_key(
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_newEpoch","params":[97]}"#,
+            API_KEY,
+        ),
+    )
+    .await;
+    assert_eq!(epoch["result"]["network"], 97);
+    assert_eq!(epoch["result"]["epoch"], 0);
+    let latest = |network: i64| {
+        rpc(&format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"orand_getLatestEpoch","params":[{}]}}"#,
+            network
+        ))
+    };
+    let (_, latest_97) = call(&app, latest(97)).await;
+    assert_eq!(latest_97["result"]["y"], epoch["result"]["y"]);
+    let (_, latest_56) = call(&app, latest(56)).await;
+    assert_eq!(latest_56["error"]["code"], NOT_FOUND);
+
+    // Networks that aren't registered are named in the error
+    let (_, response) = call(
+        &app,
+        rpc_with_api_key(
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_newEpoch","params":[1]}"#,
+            API_KEY,
+        ),
+    )
+    .await;
+    assert_eq!(response["error"]["code"], NOT_FOUND);
+    assert!(response["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("network 1"));
+}
+
+#[tokio::test]
+async fn public_epoch_has_its_metadata() {
+    let app = test_router().await;
+    let request = r#"{"jsonrpc":"2.0","id":1,"method":"orand_getPublicEpoch","params":[56, 0]}"#;
+    let (_, response) = call(&app, rpc(request)).await;
+    assert_eq!(response["error"]["code"], NOT_FOUND);
+
+    let (_, created) = call(
+        &app,
+        rpc_with_api_key(
+            r#"{"jsonrpc":"2.0","id":2,"method":"orand_newEpoch","params":[56, 17000000]}"#,
+            API_KEY,
+        ),
+    )
+    .await;
+    let (_, response) = call(&app, rpc(request)).await;
+    let epoch = response["result"].as_object().unwrap();
+    let mut fields: Vec<_> = epoch.keys().map(String::as_str).collect();
+    fields.sort();
+    assert_eq!(
+        fields,
+        vec![
+            "alpha",
+            "block_number",
+            "created_date",
+            "epoch",
+            "network",
+            "proof"
+        ]
+    );
+    assert_eq!(epoch["network"], 56);
+    assert_eq!(epoch["epoch"], 0);
+    assert_eq!(epoch["alpha"], created["result"]["alpha"]);
+    assert_eq!(epoch["block_number"], 17_000_000);
+    assert_eq!(
+        epoch["proof"]["y"],
+        format!("0x{}", created["result"]["y"].as_str().unwrap())
+    );
+
+    // YYYY-MM-DDTHH:MM:SSZ
+    let created_date = epoch["created_date"].as_str().unwrap();
+    assert_eq!(created_date.len(), 20);
+    for (i, c) in created_date.char_indices() {
+        match i {
+            4 | 7 => assert_eq!(c, '-'),
+            10 => assert_eq!(c, 'T'),
+            13 | 16 => assert_eq!(c, ':'),
+            19 => assert_eq!(c, 'Z'),
+            _ => assert!(c.is_ascii_digit()),
+        }
+    }
+}
+
+#[tokio::test]
+async fn malformed_requests_are_rejected() {
+    let app = test_router().await;
+    let (_, response) = call(&app, rpc("{")).await;
+    assert_eq!(response["error"]["code"], PARSE_ERROR);
+    assert_eq!(response["id"], Value::Null);
+
+    let (_, response) = call(
+        &app,
+        rpc(r#"{"jsonrpc":"2.0","id":1,"method":"orand_getEpochRange","params":[56, 5, 1]}"#),
+    )
+    .await;
+    assert_eq!(response["error"]["code"], INVALID_PARAMS);
+
+    // Negative ids are rejected before they reach the database
+    for request in [
+        r#"{"jsonrpc":"2.0","id":1,"method":"orand_getPublicEpoch","params":[-56, 1]}"#,
+        r#"{"jsonrpc":"2.0","id":1,"method":"orand_getPublicEpoch","params":[56, -1]}"#,
+    ] {
+        let (_, response) = call(&app, rpc(request)).await;
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    }
+    let (_, response) = call(
+        &app,
+        rpc_with_api_key(
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_newEpoch","params":[-56]}"#,
+            API_KEY,
+        ),
+    )
+    .await;
+    assert_eq!(response["error"]["code"], INVALID_PARAMS);
+
+    let (status, _) = call(&app, Request::get("/unknown").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = call(
+        &app,
+        Request::post("/")
+            .body(Body::from(vec![b' '; 128 * 1024]))
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn admin_methods_need_admin_token() {
+    let app = test_router().await;
+    let prune = r#"{"jsonrpc":"2.0","id":1,"method":"orand_prune","params":[56, 10]}"#;
+    let (_, response) = call(&app, rpc(prune)).await;
+    assert_eq!(response["error"]["code"], UNAUTHORIZED);
+
+    let (_, response) = call(
+        &app,
+        Request::post("/")
+            .header(ADMIN_TOKEN_HEADER, "wrong")
+            .body(Body::from(prune))
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(response["error"]["code"], UNAUTHORIZED);
+
+    let (_, response) = call(
+        &app,
+        Request::post("/")
+            .header(ADMIN_TOKEN_HEADER, "admin")
+            .body(Body::from(prune))
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(response["result"]["deleted"], 0);
+}
+
+#[tokio::test]
+async fn every_network_with_an_epoch_is_listed() {
+    let app = test_router().await;
+    let list = r#"{"jsonrpc":"2.0","id":1,"method":"orand_listNetworks"}"#;
+    let (_, response) = call(&app, rpc(list)).await;
+    assert_eq!(response["result"], json!([]));
+
+    for id in [2, 3] {
+        call(&app, rpc_with_api_key(&new_epoch_request(id), API_KEY)).await;
+    }
+    let genesis = format!(
+        r#"{{"jsonrpc":"2.0","id":3,"method":"orand_createGenesis","params":[97, "{}"]}}"#,
+        "11".repeat(32)
+    );
+    call(
+        &app,
+        Request::post("/")
+            .header(ADMIN_TOKEN_HEADER, "admin")
+            .body(Body::from(genesis))
+            .unwrap(),
+    )
+    .await;
+
+    let (_, response) = call(&app, rpc(list)).await;
+    let networks = response["result"].as_array().unwrap();
+    assert_eq!(
+        networks
+            .iter()
+            .map(|n| (
+                n["network"].as_i64().unwrap(),
+                n["latest_epoch"].as_i64().unwrap()
+            ))
+            .collect::<Vec<_>>(),
+        vec![(56, 1), (97, 0)]
+    );
+    for network in networks {
+        assert_eq!(network["address"].as_str().unwrap().len(), 40);
+    }
+}
+
+#[tokio::test]
+async fn new_epoch_without_a_keyring_is_not_found() {
+    let state = test_state(ServerConfig::default()).await;
+    // Foreign keys of SQLite aren't enforced on databases that were created without them
+    for sql in [
+        "PRAGMA foreign_keys = OFF",
+        "UPDATE network SET keyring_id = 99 WHERE chain_id = 56",
+    ] {
+        state
+            .db()
+            .connection()
+            .execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
+            .await
+            .unwrap();
+    }
+    let app = router(state);
+    let (status, response) = call(
+        &app,
+        rpc_with_api_key(
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_newEpoch","params":[56]}"#,
+            API_KEY,
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response["error"]["code"], NOT_FOUND);
+    assert!(response["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("network 56"));
+}
+
+#[tokio::test]
+async fn writes_are_refused_once_shutdown_is_triggered() {
+    let state = test_state(ServerConfig::default()).await;
+    let shutdown = state.shutdown();
+    let app = router(state);
+    let new_epoch = r#"{"jsonrpc":"2.0","id":1,"method":"orand_newEpoch","params":[56]}"#;
+    let (_, genesis) = call(&app, rpc_with_api_key(new_epoch, API_KEY)).await;
+    assert_eq!(shutdown.in_flight(), 0);
+
+    assert!(shutdown.drain(Duration::from_secs(1)).await);
+    let (status, response) = call(&app, rpc_with_api_key(new_epoch, API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response["error"]["code"], SHUTTING_DOWN);
+    // Epochs are still served until the node exits
+    let (_, latest) = call(
+        &app,
+        rpc(r#"{"jsonrpc":"2.0","id":1,"method":"orand_getLatestEpoch","params":[56]}"#),
+    )
+    .await;
+    assert_eq!(latest["result"]["epoch"], 0);
+    assert_eq!(latest["result"]["y"], genesis["result"]["y"]);
+}
+
+#[tokio::test]
+async fn genesis_of_an_unregistered_network_is_not_found() {
+    let app = test_router().await;
+    let genesis = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"orand_createGenesis","params":[1, "{}"]}}"#,
+        "11".repeat(32)
+    );
+    let (_, response) = call(
+        &app,
+        Request::post("/")
+            .header(ADMIN_TOKEN_HEADER, "admin")
+            .body(Body::from(genesis))
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(response["error"]["code"], NOT_FOUND);
+}
+
+#[tokio::test]
+async fn every_new_epoch_has_an_audit_record() {
+    let app = test_router().await;
+    for id in [10, 11] {
+        let (_, created) = call(&app, rpc_with_api_key(&new_epoch_request(id), API_KEY)).await;
+        assert!(created["result"].is_object());
+    }
+
+    let audit_log = r#"{"jsonrpc":"2.0","id":2,"method":"orand_getAuditLog","params":[56, 0, 10]}"#;
+    let (_, response) = call(&app, rpc_with_api_key(audit_log, API_KEY)).await;
+    assert_eq!(response["error"]["code"], UNAUTHORIZED);
+
+    let (_, response) = call(
+        &app,
+        Request::post("/")
+            .header(ADMIN_TOKEN_HEADER, "admin")
+            .body(Body::from(audit_log))
+            .unwrap(),
+    )
+    .await;
+    let records = response["result"].as_array().unwrap();
+    assert_eq!(records.len(), 2);
+    for (epoch, record) in records.iter().enumerate() {
+        assert_eq!(record["network"], 56);
+        assert_eq!(record["epoch"], epoch);
+        // Id of the relayer key
+        assert_eq!(record["actor"], 1);
+        assert_eq!(
+            record["request_hash"],
+            hex::encode(Sha256::digest(new_epoch_request(10 + epoch as u64)))
+        );
+        assert!(record["timestamp"].is_string());
+    }
+}
+
+#[tokio::test]
+async fn ready_once_every_network_has_an_epoch() {
+    let app = test_router().await;
+    let (status, body) = call(&app, Request::get("/healthz").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+
+    let (status, body) = call(&app, Request::get("/readyz").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["unhealthy_networks"], json!([56]));
+
+    call(
+        &app,
+        rpc_with_api_key(
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_newEpoch","params":[56]}"#,
+            API_KEY,
+        ),
+    )
+    .await;
+    let (status, body) = call(&app, Request::get("/readyz").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+}
+
+// Value of a counter in the Prometheus text format, zero if it wasn't recorded yet
+async fn scrape(app: &Router, series: &str) -> u64 {
+    let response = app
+        .clone()
+        .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    String::from_utf8(body.to_vec())
+        .unwrap()
+        .lines()
+        .find_map(|line| line.strip_prefix(series))
+        .map(|value| value.trim().parse().unwrap())
+        .unwrap_or(0)
+}
+
+#[tokio::test]
+async fn requests_are_counted() {
+    let app = test_router().await;
+    let series = r#"orand_requests_total{method="orand_getPublicKey"}"#;
+    let before = scrape(&app, series).await;
+    call(
+        &app,
+        rpc(r#"{"jsonrpc":"2.0","id":1,"method":"orand_getPublicKey","params":[56]}"#),
+    )
+    .await;
+    // Recorder is global, other tests may run at the same time
+    assert!(scrape(&app, series).await > before);
+}
+
+#[tokio::test]
+async fn new_epoch_needs_api_key() {
+    let app = test_router().await;
+    let new_epoch = r#"{"jsonrpc":"2.0","id":1,"method":"orand_newEpoch","params":[56]}"#;
+    let (_, response) = call(&app, rpc(new_epoch)).await;
+    assert_eq!(response["error"]["code"], UNAUTHORIZED);
+
+    let (_, response) = call(&app, rpc_with_api_key(new_epoch, "wrong-api-key")).await;
+    assert_eq!(response["error"]["code"], UNAUTHORIZED);
+
+    // Read methods stay open
+    let (_, response) = call(
+        &app,
+        rpc(r#"{"jsonrpc":"2.0","id":1,"method":"orand_getLatestEpoch","params":[56]}"#),
+    )
+    .await;
+    assert_eq!(response["error"]["code"], NOT_FOUND);
+
+    let (_, response) = call(&app, rpc_with_api_key(new_epoch, API_KEY)).await;
+    assert_eq!(response["result"]["epoch"], 0);
+}
+
+#[tokio::test]
+async fn caller_alpha_is_proven_once() {
+    let app = test_router().await;
+    let prove_alpha = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"orand_proveAlpha","params":[56,"0x{}"]}}"#,
+        "2a".repeat(32)
+    );
+    let (_, response) = call(&app, rpc(&prove_alpha)).await;
+    assert_eq!(response["error"]["code"], UNAUTHORIZED);
+
+    let (_, proven) = call(&app, rpc_with_api_key(&prove_alpha, API_KEY)).await;
+    assert_eq!(proven["result"]["alpha"], "2a".repeat(32));
+    assert_eq!(proven["result"]["caller_alpha"], true);
+    let (_, retried) = call(&app, rpc_with_api_key(&prove_alpha, API_KEY)).await;
+    assert_eq!(retried["result"], proven["result"]);
+
+    // It isn't an epoch of the network
+    let (_, latest) = call(
+        &app,
+        rpc(r#"{"jsonrpc":"2.0","id":1,"method":"orand_getLatestEpoch","params":[56]}"#),
+    )
+    .await;
+    assert_eq!(latest["error"]["code"], NOT_FOUND);
+}
+
+#[tokio::test]
+async fn caller_alpha_is_not_replayed_within_the_window() {
+    let app = test_router_with(ServerConfig {
+        replay_window_secs: Some(3600),
+        ..ServerConfig::default()
+    })
+    .await;
+    let prove_alpha = |alpha: &str| {
+        format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"orand_proveAlpha","params":[56,"{}"]}}"#,
+            alpha.repeat(32)
+        )
+    };
+    let (_, proven) = call(&app, rpc_with_api_key(&prove_alpha("2a"), API_KEY)).await;
+    assert_eq!(proven["result"]["caller_alpha"], true);
+    let (_, replayed) = call(&app, rpc_with_api_key(&prove_alpha("2a"), API_KEY)).await;
+    assert_eq!(replayed["error"]["code"], REPLAY);
+    assert_eq!(replayed["result"], Value::Null);
+
+    let (_, other) = call(&app, rpc_with_api_key(&prove_alpha("2b"), API_KEY)).await;
+    assert_eq!(other["result"]["alpha"], "2b".repeat(32));
+}
+
+#[tokio::test]
+async fn failed_caller_alpha_can_be_retried_within_the_window() {
+    let state = test_state(ServerConfig {
+        replay_window_secs: Some(3600),
+        ..ServerConfig::default()
+    })
+    .await;
+    let db = state.db();
+    let app = router(state);
+    let prove_alpha = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"orand_proveAlpha","params":[56,"{}"]}}"#,
+        "2a".repeat(32)
+    );
+    let execute = |sql: &'static str| {
+        let db = db.clone();
+        async move {
+            db.connection()
+                .execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
+                .await
+                .unwrap();
+        }
+    };
+    execute(
+        "CREATE TRIGGER fail_prove BEFORE INSERT ON randomness \
+         BEGIN SELECT RAISE(ABORT, 'injected failure'); END",
+    )
+    .await;
+    let (_, failed) = call(&app, rpc_with_api_key(&prove_alpha, API_KEY)).await;
+    assert_eq!(failed["error"]["code"], INTERNAL_ERROR);
+
+    execute("DROP TRIGGER fail_prove").await;
+    let (_, proven) = call(&app, rpc_with_api_key(&prove_alpha, API_KEY)).await;
+    assert_eq!(proven["result"]["alpha"], "2a".repeat(32));
+    let (_, replayed) = call(&app, rpc_with_api_key(&prove_alpha, API_KEY)).await;
+    assert_eq!(replayed["error"]["code"], REPLAY);
+}
+
+#[tokio::test]
+async fn simulated_prove_does_not_write() {
+    let state = test_state(ServerConfig::default()).await;
+    let db = state.db();
+    let app = router(state);
+    call(
+        &app,
+        rpc_with_api_key(
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_newEpoch","params":[56]}"#,
+            API_KEY,
+        ),
+    )
+    .await;
+    let row_count = || async {
+        let row = db
+            .connection()
+            .query_one(Statement::from_string(
+                DbBackend::Sqlite,
+                "SELECT COUNT(*) AS count FROM randomness".to_string(),
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+        row.try_get::<i64>("", "count").unwrap()
+    };
+    let before = row_count().await;
+    let simulate = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"orand_simulateProve","params":[56,"{}"]}}"#,
+        "2a".repeat(32)
+    );
+    let (_, response) = call(&app, rpc(&simulate)).await;
+    assert_eq!(response["error"]["code"], UNAUTHORIZED);
+    let (_, simulated) = call(&app, rpc_with_api_key(&simulate, API_KEY)).await;
+    assert!(simulated["result"]["y"].is_string());
+    // Randomness of an alpha doesn't depend on the nonce of its proof
+    let (_, again) = call(&app, rpc_with_api_key(&simulate, API_KEY)).await;
+    assert_eq!(again["result"]["y"], simulated["result"]["y"]);
+    assert_eq!(row_count().await, before);
+}
+
+#[tokio::test]
+async fn proof_is_encoded_as_cbor_if_it_is_accepted() {
+    let app = test_router().await;
+    let (_, epoch) = call(
+        &app,
+        rpc_with_api_key(
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_newEpoch","params":[56]}"#,
+            API_KEY,
+        ),
+    )
+    .await;
+    let (status, json_proof) = call(
+        &app,
+        Request::get("/proof/56/0").body(Body::empty()).unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        json_proof["y"],
+        format!("0x{}", epoch["result"]["y"].as_str().unwrap())
+    );
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::get("/proof/56/0")
+                .header(ACCEPT, "application/json;q=0.5, application/cbor")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[CONTENT_TYPE], CBOR);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let proof = ECVRFProof::from_cbor(&body).unwrap();
+    assert_eq!(serde_json::to_value(proof).unwrap(), json_proof);
+
+    let (status, _) = call(
+        &app,
+        Request::get("/proof/56/1").body(Body::empty()).unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn epochs_are_served_for_caches() {
+    let app = test_router().await;
+    let (_, genesis) = call(&app, rpc_with_api_key(&new_epoch_request(1), API_KEY)).await;
+    let (_, latest) = call(&app, rpc_with_api_key(&new_epoch_request(2), API_KEY)).await;
+    let get = |uri: &str, etag: Option<&HeaderValue>| {
+        let mut request = Request::get(uri);
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        app.clone().oneshot(request.body(Body::empty()).unwrap())
+    };
+
+    let response = get("/proof/56/0.json", None).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[CACHE_CONTROL],
+        "public, max-age=31536000, immutable"
+    );
+    let etag = response.headers()[ETAG].clone();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let epoch: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(epoch["epoch"], 0);
+    assert_eq!(
+        epoch["proof"]["y"],
+        format!("0x{}", genesis["result"]["y"].as_str().unwrap())
+    );
+    let response = get("/proof/56/0.json", Some(&etag)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    // The proof alone is another representation
+    let response = get("/proof/56/0", Some(&etag)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_ne!(response.headers()[ETAG], etag);
+
+    let response = get("/proof/56/latest.json", None).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[CACHE_CONTROL], "public, max-age=5");
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let epoch: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(epoch["epoch"], 1);
+    let (_, proof) = call(
+        &app,
+        Request::get("/proof/56/latest")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(
+        proof["y"],
+        format!("0x{}", latest["result"]["y"].as_str().unwrap())
+    );
+
+    for (uri, status) in [
+        ("/proof/56/2.json", StatusCode::NOT_FOUND),
+        ("/proof/97/latest.json", StatusCode::NOT_FOUND),
+        ("/proof/56/first.json", StatusCode::BAD_REQUEST),
+    ] {
+        assert_eq!(get(uri, None).await.unwrap().status(), status, "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn epochs_are_served_as_drand_beacons() {
+    let app = test_router().await;
+    call(&app, rpc_with_api_key(&new_epoch_request(1), API_KEY)).await;
+    let (_, latest) = call(&app, rpc_with_api_key(&new_epoch_request(2), API_KEY)).await;
+    let beacon = |uri: &str| call(&app, Request::get(uri).body(Body::empty()).unwrap());
+    let (_, proof) = beacon("/proof/56/0").await;
+    let genesis = ECVRFProof::from_orand_json(&proof).unwrap();
+
+    let (status, first) = beacon("/public/0").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(first["round"], 0);
+    assert_eq!(first["signature"], hex::encode(genesis.to_bytes()));
+    assert!(first["previous_signature"].is_null());
+
+    let (status, second) = beacon("/public/latest").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(second["round"], 1);
+    assert_eq!(second["randomness"], latest["result"]["y"]);
+    assert_eq!(second["previous_signature"], first["signature"]);
+    let signature = hex::decode(second["signature"].as_str().unwrap()).unwrap();
+    assert_eq!(signature.len(), PROOF_SIZE);
+    assert_eq!(beacon("/public/1").await.1, second);
+
+    for (uri, status) in [
+        ("/public/2", StatusCode::NOT_FOUND),
+        ("/public/first", StatusCode::BAD_REQUEST),
+    ] {
+        assert_eq!(beacon(uri).await.0, status, "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn signed_responses_are_verified_with_the_oracle_address() {
+    let app = test_router_with(ServerConfig {
+        signed_responses: true,
+        ..ServerConfig::default()
+    })
+    .await;
+    let envelope = |value: Value| serde_json::from_value::<SignedResponse>(value).unwrap();
+    let (_, response) = call(
+        &app,
+        rpc(r#"{"jsonrpc":"2.0","id":1,"method":"orand_getPublicKey","params":[56]}"#),
+    )
+    .await;
+    let signed = envelope(response);
+    let public_key: Value = serde_json::from_str(&signed.payload).unwrap();
+    let address: [u8; 20] = hex::decode(
+        public_key["result"]["address"]
+            .as_str()
+            .unwrap()
+            .trim_start_matches("0x"),
+    )
+    .unwrap()
+    .try_into()
+    .unwrap();
+    assert!(signed.verify(&address).unwrap());
+
+    let new_epoch = r#"{"jsonrpc":"2.0","id":1,"method":"orand_newEpoch","params":[56]}"#;
+    let (_, response) = call(&app, rpc_with_api_key(new_epoch, API_KEY)).await;
+    assert!(envelope(response).verify(&address).unwrap());
+    let (_, response) = call(
+        &app,
+        Request::get("/proof/56/0.json")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    let signed = envelope(response);
+    assert!(signed.verify(&address).unwrap());
+    let epoch: Value = serde_json::from_str(&signed.payload).unwrap();
+    assert_eq!(epoch["epoch"], 0);
+
+    let tampered = SignedResponse {
+        payload: signed.payload.replace("\"epoch\":0", "\"epoch\":1"),
+        ..signed.clone()
+    };
+    assert_ne!(tampered.payload, signed.payload);
+    assert!(!tampered.verify(&address).unwrap());
+    assert!(!signed.verify(&[0u8; 20]).unwrap());
+}
+
+#[tokio::test]
+async fn epoch_range_is_paged_with_a_cursor() {
+    let app = test_router().await;
+    for id in 0..3 {
+        let new_epoch = format!(
+            r#"{{"jsonrpc":"2.0","id":{},"method":"orand_newEpoch","params":[56]}}"#,
+            id
+        );
+        call(&app, rpc_with_api_key(&new_epoch, API_KEY)).await;
+    }
+    let page = |cursor: Value| {
+        rpc(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "orand_getEpochRange",
+            "params": {"network": 56, "from_epoch": 0, "cursor": cursor, "limit": 2},
+        })
+        .to_string())
+    };
+    let (_, first) = call(&app, page(Value::Null)).await;
+    let epochs = |response: &Value| {
+        response["result"]["epochs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|epoch| epoch["epoch"].as_i64().unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(epochs(&first), vec![0, 1]);
+    let (_, last) = call(&app, page(first["result"]["next_cursor"].clone())).await;
+    assert_eq!(epochs(&last), vec![2]);
+    assert_eq!(last["result"]["next_cursor"], Value::Null);
+
+    let (_, response) = call(&app, page(json!("bogus"))).await;
+    assert_eq!(response["error"]["code"], INVALID_PARAMS);
+}
+
+// Status, content type and body of GET uri
+async fn export(app: &Router, uri: &str) -> (StatusCode, Option<HeaderValue>, Bytes) {
+    let response = app
+        .clone()
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let content_type = response.headers().get(CONTENT_TYPE).cloned();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, content_type, body)
+}
+
+#[tokio::test]
+async fn history_of_a_network_is_exported() {
+    let state = test_state(ServerConfig::default()).await;
+    let db = state.db();
+    let app = router(state);
+    for _ in 0..300 {
+        new_epoch(&db, "chiro", NetworkId(56), None, &Trigger::default())
+            .await
+            .unwrap();
+    }
+    new_epoch(&db, "chiro", NetworkId(97), None, &Trigger::default())
+        .await
+        .unwrap();
+    let (status, content_type, body) = export(&app, "/export/56").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type.unwrap(), "application/x-ndjson");
+    let lines: Vec<Value> = std::str::from_utf8(&body)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 300);
+    for (epoch, line) in lines.iter().enumerate() {
+        assert_eq!(line["network"], 56);
+        assert_eq!(line["epoch"], epoch);
+    }
+
+    let (status, _, body) = export(&app, "/export/56?format=binary").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.len(), 300 * PROOF_SIZE);
+    let last = ECVRFProof::from_bytes(&body[299 * PROOF_SIZE..]).unwrap();
+    assert_eq!(serde_json::to_value(last).unwrap(), lines[299]["proof"]);
+
+    let (status, _, _) = export(&app, "/export/1").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _, _) = export(&app, "/export/56?format=xml").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn binary_export_fails_on_an_epoch_without_a_proof() {
+    let state = test_state(ServerConfig::default()).await;
+    let db = state.db();
+    let app = router(state);
+    for _ in 0..3 {
+        new_epoch(&db, "chiro", NetworkId(56), None, &Trigger::default())
+            .await
+            .unwrap();
+    }
+    db.connection()
+        .execute(Statement::from_string(
+            DbBackend::Sqlite,
+            "UPDATE randomness SET proof = NULL WHERE epoch = 1".to_string(),
+        ))
+        .await
+        .unwrap();
+
+    // The body ends with an error instead of leaving the epoch out
+    let response = app
+        .clone()
+        .oneshot(
+            Request::get("/export/56?format=binary")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(to_bytes(response.into_body(), usize::MAX).await.is_err());
+
+    // JSON has a null proof for it
+    let (_, _, body) = export(&app, "/export/56").await;
+    assert_eq!(std::str::from_utf8(&body).unwrap().lines().count(), 3);
+}
+
+#[tokio::test]
+async fn epoch_is_found_by_its_y() {
+    let app = test_router().await;
+    let (_, created) = call(
+        &app,
+        rpc_with_api_key(
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_newEpoch","params":[56]}"#,
+            API_KEY,
+        ),
+    )
+    .await;
+    let y = created["result"]["y"].as_str().unwrap();
+    let find = |network: i64, y: &str| {
+        rpc(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "orand_findByValue",
+            "params": [network, y],
+        })
+        .to_string())
+    };
+    let (_, found) = call(&app, find(56, &format!("0x{}", y.to_uppercase()))).await;
+    assert_eq!(found["result"]["epochs"], json!([created["result"]]));
+    assert_eq!(found["result"]["anomaly"], false);
+
+    let (_, response) = call(&app, find(97, y)).await;
+    assert_eq!(response["error"]["code"], NOT_FOUND);
+    let (_, response) = call(&app, find(56, "0x1234")).await;
+    assert_eq!(response["error"]["code"], INVALID_PARAMS);
+}
+
+#[tokio::test]
+async fn epochs_are_found_by_time_in_utc() {
+    let state = test_state(ServerConfig::default()).await;
+    let db = state.db();
+    let app = router(state);
+    for epoch in 0..3 {
+        let new_epoch = format!(
+            r#"{{"jsonrpc":"2.0","id":{},"method":"orand_newEpoch","params":[56]}}"#,
+            epoch
+        );
+        call(&app, rpc_with_api_key(&new_epoch, API_KEY)).await;
+        db.connection()
+            .execute(Statement::from_string(
+                DbBackend::Sqlite,
+                format!(
+                    "UPDATE randomness SET created_date = '2023-01-0{} 00:00:00' WHERE epoch = {}",
+                    epoch + 1,
+                    epoch
+                ),
+            ))
+            .await
+            .unwrap();
+    }
+    let by_time = |from: &str, to: &str| {
+        rpc(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "orand_getEpochsByTime",
+            "params": [56, from, to],
+        })
+        .to_string())
+    };
+    // Both bounds are 2023-01-02 00:00:00 UTC
+    let (_, response) = call(
+        &app,
+        by_time("2023-01-02T07:00:00+07:00", "2023-01-01T19:00:00-05:00"),
+    )
+    .await;
+    assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    let (_, response) = call(
+        &app,
+        by_time("2023-01-01T19:00:00-05:00", "2023-01-03T00:00:00Z"),
+    )
+    .await;
+    let epochs: Vec<_> = response["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|epoch| epoch["epoch"].as_i64().unwrap())
+        .collect();
+    assert_eq!(epochs, vec![1]);
+}
+
+#[tokio::test]
+async fn corrupted_epoch_is_not_served_in_strict_mode() {
+    let state = test_state(ServerConfig {
+        strict_integrity: true,
+        ..ServerConfig::default()
+    })
+    .await;
+    let db = state.db();
+    let app = router(state);
+    let new_epoch = r#"{"jsonrpc":"2.0","id":1,"method":"orand_newEpoch","params":[56]}"#;
+    call(&app, rpc_with_api_key(new_epoch, API_KEY)).await;
+    let get_epoch = r#"{"jsonrpc":"2.0","id":1,"method":"orand_getPublicEpoch","params":[56,0]}"#;
+    let (_, response) = call(&app, rpc(get_epoch)).await;
+    assert_eq!(response["result"]["epoch"], 0);
+
+    db.connection()
+        .execute(Statement::from_string(
+            DbBackend::Sqlite,
+            format!("UPDATE randomness SET s = '{}'", "01".repeat(32)),
+        ))
+        .await
+        .unwrap();
+    let (_, response) = call(&app, rpc(get_epoch)).await;
+    assert_eq!(response["error"]["code"], INTERNAL_ERROR);
+    let (status, _) = call(
+        &app,
+        Request::get("/proof/56/0").body(Body::empty()).unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn corrupted_epoch_is_healed_by_the_operator() {
+    let state = test_state(ServerConfig {
+        admin_token: Some("admin".to_string()),
+        strict_integrity: true,
+        ..ServerConfig::default()
+    })
+    .await;
+    let db = state.db();
+    let app = router(state);
+    let new_epoch = r#"{"jsonrpc":"2.0","id":1,"method":"orand_newEpoch","params":[56]}"#;
+    let (_, created) = call(&app, rpc_with_api_key(new_epoch, API_KEY)).await;
+    let heal = r#"{"jsonrpc":"2.0","id":1,"method":"orand_healEpoch","params":[56,0]}"#;
+    let admin = || {
+        Request::post("/")
+            .header(ADMIN_TOKEN_HEADER, "admin")
+            .body(Body::from(heal))
+            .unwrap()
+    };
+    let (_, response) = call(&app, rpc_with_api_key(heal, API_KEY)).await;
+    assert_eq!(response["error"]["code"], UNAUTHORIZED);
+    let (_, response) = call(&app, admin()).await;
+    assert_eq!(response["result"]["healed"], false);
+
+    db.connection()
+        .execute(Statement::from_string(
+            DbBackend::Sqlite,
+            format!("UPDATE randomness SET s = '{}'", "01".repeat(32)),
+        ))
+        .await
+        .unwrap();
+    let (_, response) = call(&app, admin()).await;
+    assert_eq!(response["result"]["healed"], true);
+    assert_eq!(response["result"]["epoch"]["y"], created["result"]["y"]);
+    let get_epoch = r#"{"jsonrpc":"2.0","id":1,"method":"orand_getPublicEpoch","params":[56,0]}"#;
+    let (_, response) = call(&app, rpc(get_epoch)).await;
+    assert_eq!(response["result"]["epoch"], 0);
+
+    let missing = r#"{"jsonrpc":"2.0","id":1,"method":"orand_healEpoch","params":[56,5]}"#;
+    let (_, response) = call(
+        &app,
+        Request::post("/")
+            .header(ADMIN_TOKEN_HEADER, "admin")
+            .body(Body::from(missing))
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(response["error"]["code"], NOT_FOUND);
+}
+
+// Name and fields of a span
+type RecordedSpan = (String, Vec<(String, String)>);
+
+// Every span that was created
+#[derive(Clone, Default)]
+struct SpanRecorder(Arc<Mutex<Vec<RecordedSpan>>>);
+
+struct FieldRecorder(Vec<(String, String)>);
+
+impl Visit for FieldRecorder {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push((field.name().to_string(), value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .push((field.name().to_string(), format!("{:?}", value)));
+    }
+}
+
+impl<S: Subscriber> Layer<S> for SpanRecorder {
+    fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+        let mut fields = FieldRecorder(Vec::new());
+        attrs.record(&mut fields);
+        self.0
+            .lock()
+            .unwrap()
+            .push((attrs.metadata().name().to_string(), fields.0));
+    }
+}
+
+#[tokio::test]
+async fn requests_are_traced_without_secrets() {
+    let recorder = SpanRecorder::default();
+    let _guard = tracing::subscriber::set_default(Registry::default().with(recorder.clone()));
+    let app = test_router().await;
+    call(
+        &app,
+        rpc_with_api_key(
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_newEpoch","params":[56]}"#,
+            API_KEY,
+        ),
+    )
+    .await;
+
+    let spans = recorder.0.lock().unwrap().clone();
+    let field = |name: &str, field: &str| {
+        spans
+            .iter()
+            .find(|(span, _)| span == name)
+            .and_then(|(_, fields)| fields.iter().find(|(f, _)| f == field))
+            .map(|(_, value)| value.clone())
+    };
+    assert_eq!(
+        field("json_rpc", "method").as_deref(),
+        Some("orand_newEpoch")
+    );
+    assert_eq!(field("json_rpc", "network").as_deref(), Some("56"));
+    assert_eq!(field("prove", "epoch").as_deref(), Some("0"));
+    assert!(spans.iter().any(|(span, _)| span == "insert_audited"));
+    // Neither the API key nor a secret key is recorded
+    for (_, fields) in spans.iter() {
+        for (name, value) in fields {
+            assert!(
+                !name.contains("secret") && !name.contains("key"),
+                "{}",
+                name
+            );
+            assert!(!value.contains(API_KEY));
+        }
+    }
+}
+
+#[tokio::test]
+async fn public_key_of_a_network_matches_its_address() {
+    let app = test_router().await;
+    let (_, response) = call(
+        &app,
+        rpc(r#"{"jsonrpc":"2.0","id":1,"method":"orand_getPublicKey","params":[56]}"#),
+    )
+    .await;
+    let result = &response["result"];
+    let hex_field = |name: &str| {
+        hex::decode(result[name].as_str().unwrap().strip_prefix("0x").unwrap()).unwrap()
+    };
+    let uncompressed = hex_field("public_key_uncompressed");
+    let compressed = hex_field("public_key_compressed");
+    assert_eq!((uncompressed.len(), uncompressed[0]), (65, 0x04));
+    assert_eq!(compressed.len(), 33);
+    assert_eq!(compressed[1..], uncompressed[1..33]);
+    assert_eq!(compressed[0], 0x02 | (uncompressed[64] & 1));
+
+    let mut hash = [0u8; 32];
+    let mut keccak = Keccak::v256();
+    keccak.update(&uncompressed[1..]);
+    keccak.finalize(&mut hash);
+    assert_eq!(hex_field("address"), hash[12..]);
+
+    let (_, response) = call(
+        &app,
+        rpc(r#"{"jsonrpc":"2.0","id":1,"method":"orand_getPublicKey","params":[1]}"#),
+    )
+    .await;
+    assert_eq!(response["error"]["code"], NOT_FOUND);
+}
+
+#[tokio::test]
+async fn proof_is_verified_with_the_keyring_of_its_network() {
+    let state = test_state(ServerConfig::default()).await;
+    let keypair = generate_keypair();
+    let other = state
+        .db()
+        .table_keyring()
+        .await
+        .store_keyring(
+            json!({
+                "username": "other",
+                "hmac_secret": "00",
+                "public_key": hex::encode(keypair.public_key.serialize()),
+                "address": hex::encode(get_address(keypair.public_key)),
+            }),
+            &keypair.secret_key,
+        )
+        .await
+        .unwrap();
+    state
+        .db()
+        .connection()
+        .execute(Statement::from_string(
+            DbBackend::Sqlite,
+            format!(
+                "UPDATE network SET keyring_id = {} WHERE chain_id = 97",
+                other.id
+            ),
+        ))
+        .await
+        .unwrap();
+    let app = router(state);
+    let alpha = Scalar::from_int(42);
+    let proof = ECVRF::new(keypair.secret_key).prove(&alpha).unwrap();
+    let verify = |network: i64| {
+        rpc(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "orand_verifyProof",
+            "params": [network, hex::encode(alpha.b32()), proof],
+        })
+        .to_string())
+    };
+
+    let (_, response) = call(&app, verify(97)).await;
+    assert_eq!(response["result"]["valid"], true);
+    // Network 56 is signed by another keyring
+    let (_, response) = call(&app, verify(56)).await;
+    assert_ne!(response["result"]["valid"], true);
+}
+
+fn rpc_from(body: &str, client: [u8; 4]) -> Request<Body> {
+    Request::post("/")
+        .extension(ConnectInfo(SocketAddr::from((client, 4000))))
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn clients_over_the_limit_are_throttled() {
+    let app = test_router_with(ServerConfig {
+        rate_limit: Some(RateLimitConfig {
+            requests_per_second: 0.001,
+            burst: 3,
+        }),
+        ..ServerConfig::default()
+    })
+    .await;
+    let request = r#"{"jsonrpc":"2.0","id":9,"method":"orand_getPublicEpoch","params":[56, 0]}"#;
+    for _ in 0..3 {
+        let (status, _) = call(&app, rpc_from(request, [10, 0, 0, 1])).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let (status, response) = call(&app, rpc_from(request, [10, 0, 0, 1])).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response["error"]["code"], RATE_LIMITED);
+    assert_eq!(response["id"], 9);
+
+    // Other clients have their own bucket
+    let (status, _) = call(&app, rpc_from(request, [10, 0, 0, 2])).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn batches_are_rejected_by_the_parser_rather_than_the_limiter() {
+    let app = test_router_with(ServerConfig {
+        rate_limit: Some(RateLimitConfig {
+            requests_per_second: 0.001,
+            burst: 2,
+        }),
+        ..ServerConfig::default()
+    })
+    .await;
+    // Three requests in one body are charged as one
+    let request = r#"{"jsonrpc":"2.0","id":9,"method":"orand_getPublicEpoch","params":[56, 0]}"#;
+    let batch = format!("[{},{},{}]", request, request, request);
+    let (status, response) = call(&app, rpc_from(&batch, [10, 0, 0, 3])).await;
+    assert_ne!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response["error"]["code"], INVALID_REQUEST);
+    let (status, _) = call(&app, rpc_from(request, [10, 0, 0, 3])).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn subscribers_receive_new_epochs() {
+    let state = test_state(ServerConfig::default()).await;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = tokio::spawn(serve(listener, state.clone(), std::future::pending()));
+
+    let (mut socket, _) = connect_async(format!("ws://{}/ws", address)).await.unwrap();
+    socket
+        .send(Message::Text(
+            r#"{"jsonrpc":"2.0","id":1,"method":"orand_subscribe","params":["epochs", 56]}"#.into(),
+        ))
+        .await
+        .unwrap();
+    let subscribed = read_json(&mut socket).await;
+    assert_eq!(subscribed["id"], 1);
+    let subscription = subscribed["result"].clone();
+
+    let (_, created) = call(
+        &router(state.clone()),
+        rpc_with_api_key(
+            r#"{"jsonrpc":"2.0","id":2,"method":"orand_newEpoch","params":[56]}"#,
+            API_KEY,
+        ),
+    )
+    .await;
+    let pushed = read_json(&mut socket).await;
+    assert_eq!(pushed["method"], "orand_subscription");
+    assert_eq!(pushed["params"]["subscription"], subscription);
+    assert_eq!(pushed["params"]["result"], created["result"]);
+    assert_eq!(
+        pushed["params"]["result"]["proof"],
+        created["result"]["proof"]
+    );
+
+    socket
+        .send(Message::Text(
+            format!(
+                r#"{{"jsonrpc":"2.0","id":3,"method":"orand_unsubscribe","params":[{}]}}"#,
+                subscription
+            )
+            .into(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(read_json(&mut socket).await["result"], true);
+    socket.close(None).await.unwrap();
+    server.abort();
+}
+
+async fn read_json<S>(socket: &mut S) -> Value
+where
+    S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    match socket.next().await.unwrap().unwrap() {
+        Message::Text(text) => serde_json::from_str(text.as_str()).unwrap(),
+        message => panic!("Unexpected message {:?}", message),
+    }
+}